@@ -1,10 +1,11 @@
 use std::path::PathBuf;
 
-use ohlcv::Database;
-use tracing::instrument;
+use inquire::Confirm;
+use ohlcv::{Coin, Database};
+use tracing::{info, instrument};
 
 use crate::{
-    config::{CoinConfig, Config},
+    config::{matches_name, CoinConfig, Config},
     Error,
 };
 
@@ -16,33 +17,106 @@ use super::root_credentials;
 ///
 /// * `all` - Whether to drop all tables. If false, only tables for the
 ///   configured coins will be dropped.
+/// * `yes` - Whether to skip the confirmation prompt, e.g. for scripted use.
+/// * `names` - Coins to drop given as symbol, optionally followed by the
+///   currency, e.g. `BTC` or `BTC/USD`. If not empty, only the tables of these
+///   coins are dropped. A name is looked up in the configured coins first and
+///   then in the coins stored in the database, so tables of coins removed from
+///   the configuration can be dropped as well.
 /// * `config` - Optional path to the configuration file. If not provided, the
+///   file named by the environment variable `OHLCV_CONFIG` or else the
 ///   default configuration file will be used. This file is expected to be in
 ///   TOML format. The default file is `ohlcv.toml` and is expected to be in the
 ///   current working directory or in `/etc/ohlcv`.
 ///
 /// # Errors
 ///
-/// Returns an error if the tables cannot be dropped or if the configuration
-/// file cannot be loaded.
+/// Returns an error if the tables cannot be dropped, if a name matches no
+/// coin or more than one coin, if the configuration file cannot be loaded or
+/// if the confirmation prompt fails.
 #[instrument]
-pub async fn drop(all: bool, config: Option<&PathBuf>) -> Result<(), Error> {
+pub async fn drop(
+    all: bool,
+    yes: bool,
+    names: &[String],
+    config: Option<&PathBuf>,
+) -> Result<(), Error> {
     let mut config = Config::load(config)?;
-    let creds = root_credentials(&config.database)?;
-
-    if all {
-        config.database.drop_schema(creds, None).await?;
+    let coins = if all {
+        None
+    } else if !names.is_empty() {
+        Some(find_coins(&mut config, names).await?)
     } else {
-        let coins = config
-            .coins
-            .iter()
-            .map(CoinConfig::as_coin)
-            .collect::<Vec<_>>();
-
-        config
-            .database
-            .drop_schema(creds, Some(coins.as_slice()))
-            .await?;
+        Some(
+            config
+                .coins
+                .iter()
+                .map(CoinConfig::as_coin)
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    };
+
+    if !yes {
+        let tables = match &coins {
+            Some(coins) => coins.len(),
+            None => config.database.list_coins().await?.len(),
+        };
+
+        if !confirm(tables)? {
+            info!("Aborted dropping the tables");
+            return Ok(());
+        }
     }
+
+    let creds = root_credentials(&config.database)?;
+
+    config.database.drop_schema(creds, coins.as_deref()).await?;
     Ok(())
 }
+
+/// Find the coins with the given names.
+///
+/// The configured coins take precedence over the coins stored in the
+/// database, which are only listed if a name is not configured.
+async fn find_coins(config: &mut Config, names: &[String]) -> Result<Vec<Coin>, Error> {
+    let mut stored = None;
+    let mut coins = Vec::<Coin>::with_capacity(names.len());
+
+    for name in names {
+        let coin = match config.find_coin(name) {
+            Err(Error::CoinUnknown(_)) => {
+                if stored.is_none() {
+                    stored = Some(config.database.list_coins().await?);
+                }
+
+                let mut matches = stored
+                    .iter()
+                    .flatten()
+                    .filter(|coin| matches_name(coin.symbol(), coin.currency(), name));
+
+                match (matches.next(), matches.next()) {
+                    (Some(coin), None) => coin.clone(),
+                    (Some(_), Some(_)) => return Err(Error::CoinAmbiguous(name.clone())),
+                    (None, _) => return Err(Error::CoinUnknown(name.clone())),
+                }
+            }
+            result => result?,
+        };
+
+        if !coins.contains(&coin) {
+            coins.push(coin);
+        }
+    }
+    Ok(coins)
+}
+
+/// Ask the user to confirm dropping the given number of tables.
+fn confirm(tables: usize) -> Result<bool, Error> {
+    Confirm::new(&format!(
+        "Drop {tables} table(s) including all their candles?"
+    ))
+    .with_default(false)
+    .with_help_message("This cannot be undone. Use `--yes` to skip this prompt.")
+    .prompt()
+    .map_err(|err| Error::Confirm(Box::new(err)))
+}
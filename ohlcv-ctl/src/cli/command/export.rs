@@ -0,0 +1,214 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    ops::Bound,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "gzip")]
+use flate2::write::GzEncoder;
+use futures_util::TryStreamExt;
+use ohlcv::{Coin, Database, DbType, Timeframe};
+use time::OffsetDateTime;
+use tracing::{info, instrument};
+
+use crate::{
+    config::{CoinConfig, Config},
+    format::{Compression, Format},
+    Error,
+};
+
+/// Options of the export command.
+#[derive(Clone, Debug, Default)]
+pub struct ExportOptions {
+    /// Coin to export given as symbol, optionally followed by the currency,
+    /// e.g. `BTC` or `BTC/USD`. Not used by [`export_all`].
+    pub coin: String,
+    /// Timeframe of the candles to export. If not set, [`export`] exports the
+    /// candles of five minutes and [`export_all`] the candles of all
+    /// timeframes.
+    pub timeframe: Option<Timeframe>,
+    /// Format of the exported candles.
+    pub format: Format,
+    /// Compression of the written file.
+    pub compression: Compression,
+    /// Start of the candles to export, inclusive. The candle containing the
+    /// time is exported. If not set, the candles are exported from the first
+    /// one on.
+    pub from: Option<OffsetDateTime>,
+    /// End of the candles to export, exclusive. If not set, the candles are
+    /// exported up to the last completed one.
+    pub to: Option<OffsetDateTime>,
+    /// File to write the candles to. If not set, the candles are written to
+    /// stdout. Not used by [`export_all`].
+    pub output: Option<PathBuf>,
+}
+
+impl ExportOptions {
+    /// Check that the start of the range is not after its end.
+    fn check_range(&self) -> Result<(), Error> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(Error::TimestampOrder(from, to));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Export the candles of a coin.
+///
+/// The stored candles of the timeframe within the range of the options are
+/// written in the format of the options, sorted by timestamp. The range is
+/// resolved by [`Timeframe::range`]. The candles are streamed from the
+/// database, so the memory usage does not grow with the number of candles.
+///
+/// # Arguments
+///
+/// * `options` - The options of the command.
+/// * `config` - Optional path to the configuration file. If not provided, the
+///   file named by the environment variable `OHLCV_CONFIG` or else the
+///   default configuration file will be used. This file is expected to be in
+///   TOML format. The default file is `ohlcv.toml` and is expected to be in
+///   the current working directory or in `/etc/ohlcv`.
+///
+/// # Errors
+///
+/// Returns an error if the start of the range is after its end, if the coin is
+/// not configured, if the candles cannot be queried or written, or if the
+/// configuration file cannot be loaded.
+#[instrument]
+pub async fn export(options: &ExportOptions, config: Option<&PathBuf>) -> Result<(), Error> {
+    options.check_range()?;
+
+    let mut config = Config::load(config)?;
+    let coin = config.find_coin(&options.coin)?;
+    let output: Box<dyn Write + Send> = match &options.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    let timeframe = options.timeframe.unwrap_or_default();
+    let count = write_candles(&mut config.database, &coin, &[timeframe], options, output).await?;
+
+    info!("Exported {count} candles of {coin:#}");
+    Ok(())
+}
+
+/// Export the candles of all configured coins.
+///
+/// Every coin is written to its own file in `output_dir`, named by the table
+/// of the coin and the extensions of the format and the compression, e.g.
+/// `candles_btc_usd.csv` or `candles_btc_usd.csv.gz`.
+/// The directory is created if it does not exist and existing files are
+/// replaced. Without a timeframe in the options, a file holds the candles of
+/// all timeframes, one timeframe after the other. Otherwise the candles are
+/// selected like by [`export`].
+///
+/// # Arguments
+///
+/// * `options` - The options of the command.
+/// * `output_dir` - Directory to write the files to.
+/// * `config` - Optional path to the configuration file. If not provided, the
+///   file named by the environment variable `OHLCV_CONFIG` or else the
+///   default configuration file will be used. This file is expected to be in
+///   TOML format. The default file is `ohlcv.toml` and is expected to be in
+///   the current working directory or in `/etc/ohlcv`.
+///
+/// # Errors
+///
+/// Returns an error if the start of the range is after its end, if the
+/// directory or a file cannot be created, if the candles cannot be queried or
+/// written, or if the configuration file cannot be loaded.
+#[instrument]
+pub async fn export_all(
+    options: &ExportOptions,
+    output_dir: &Path,
+    config: Option<&PathBuf>,
+) -> Result<(), Error> {
+    options.check_range()?;
+
+    let mut config = Config::load(config)?;
+    let coins = config
+        .coins
+        .iter()
+        .map(CoinConfig::as_coin)
+        .collect::<Result<Vec<_>, _>>()?;
+    let timeframes = options
+        .timeframe
+        .as_ref()
+        .map_or(Timeframe::ALL.as_slice(), std::slice::from_ref);
+    let mut total = 0;
+
+    fs::create_dir_all(output_dir)?;
+    for coin in &coins {
+        let mut name = format!("{}.{}", coin.table_name(), options.format.extension());
+        if let Some(extension) = options.compression.extension() {
+            name = format!("{name}.{extension}");
+        }
+        let path = output_dir.join(name);
+        let output = File::create(&path)?;
+        let count = write_candles(&mut config.database, coin, timeframes, options, output).await?;
+
+        info!("Exported {count} candles of {coin:#} to {}", path.display());
+        total += count;
+    }
+
+    info!("Exported {total} candles of {} coins", coins.len());
+    Ok(())
+}
+
+/// Write the candles of a coin in the format and compression of the options.
+///
+/// Returns the number of written candles, see [`write_format`].
+async fn write_candles(
+    database: &mut DbType,
+    coin: &Coin,
+    timeframes: &[Timeframe],
+    options: &ExportOptions,
+    output: impl Write,
+) -> Result<usize, Error> {
+    match options.compression {
+        Compression::None => write_format(database, coin, timeframes, options, output).await,
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(output, flate2::Compression::default());
+            let count = write_format(database, coin, timeframes, options, &mut encoder).await?;
+
+            encoder.finish()?;
+            Ok(count)
+        }
+    }
+}
+
+/// Write the candles of a coin in the format of the options.
+///
+/// The candles of the timeframes are written one timeframe after the other,
+/// each sorted by timestamp and limited to the range of the options. Returns
+/// the number of written candles.
+async fn write_format(
+    database: &mut DbType,
+    coin: &Coin,
+    timeframes: &[Timeframe],
+    options: &ExportOptions,
+    output: impl Write,
+) -> Result<usize, Error> {
+    let mut writer = BufWriter::new(output);
+    let mut count = 0;
+
+    options.format.write_header(&mut writer)?;
+    for timeframe in timeframes {
+        let (start, end) = timeframe.range((
+            options.from.map_or(Bound::Unbounded, Bound::Included),
+            options.to.map_or(Bound::Unbounded, Bound::Excluded),
+        ));
+        let mut candles = database.stream_candles(coin, *timeframe, start, end);
+
+        while let Some(candle) = candles.try_next().await? {
+            options.format.write_candle(&mut writer, &candle)?;
+            count += 1;
+        }
+    }
+    writer.flush()?;
+
+    Ok(count)
+}
@@ -1,25 +1,610 @@
-use std::path::PathBuf;
+use std::{future::Future, io, iter, path::PathBuf, pin::pin};
 
-use tracing::instrument;
+use futures_util::{future::FusedFuture, stream, FutureExt, Stream, StreamExt};
+use ohlcv::{
+    exchange::Fetcher,
+    series::{gaps, merge_by_timestamp, validate_series},
+    Candle, Coin, Database, DbType, Exchange, Timeframe,
+};
+use time::{Date, Duration, OffsetDateTime};
+use tokio::signal;
+use tracing::{error, info, info_span, instrument, warn, Instrument};
 
-use crate::{config::Config, Error};
+use super::report::{CoinReport, ExchangeReport, FetchReport};
+use crate::{
+    config::{CoinConfig, Config},
+    Error,
+};
+
+/// Default number of coins downloaded at the same time.
+pub const DEFAULT_PARALLELISM: usize = 4;
+
+/// Options of the fetch command.
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct FetchOptions {
+    /// If set, the candles are not stored in the database.
+    pub dry_run: bool,
+    /// If set, days with stored candles are downloaded again and their stored
+    /// candles are replaced.
+    pub force: bool,
+    /// First day to download. Defaults to the previous day.
+    pub from: Option<Date>,
+    /// Last day to download. Defaults to the previous day.
+    pub to: Option<Date>,
+    /// If set, no progress is reported.
+    pub quiet: bool,
+    /// Maximum number of coins downloaded at the same time. Defaults to
+    /// [`DEFAULT_PARALLELISM`].
+    pub parallelism: usize,
+    /// If set, an exchange quoting a coin in another currency than configured
+    /// is an error instead of a warning.
+    pub strict: bool,
+    /// Coins to download given as symbol, optionally followed by the currency,
+    /// e.g. `BTC` or `BTC/USD`. If empty, all configured coins are downloaded.
+    pub coins: Vec<String>,
+    /// Exchanges to download from. If empty, all configured exchanges of the
+    /// coins are used.
+    pub exchanges: Vec<Exchange>,
+    /// File to write a summary of the run to as JSON, see [`FetchReport`].
+    pub report: Option<PathBuf>,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            force: false,
+            from: None,
+            to: None,
+            quiet: false,
+            parallelism: DEFAULT_PARALLELISM,
+            strict: false,
+            coins: Vec::new(),
+            exchanges: Vec::new(),
+            report: None,
+        }
+    }
+}
 
 /// Fetch data from the origin.
 ///
+/// The 5-minute candles of every day in the range of the options are downloaded
+/// for every configured coin from every exchange listed for the coin. If the
+/// options list coins, only these coins are downloaded. If they list exchanges,
+/// only these exchanges are requested and coins configured for none of them are
+/// not downloaded. Without a range only the previous day is downloaded. Coins
+/// with all candles of a day already stored are skipped for that day, unless
+/// the options force the download. The candles of an exchange are rejected if
+/// they violate the download-failure rules of [`validate_series`]; the coin
+/// then falls back to the other exchanges. The candles of the accepted
+/// exchanges are merged by timestamp and stored in the database.
+///
+/// If forced, the stored candles of the day are replaced by the merged candles,
+/// so candles corrected by an exchange or a bad merge of a previous run are
+/// replaced entirely. The deletion and the insertion are a single transaction,
+/// so a failed insertion keeps the stored candles. If no exchange provides
+/// valid candles, the stored candles are kept. Whether stored candles were
+/// replaced or the day was downloaded freshly is logged for every coin.
+///
+/// In a dry run the candles are downloaded, validated and merged, but not
+/// stored. Instead a summary is logged for every coin.
+///
+/// Up to `parallelism` coins of a day are downloaded at the same time. The
+/// exchanges of a coin are requested one after the other, so there are never
+/// more than `parallelism` downloads running. Every request still waits for
+/// the rate limit of its exchange. The candles of a coin are stored as soon as
+/// they are downloaded.
+///
+/// On Ctrl-C the pending downloads are cancelled, while the candles of a coin
+/// being stored are still written. Every coin is stored in a single
+/// transaction, so no day is left half-written. The number of processed and
+/// skipped coins is logged before returning.
+///
+/// Every day is processed within a `day` span. Unless the options are quiet,
+/// the number of processed coins and fetched candles is reported after every
+/// coin.
+///
+/// If the options name a report file, a [`FetchReport`] is written to it once
+/// the downloads are done, also if the run is interrupted or fails to store
+/// the candles. The file is replaced atomically, so it never holds a partial
+/// report.
+///
 /// # Arguments
 ///
+/// * `options` - The options of the command.
 /// * `config` - Optional path to the configuration file. If not provided, the
+///   file named by the environment variable `OHLCV_CONFIG` or else the
 ///   default configuration file will be used. This file is expected to be in
 ///   TOML format. The default file is `ohlcv.toml` and is expected to be in
 ///   the current working directory or in `/etc/ohlcv`.
 ///
 /// # Errors
 ///
-/// Returns an error if the data cannot be fetched or if the configuration file
-/// cannot be loaded.
+/// Returns an error if the range is empty or not complete yet, if a listed coin
+/// is not configured or ambiguous, if a listed exchange is not configured for
+/// any of the coins, if the candles cannot be merged or stored, or if the
+/// configuration file cannot be loaded. If the options are strict, an exchange
+/// quoting a coin in another currency than configured is an error, see
+/// [`Config::check_currencies`]. Failing to write the report is an error,
+/// unless the run failed already.
 #[instrument]
-pub async fn fetch(config: Option<&PathBuf>) -> Result<(), Error> {
-    let _config = Config::load(config)?;
+pub async fn fetch(options: &FetchOptions, config: Option<&PathBuf>) -> Result<(), Error> {
+    let yesterday = OffsetDateTime::now_utc()
+        .date()
+        .saturating_sub(Duration::DAY);
+    let from = options.from.unwrap_or(yesterday);
+    let to = options.to.unwrap_or(yesterday);
+
+    if to > yesterday {
+        return Err(Error::DateFuture(to));
+    }
+    if from > to {
+        return Err(Error::DateOrder(from, to));
+    }
+
+    let mut config = Config::load(config)?;
+
+    config.select_coins(&options.coins)?;
+    config.select_exchanges(&options.exchanges)?;
+    if options.strict {
+        config.check_currencies()?;
+    }
+
+    let fetcher = config.fetcher()?;
+    let mut report = FetchReport::new(from, to, options.dry_run, options.force);
+    let result = fetch_days(
+        options,
+        &mut config,
+        &fetcher,
+        from,
+        to,
+        &mut report,
+        signal::ctrl_c(),
+    )
+    .await;
+
+    if let Some(path) = &options.report {
+        if let Err(err) = &result {
+            report.error = Some(err.to_string());
+        }
+        match report.write(path) {
+            Ok(()) => info!("Wrote the report to {}", path.display()),
+            Err(err) if result.is_err() => {
+                error!("Failed to write the report to {}: {err}", path.display());
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    result
+}
+
+/// Fetch the candles of the days from `from` to `to` for the coins of the
+/// configuration, see [`fetch`].
+///
+/// Every processed coin is added to the report. The fetch is interrupted once
+/// `interrupt` resolves, e.g. on Ctrl-C.
+async fn fetch_days(
+    options: &FetchOptions,
+    config: &mut Config,
+    fetcher: &Fetcher,
+    from: Date,
+    to: Date,
+    report: &mut FetchReport,
+    interrupt: impl Future<Output = io::Result<()>>,
+) -> Result<(), Error> {
+    let days = iter::successors(Some(from), |date| date.next_day()).take_while(|date| *date <= to);
+    let total = days.clone().count() * config.coins.len();
+    let mut done = 0;
+    let mut skipped = 0;
+    let mut candles = 0;
+    let mut interrupt = pin!(interrupt.fuse());
+
+    for date in days {
+        let span = info_span!("day", %date);
+        let mut pending = Vec::with_capacity(config.coins.len());
+        let progress = |done: usize, candles: usize| {
+            if !options.quiet {
+                span.in_scope(|| {
+                    info!(done, total, candles, "Processed {done} of {total} coins");
+                });
+            }
+        };
+
+        for coin_config in &config.coins {
+            let coin = coin_config.as_coin()?;
+
+            if !options.force
+                && config
+                    .database
+                    .has_candles(&coin, Timeframe::FiveMinutes, date)
+                    .instrument(span.clone())
+                    .await?
+            {
+                span.in_scope(|| {
+                    info!("Skipping {coin:#}, candles of {date} are already stored");
+                });
+                report.coins.push(CoinReport {
+                    skipped: true,
+                    ..CoinReport::new(&coin, date)
+                });
+                done += 1;
+                skipped += 1;
+                progress(done, candles);
+            } else {
+                pending.push((coin_config, coin));
+            }
+        }
+
+        let downloads = stream::iter(pending)
+            .map(|(coin_config, coin)| {
+                download_coin(fetcher, coin_config, coin, date).instrument(span.clone())
+            })
+            .buffer_unordered(options.parallelism.max(1));
+
+        store_downloads(
+            downloads,
+            &mut interrupt,
+            &mut config.database,
+            date,
+            options,
+            report,
+            |stored| {
+                candles += stored;
+                done += 1;
+                progress(done, candles);
+            },
+        )
+        .instrument(span.clone())
+        .await?;
+        if report.interrupted {
+            break;
+        }
+    }
+
+    if report.interrupted {
+        warn!(
+            done,
+            skipped,
+            total,
+            "Interrupted after processing {done} of {total} coins, \
+            {skipped} of them were already stored"
+        );
+    }
+    Ok(())
+}
+
+/// Store the downloads of the coins of a day as they finish.
+///
+/// The number of candles fetched for every stored coin is passed to `stored`.
+/// If `interrupt` resolves first, the report is marked as interrupted and the
+/// pending downloads are dropped. If it fails, the failure is logged and the
+/// downloads can no longer be interrupted.
+async fn store_downloads(
+    downloads: impl Stream<Item = (Coin, Vec<Vec<Candle>>, CoinReport)>,
+    interrupt: &mut (impl FusedFuture<Output = io::Result<()>> + Unpin),
+    database: &mut DbType,
+    date: Date,
+    options: &FetchOptions,
+    report: &mut FetchReport,
+    mut stored: impl FnMut(usize),
+) -> Result<(), Error> {
+    let mut downloads = pin!(downloads);
+
+    loop {
+        // Downloads already finished take precedence over the interrupt.
+        let download = tokio::select! {
+            biased;
+            download = downloads.next() => download,
+            result = &mut *interrupt => match result {
+                Ok(()) => {
+                    report.interrupted = true;
+                    break;
+                }
+                Err(err) => {
+                    warn!("Failed to listen for Ctrl-C, the fetch cannot be interrupted: {err}");
+                    continue;
+                }
+            },
+        };
+        let Some((coin, series, mut coin_report)) = download else {
+            break;
+        };
+        let candles = store_coin(database, &coin, date, series, options, &mut coin_report).await;
+
+        report.coins.push(coin_report);
+        stored(candles?);
+    }
+    Ok(())
+}
+
+/// Fetch the candles of a coin for a single day from all its exchanges.
+///
+/// Returns the coin, the valid series of candles of every exchange and the
+/// report of the coin listing the downloads of the exchanges. Exchanges
+/// failing to provide valid candles are logged and skipped.
+#[instrument(skip(fetcher, coin_config, coin), fields(coin = %coin))]
+async fn download_coin(
+    fetcher: &Fetcher,
+    coin_config: &CoinConfig,
+    coin: Coin,
+    date: Date,
+) -> (Coin, Vec<Vec<Candle>>, CoinReport) {
+    let mut series = Vec::with_capacity(coin_config.exchanges.len());
+    let mut report = CoinReport::new(&coin, date);
+
+    for (exchange, symbol) in &coin_config.exchanges {
+        let mut exchange_report = ExchangeReport {
+            exchange: *exchange,
+            symbol: symbol.clone(),
+            candles: 0,
+            gaps: 0,
+            missing: 0,
+            error: None,
+        };
+        let candles = fetcher
+            .fetch_candles(*exchange, symbol, date)
+            .await
+            .and_then(|candles| {
+                let gaps = gaps(&candles, Timeframe::FiveMinutes);
+
+                exchange_report.candles = candles.len();
+                exchange_report.gaps = gaps.len();
+                exchange_report.missing = gaps.iter().map(|(_, count)| count).sum();
+                validate_series(&candles, Timeframe::FiveMinutes).map(|()| candles)
+            });
+
+        match candles {
+            Ok(candles) => {
+                info!(
+                    "Fetched {} candles for {coin:#} from {exchange}",
+                    candles.len()
+                );
+                series.push(candles);
+            }
+            Err(err) => {
+                error!("Failed to fetch {coin:#} from {exchange}: {err}");
+                exchange_report.error = Some(err.to_string());
+            }
+        }
+        report.exchanges.push(exchange_report);
+    }
+    (coin, series, report)
+}
+
+/// Merge and store the downloaded candles of a coin.
+///
+/// If the options force the download, the stored candles of the day are
+/// deleted before the candles are inserted. Returns the number of candles
+/// fetched from all exchanges. The merged candles, their gaps and whether
+/// they were stored are recorded in the report of the coin.
+#[instrument(skip(database, coin, series, options, report), fields(coin = %coin))]
+async fn store_coin(
+    database: &mut DbType,
+    coin: &Coin,
+    date: Date,
+    series: Vec<Vec<Candle>>,
+    options: &FetchOptions,
+    report: &mut CoinReport,
+) -> Result<usize, Error> {
+    if series.is_empty() {
+        error!("No exchange provided valid candles for {coin:#}");
+        return Ok(0);
+    }
+
+    let downloaded = series.iter().map(Vec::len).sum::<usize>();
+    let sources = series.len();
+    let candles = merge_exchanges(series)?;
+    let gaps = gaps(&candles, Timeframe::FiveMinutes);
+    let missing = gaps.iter().map(|(_, count)| count).sum::<usize>();
+
+    report.candles = candles.len();
+    // A candle with fewer sources than exchanges is missing on at least one.
+    report.filled = candles
+        .iter()
+        .filter(|candle| candle.sources.get() < sources)
+        .count();
+    report.gaps = gaps.len();
+    report.missing = missing;
+
+    if options.dry_run {
+        let merged = candles.iter().filter(|candle| candle.was_merged()).count();
+
+        info!(
+            "Dry run for {coin:#}: {downloaded} candles fetched from {sources} exchanges, \
+            {} candles after merging ({merged} from multiple sources), \
+            {} gaps with {missing} missing candles",
+            candles.len(),
+            gaps.len()
+        );
+        return Ok(downloaded);
+    }
+
+    let (deleted, rows) = if options.force {
+        let start = date.midnight().assume_utc();
+        let range = start..start + Timeframe::OneDay.duration();
+
+        database
+            .replace_candles(coin, Some(Timeframe::FiveMinutes), range, &candles)
+            .await?
+    } else {
+        (0, database.insert_candles(coin, &candles).await?)
+    };
+
+    report.stored = true;
+
+    if deleted > 0 {
+        info!(
+            "Overwrote {deleted} stored candles for {coin:#} with {} candles ({rows} rows affected)",
+            candles.len()
+        );
+    } else {
+        info!(
+            "Stored {} candles for {coin:#} ({rows} rows affected)",
+            candles.len()
+        );
+    }
+    Ok(downloaded)
+}
+
+/// Merge the candles of several exchanges into a single series.
+///
+/// The candles are sorted by timestamp and the candles of every timestamp are
+/// merged into one candle. Candles present on only one exchange are passed
+/// through unchanged. The resulting series is sorted by timestamp.
+fn merge_exchanges(series: Vec<Vec<Candle>>) -> Result<Vec<Candle>, ohlcv::Error> {
+    let mut candles = series.into_iter().flatten().collect::<Vec<_>>();
+
+    candles.sort_by_key(|candle| candle.timestamp);
+    merge_by_timestamp(candles).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use futures_util::future;
+    use ohlcv::Currency;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    fn candle(timestamp: OffsetDateTime, price: Decimal, volume: Decimal) -> Candle {
+        Candle::new(
+            timestamp,
+            Timeframe::FiveMinutes,
+            price,
+            price,
+            price,
+            price,
+            volume,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn merge() {
+        let first = datetime!(2024-01-01 00:00 UTC);
+        let second = datetime!(2024-01-01 00:05 UTC);
+        let series = vec![
+            vec![
+                candle(first, dec!(100), dec!(1)),
+                candle(second, dec!(102), dec!(1)),
+            ],
+            vec![candle(first, dec!(110), dec!(3))],
+        ];
+
+        let candles = merge_exchanges(series).unwrap();
+
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].timestamp, first);
+        assert_eq!(candles[0].sources, NonZero::new(2).unwrap());
+        assert_eq!(candles[0].open, dec!(107.5));
+        assert_eq!(candles[0].volume, dec!(4));
+        assert_eq!(candles[1].timestamp, second);
+        assert_eq!(candles[1].open, dec!(102));
+        assert_eq!(candles[1].sources, NonZero::<usize>::MIN);
+    }
+
+    /// Temporary SQLite database with the table of the coin.
+    #[cfg(feature = "sqlite")]
+    async fn temp_database(name: &str, coin: &Coin) -> (DbType, PathBuf) {
+        let path = std::env::temp_dir().join(format!("ohlcv-ctl-{name}-{}.db", std::process::id()));
+        let mut database = toml::from_str::<DbType>(&format!(
+            "type = \"sqlite\"\ndatabase = {:?}",
+            path.to_string_lossy()
+        ))
+        .unwrap();
+
+        database
+            .init_schema(None, std::slice::from_ref(coin))
+            .await
+            .unwrap();
+        (database, path)
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn store_interrupted() {
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let date = date!(2024 - 01 - 01);
+        let (mut database, path) = temp_database("interrupted", &coin).await;
+        let series = vec![vec![candle(
+            datetime!(2024-01-01 00:00 UTC),
+            dec!(100),
+            dec!(1),
+        )]];
+        // The download of the second coin never finishes.
+        let downloads = stream::iter([(coin.clone(), series, CoinReport::new(&coin, date))])
+            .chain(stream::pending());
+        let mut report = FetchReport::new(date, date, false, false);
+        let mut stored = Vec::new();
+
+        store_downloads(
+            downloads,
+            &mut future::ready(Ok(())).fuse(),
+            &mut database,
+            date,
+            &FetchOptions::default(),
+            &mut report,
+            |candles| stored.push(candles),
+        )
+        .await
+        .unwrap();
+        let candles = database
+            .query_candles(
+                &coin,
+                Timeframe::FiveMinutes,
+                datetime!(2024-01-01 00:00 UTC),
+                datetime!(2024-01-02 00:00 UTC),
+            )
+            .await
+            .unwrap();
+        drop(database);
+        let _ = std::fs::remove_file(path);
+
+        assert!(report.interrupted);
+        assert_eq!(stored, [1]);
+        assert!(report.coins[0].stored);
+        assert_eq!(candles.len(), 1);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn store_interrupt_failed() {
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let date = date!(2024 - 01 - 01);
+        let (mut database, path) = temp_database("interrupt-failed", &coin).await;
+        let series = vec![vec![candle(
+            datetime!(2024-01-01 00:00 UTC),
+            dec!(100),
+            dec!(1),
+        )]];
+        // The download finishes after the interrupt failed.
+        let downloads = stream::once(async move {
+            tokio::task::yield_now().await;
+            (coin.clone(), series, CoinReport::new(&coin, date))
+        });
+        let mut report = FetchReport::new(date, date, false, false);
+
+        store_downloads(
+            downloads,
+            &mut future::ready(Err(io::Error::other("no signal handler"))).fuse(),
+            &mut database,
+            date,
+            &FetchOptions::default(),
+            &mut report,
+            |_| {},
+        )
+        .await
+        .unwrap();
+        drop(database);
+        let _ = std::fs::remove_file(path);
 
-    todo!()
+        assert!(!report.interrupted);
+        assert_eq!(report.coins.len(), 1);
+        assert!(report.coins[0].stored);
+    }
 }
@@ -0,0 +1,376 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    mem,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+#[cfg(feature = "gzip")]
+use flate2::read::MultiGzDecoder;
+use ohlcv::{Candle, Database, Timeframe};
+use time::OffsetDateTime;
+use tracing::{info, instrument, warn};
+
+use crate::{config::Config, format::Format, Error};
+
+/// Number of candles inserted into the database at once.
+const IMPORT_BATCH_SIZE: usize = 100_000;
+
+/// Options of the import command.
+#[derive(Clone, Debug, Default)]
+pub struct ImportOptions {
+    /// Coin to import given as symbol, optionally followed by the currency,
+    /// e.g. `BTC` or `BTC/USD`.
+    pub coin: String,
+    /// Format of the imported candles.
+    pub format: Format,
+    /// File to read the candles from. If the path is `-`, the candles are
+    /// read from stdin. Files ending with `.gz` are decompressed if the
+    /// feature `gzip` is enabled.
+    pub input: PathBuf,
+    /// Handling of candles with the same timestamp and timeframe as a
+    /// preceding candle of the input.
+    pub on_duplicate: OnDuplicate,
+}
+
+/// Handling of duplicate candles within the imported input.
+///
+/// Two candles are duplicates if they have the same timestamp and timeframe.
+/// Only the input is checked, candles already stored in the database are
+/// always replaced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnDuplicate {
+    /// Abort the import with the lines of both candles.
+    #[default]
+    Error,
+    /// Keep the first candle and skip the duplicate with a warning.
+    Skip,
+    /// Replace the first candle by the duplicate with a warning.
+    Overwrite,
+}
+
+impl OnDuplicate {
+    /// The names of the handlings as accepted by [`OnDuplicate::from_str`].
+    pub const NAMES: [&'static str; 3] = ["error", "skip", "overwrite"];
+}
+
+impl fmt::Display for OnDuplicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Skip => write!(f, "skip"),
+            Self::Overwrite => write!(f, "overwrite"),
+        }
+    }
+}
+
+impl FromStr for OnDuplicate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Self::Error),
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// Import the candles of a coin.
+///
+/// The candles are read line by line in the format of the options, as written
+/// by the export command. They are inserted in batches, so only a batch of
+/// candles is held in memory. Every batch is inserted in its own transaction,
+/// so the batches stored before an error are kept. Existing candles with the
+/// same timestamp and timeframe are replaced.
+///
+/// Duplicate candles within the input are handled according to the options.
+/// To detect them, the timestamp, timeframe and line of every imported candle
+/// are kept until the import is done. Candles that are not in ascending order
+/// of their timestamps within their timeframe are imported, but reported with
+/// a warning naming the first of these lines.
+///
+/// # Arguments
+///
+/// * `options` - The options of the command.
+/// * `config` - Optional path to the configuration file. If not provided, the
+///   file named by the environment variable `OHLCV_CONFIG` or else the
+///   default configuration file will be used. This file is expected to be in
+///   TOML format. The default file is `ohlcv.toml` and is expected to be in
+///   the current working directory or in `/etc/ohlcv`.
+///
+/// # Errors
+///
+/// Returns an error if the coin is not configured, if the input cannot be read
+/// or a line cannot be parsed, if the input contains a duplicate candle and
+/// the options do not allow it, if the candles cannot be stored, or if the
+/// configuration file cannot be loaded.
+#[instrument]
+pub async fn import(options: &ImportOptions, config: Option<&PathBuf>) -> Result<(), Error> {
+    let mut config = Config::load(config)?;
+    let coin = config.find_coin(&options.coin)?;
+    let input: Box<dyn Read + Send> = if options.input == Path::new("-") {
+        Box::new(io::stdin())
+    } else {
+        open(&options.input)?
+    };
+    let mut batches = Batches::new(options.on_duplicate, IMPORT_BATCH_SIZE);
+    let mut count = 0;
+
+    for (index, line) in BufReader::new(input).lines().enumerate() {
+        let number = index + 1;
+        let candle = options
+            .format
+            .read_candle(&line?)
+            .map_err(|reason| Error::ImportLine(number, reason))?;
+        let Some(candle) = candle else {
+            continue;
+        };
+
+        if let Some(batch) = batches.push(number, candle)? {
+            count += batch.len();
+            config.database.bulk_insert_candles(&coin, &batch).await?;
+        }
+    }
+    let batch = batches.finish();
+    if !batch.is_empty() {
+        count += batch.len();
+        config.database.bulk_insert_candles(&coin, &batch).await?;
+    }
+
+    if let Some(first) = batches.first_unordered {
+        warn!(
+            "{} candles are not in ascending order of their timestamps, first in line {first}",
+            batches.unordered
+        );
+    }
+    info!("Imported {count} candles of {coin:#}");
+    Ok(())
+}
+
+/// Batches of imported candles with duplicates handled.
+///
+/// The line of every candle read is kept to detect duplicates across batches,
+/// and the index of every candle of the current batch to replace a duplicate
+/// in place.
+#[derive(Debug)]
+struct Batches {
+    on_duplicate: OnDuplicate,
+    size: usize,
+    batch: Vec<Candle>,
+    lines: HashMap<(OffsetDateTime, Timeframe), usize>,
+    indices: HashMap<(OffsetDateTime, Timeframe), usize>,
+    latest: HashMap<Timeframe, OffsetDateTime>,
+    /// Number of candles preceded by a later candle of their timeframe.
+    unordered: usize,
+    /// Line of the first candle preceded by a later candle.
+    first_unordered: Option<usize>,
+}
+
+impl Batches {
+    fn new(on_duplicate: OnDuplicate, size: usize) -> Self {
+        Self {
+            on_duplicate,
+            size,
+            batch: Vec::with_capacity(size),
+            lines: HashMap::new(),
+            indices: HashMap::new(),
+            latest: HashMap::new(),
+            unordered: 0,
+            first_unordered: None,
+        }
+    }
+
+    /// Add the candle read from the line `number`.
+    ///
+    /// Returns the batch to insert once it is full. A duplicate of a candle
+    /// of a returned batch is added to the current batch if it overwrites the
+    /// first candle, as the insert replaces the stored candle.
+    fn push(&mut self, number: usize, candle: Candle) -> Result<Option<Vec<Candle>>, Error> {
+        let key = (candle.timestamp, candle.timeframe);
+
+        match self.lines.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(number);
+            }
+            Entry::Occupied(mut entry) => {
+                let first = *entry.get();
+                match self.on_duplicate {
+                    OnDuplicate::Error => return Err(Error::ImportDuplicate(number, first)),
+                    OnDuplicate::Skip => {
+                        warn!("Skipped the candle in line {number}, duplicate of line {first}");
+                        return Ok(None);
+                    }
+                    OnDuplicate::Overwrite => {
+                        warn!("Candle in line {number} replaces the duplicate of line {first}");
+                        entry.insert(number);
+                        if let Some(&index) = self.indices.get(&key) {
+                            self.batch[index] = candle;
+                            return Ok(None);
+                        }
+                    }
+                }
+            }
+        }
+        let latest = self
+            .latest
+            .entry(candle.timeframe)
+            .or_insert(candle.timestamp);
+        if *latest > candle.timestamp {
+            self.unordered += 1;
+            self.first_unordered.get_or_insert(number);
+        } else {
+            *latest = candle.timestamp;
+        }
+
+        self.indices.insert(key, self.batch.len());
+        self.batch.push(candle);
+        if self.batch.len() < self.size {
+            return Ok(None);
+        }
+        self.indices.clear();
+        Ok(Some(mem::replace(
+            &mut self.batch,
+            Vec::with_capacity(self.size),
+        )))
+    }
+
+    /// Return the candles of the last batch, which may not be full.
+    fn finish(&mut self) -> Vec<Candle> {
+        self.indices.clear();
+        mem::take(&mut self.batch)
+    }
+}
+
+/// Open the file to import.
+///
+/// Files ending with `.gz` are decompressed if the feature `gzip` is enabled.
+fn open(path: &Path) -> io::Result<Box<dyn Read + Send>> {
+    let file = File::open(path)?;
+
+    #[cfg(feature = "gzip")]
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        return Ok(Box::new(MultiGzDecoder::new(file)));
+    }
+    Ok(Box::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use time::{macros::datetime, Duration};
+
+    use super::*;
+
+    /// Candle of the `index`-th five minutes of a day with the close price `close`.
+    fn candle(index: i64, close: Decimal) -> Candle {
+        Candle::new(
+            datetime!(2024-01-01 00:00 UTC) + Duration::minutes(5 * index),
+            Timeframe::FiveMinutes,
+            close,
+            close,
+            close,
+            close,
+            dec!(1),
+        )
+        .unwrap()
+    }
+
+    /// Push the candles as lines 1, 2, ... and collect the returned batches.
+    fn push(batches: &mut Batches, candles: &[Candle]) -> Result<Vec<Vec<Candle>>, Error> {
+        let mut inserted = Vec::new();
+
+        for (index, candle) in candles.iter().enumerate() {
+            inserted.extend(batches.push(index + 1, *candle)?);
+        }
+        inserted.push(batches.finish());
+        Ok(inserted)
+    }
+
+    /// The close prices of the batches.
+    fn closes(batches: &[Vec<Candle>]) -> Vec<Vec<Decimal>> {
+        batches
+            .iter()
+            .map(|batch| batch.iter().map(|candle| candle.close).collect())
+            .collect()
+    }
+
+    #[test]
+    fn batches() {
+        let mut batches = Batches::new(OnDuplicate::Error, 2);
+        let inserted = push(
+            &mut batches,
+            &[candle(0, dec!(1)), candle(2, dec!(2)), candle(1, dec!(3))],
+        )
+        .unwrap();
+
+        assert_eq!(closes(&inserted), [vec![dec!(1), dec!(2)], vec![dec!(3)]]);
+        assert_eq!(batches.unordered, 1);
+        assert_eq!(batches.first_unordered, Some(3));
+    }
+
+    #[test]
+    fn duplicate_error() {
+        let mut batches = Batches::new(OnDuplicate::Error, 2);
+        let result = push(
+            &mut batches,
+            &[candle(0, dec!(1)), candle(1, dec!(2)), candle(0, dec!(3))],
+        );
+
+        assert!(matches!(result, Err(Error::ImportDuplicate(3, 1))));
+    }
+
+    #[test]
+    fn duplicate_skip() {
+        let mut batches = Batches::new(OnDuplicate::Skip, 2);
+        let inserted = push(
+            &mut batches,
+            &[
+                candle(0, dec!(1)),
+                candle(0, dec!(2)),
+                candle(1, dec!(3)),
+                candle(0, dec!(4)),
+                candle(2, dec!(5)),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(closes(&inserted), [vec![dec!(1), dec!(3)], vec![dec!(5)]]);
+        assert_eq!(batches.first_unordered, None);
+    }
+
+    #[test]
+    fn duplicate_overwrite() {
+        let mut batches = Batches::new(OnDuplicate::Overwrite, 2);
+        let inserted = push(
+            &mut batches,
+            &[
+                candle(0, dec!(1)),
+                candle(0, dec!(2)),
+                candle(1, dec!(3)),
+                candle(2, dec!(4)),
+                candle(0, dec!(5)),
+                candle(2, dec!(6)),
+            ],
+        )
+        .unwrap();
+
+        // A duplicate in the current batch replaces the candle in place, a
+        // duplicate of an inserted batch is inserted again.
+        assert_eq!(
+            closes(&inserted),
+            [
+                vec![dec!(2), dec!(3)],
+                vec![dec!(4), dec!(5)],
+                vec![dec!(6)]
+            ]
+        );
+        assert_eq!(batches.unordered, 1);
+        assert_eq!(batches.first_unordered, Some(5));
+    }
+}
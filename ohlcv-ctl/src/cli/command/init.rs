@@ -1,4 +1,7 @@
-use std::path::PathBuf;
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
 
 use ohlcv::Database;
 use tracing::instrument;
@@ -12,9 +15,17 @@ use super::root_credentials;
 
 /// Initialize the database
 ///
+/// The tables of the configured coins are created. If `sql_only` is set, the
+/// statements are printed to stdout instead of executed, e.g. to review them
+/// or to apply them by a migration tool. The printed statements are exactly
+/// the ones executed otherwise, each terminated by a semicolon. No connection
+/// to the database is made then.
+///
 /// # Arguments
 ///
+/// * `sql_only` - Print the statements instead of executing them.
 /// * `config` - Optional path to the configuration file. If not provided, the
+///   file named by the environment variable `OHLCV_CONFIG` or else the
 ///   default configuration file will be used. This file is expected to be in
 ///   TOML format. The default file is `ohlcv.toml` and is expected to be in the
 ///   current working directory or in `/etc/ohlcv`.
@@ -24,15 +35,24 @@ use super::root_credentials;
 /// Returns an error if the database cannot be initialized or if the
 /// configuration file cannot be loaded.
 #[instrument]
-pub async fn init(config: Option<&PathBuf>) -> Result<(), Error> {
+pub async fn init(sql_only: bool, config: Option<&PathBuf>) -> Result<(), Error> {
     let mut config = Config::load(config)?;
-    let creds = root_credentials(&config.database)?;
     let coins = config
         .coins
         .iter()
         .map(CoinConfig::as_coin)
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>, _>>()?;
 
+    if sql_only {
+        let mut stdout = io::stdout().lock();
+
+        for statement in config.database.schema_statements(&coins)? {
+            writeln!(stdout, "{statement};")?;
+        }
+        return Ok(());
+    }
+
+    let creds = root_credentials(&config.database)?;
     config
         .database
         .init_schema(creds, coins.as_slice())
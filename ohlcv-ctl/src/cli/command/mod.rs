@@ -5,21 +5,34 @@ use std::fmt;
 
 pub use drop::drop;
 
+mod export;
+pub use export::{export, export_all, ExportOptions};
+
 mod fetch;
-pub use fetch::fetch;
+pub use fetch::{fetch, FetchOptions, DEFAULT_PARALLELISM};
+
+mod import;
+pub use import::{import, ImportOptions, OnDuplicate};
 
 mod init;
 pub use init::init;
 
+mod report;
+pub use report::{CoinReport, ExchangeReport, FetchReport};
+
 use clap::ArgMatches;
 use inquire::{Password, PasswordDisplayMode};
 use ohlcv::{
     database::{Credentials, DbType},
-    Database,
+    Database, Exchange, Timeframe,
 };
+use time::{Date, OffsetDateTime};
 use tracing::instrument;
 
-use crate::Error;
+use crate::{
+    format::{Compression, Format},
+    Error,
+};
 
 /// Execute the command specified by the command line arguments.
 ///
@@ -27,27 +40,116 @@ use crate::Error;
 ///
 /// Returns an error if the command is not recognized or if an error occurs
 /// while executing the command.
-#[instrument(skip(command))]
-pub async fn execute(command: Option<(&str, &ArgMatches)>) -> Result<(), Error> {
-    match command {
+#[instrument(skip(matches))]
+#[allow(clippy::too_many_lines)]
+pub async fn execute(matches: &ArgMatches) -> Result<(), Error> {
+    let quiet = matches.get_flag("quiet");
+
+    match matches.subcommand() {
         Some(("drop", args)) => {
             let config = args.get_one::<std::path::PathBuf>("config");
             let all = args.get_flag("all");
+            let yes = args.get_flag("yes");
+            let coins = args
+                .get_many::<String>("coin")
+                .unwrap_or_default()
+                .cloned()
+                .collect::<Vec<_>>();
 
-            drop(all, config).await
+            drop(all, yes, &coins, config).await
         }
         Some(("init", args)) => {
             let config = args.get_one::<std::path::PathBuf>("config");
+            let sql_only = args.get_flag("sql_only");
 
-            init(config).await
+            init(sql_only, config).await
+        }
+        Some(("export", args)) => {
+            let config = args.get_one::<std::path::PathBuf>("config");
+            let options = ExportOptions {
+                coin: args.get_one::<String>("coin").cloned().unwrap_or_default(),
+                timeframe: args.get_one::<Timeframe>("timeframe").copied(),
+                format: args
+                    .get_one::<Format>("format")
+                    .copied()
+                    .unwrap_or_default(),
+                // The option exists only with the feature `gzip`.
+                compression: if cfg!(feature = "gzip") {
+                    args.get_one::<Compression>("compress")
+                        .copied()
+                        .unwrap_or_default()
+                } else {
+                    Compression::None
+                },
+                from: args.get_one::<OffsetDateTime>("from").copied(),
+                to: args.get_one::<OffsetDateTime>("to").copied(),
+                output: args.get_one::<std::path::PathBuf>("output").cloned(),
+            };
+
+            match args.get_one::<std::path::PathBuf>("output_dir") {
+                Some(output_dir) if args.get_flag("all") => {
+                    export_all(&options, output_dir, config).await
+                }
+                _ => export(&options, config).await,
+            }
+        }
+        Some(("import", args)) => {
+            let config = args.get_one::<std::path::PathBuf>("config");
+            let options = ImportOptions {
+                coin: args.get_one::<String>("coin").cloned().unwrap_or_default(),
+                format: args
+                    .get_one::<Format>("format")
+                    .copied()
+                    .unwrap_or_default(),
+                input: args
+                    .get_one::<std::path::PathBuf>("input")
+                    .cloned()
+                    .unwrap_or_default(),
+                on_duplicate: args
+                    .get_one::<OnDuplicate>("on_duplicate")
+                    .copied()
+                    .unwrap_or_default(),
+            };
+
+            import(&options, config).await
         }
         Some(("fetch", args)) => {
             let config = args.get_one::<std::path::PathBuf>("config");
+            let date = args.get_one::<Date>("date").copied();
+            let options = FetchOptions {
+                dry_run: args.get_flag("dry_run"),
+                force: args.get_flag("force"),
+                from: date.or_else(|| args.get_one::<Date>("from").copied()),
+                to: date.or_else(|| args.get_one::<Date>("to").copied()),
+                quiet,
+                parallelism: args
+                    .get_one::<u16>("parallelism")
+                    .map_or(DEFAULT_PARALLELISM, |parallelism| usize::from(*parallelism)),
+                strict: args.get_flag("strict"),
+                coins: args
+                    .get_many::<String>("coin")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect(),
+                exchanges: args
+                    .get_many::<Exchange>("exchange")
+                    .unwrap_or_default()
+                    .copied()
+                    .collect(),
+                report: args.get_one::<std::path::PathBuf>("report").cloned(),
+            };
 
-            fetch(config).await
+            fetch(&options, config).await
         }
         Some((command, _)) => Err(Error::CommandName(command.into())),
-        None => fetch(None).await,
+        None => {
+            let options = FetchOptions {
+                quiet,
+                ..FetchOptions::default()
+            };
+
+            fetch(&options, None).await
+        }
     }
 }
 
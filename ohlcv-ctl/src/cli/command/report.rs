@@ -0,0 +1,182 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use ohlcv::{Coin, Currency, Exchange};
+use serde::Serialize;
+use time::Date;
+
+use crate::Error;
+
+/// Summary of a fetch run as written by the option `--report`.
+///
+/// The summary is written as JSON once the downloads are done, even if the
+/// run was interrupted or failed while storing the candles.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FetchReport {
+    /// First downloaded day as `YYYY-MM-DD`.
+    pub from: String,
+    /// Last downloaded day as `YYYY-MM-DD`.
+    pub to: String,
+    /// Whether the candles were only downloaded, but not stored.
+    pub dry_run: bool,
+    /// Whether days with stored candles were downloaded again.
+    pub force: bool,
+    /// Whether the run was interrupted by Ctrl-C.
+    pub interrupted: bool,
+    /// Error aborting the run, if any.
+    pub error: Option<String>,
+    /// Processed coins, one entry per coin and day.
+    pub coins: Vec<CoinReport>,
+}
+
+impl FetchReport {
+    /// Create an empty report for the days from `from` to `to`.
+    #[must_use]
+    pub fn new(from: Date, to: Date, dry_run: bool, force: bool) -> Self {
+        Self {
+            from: from.to_string(),
+            to: to.to_string(),
+            dry_run,
+            force,
+            interrupted: false,
+            error: None,
+            coins: Vec::new(),
+        }
+    }
+
+    /// Write the report as JSON to `path`.
+    ///
+    /// The report is written to a temporary file next to `path`, which then
+    /// replaces `path`. So a failed write never leaves a partial report.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the temporary file cannot be written or renamed.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let mut temp = OsString::from(path.as_os_str());
+
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+        let result = File::create(&temp).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+
+            serde_json::to_writer_pretty(&mut writer, self)?;
+            writer.write_all(b"\n")?;
+            writer.into_inner()?.sync_all()?;
+            fs::rename(&temp, path)
+        });
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        Ok(result?)
+    }
+}
+
+/// Summary of a coin for a single day.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CoinReport {
+    /// Downloaded day as `YYYY-MM-DD`.
+    pub date: String,
+    /// Symbol of the coin.
+    pub symbol: String,
+    /// Currency of the coin.
+    pub currency: Currency,
+    /// Whether the coin was skipped, as its candles were already stored.
+    pub skipped: bool,
+    /// Downloads of the exchanges of the coin.
+    pub exchanges: Vec<ExchangeReport>,
+    /// Number of candles after merging the exchanges.
+    pub candles: usize,
+    /// Number of candles missing on some exchange, but provided by another.
+    pub filled: usize,
+    /// Number of gaps left after merging the exchanges.
+    pub gaps: usize,
+    /// Number of candles missing after merging the exchanges.
+    pub missing: usize,
+    /// Whether the merged candles were stored in the database.
+    pub stored: bool,
+}
+
+impl CoinReport {
+    /// Create an empty report of the coin for the day.
+    #[must_use]
+    pub fn new(coin: &Coin, date: Date) -> Self {
+        Self {
+            date: date.to_string(),
+            symbol: coin.symbol().to_string(),
+            currency: coin.currency(),
+            skipped: false,
+            exchanges: Vec::new(),
+            candles: 0,
+            filled: 0,
+            gaps: 0,
+            missing: 0,
+            stored: false,
+        }
+    }
+}
+
+/// Summary of the download of a coin from an exchange.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ExchangeReport {
+    /// The exchange.
+    pub exchange: Exchange,
+    /// Symbol of the coin on the exchange.
+    pub symbol: String,
+    /// Number of downloaded candles.
+    pub candles: usize,
+    /// Number of gaps in the downloaded candles.
+    pub gaps: usize,
+    /// Number of candles missing in the downloaded candles.
+    pub missing: usize,
+    /// Reason the download failed or was rejected by the validation, if any.
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::date;
+
+    use super::*;
+
+    #[test]
+    fn write() {
+        let dir = std::env::temp_dir().join(format!("ohlcv-report-{}", std::process::id()));
+        let path = dir.join("report.json");
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let mut report =
+            FetchReport::new(date!(2024 - 01 - 01), date!(2024 - 01 - 02), false, true);
+        let mut coin_report = CoinReport::new(&coin, date!(2024 - 01 - 01));
+
+        coin_report.exchanges.push(ExchangeReport {
+            exchange: Exchange::Kraken,
+            symbol: "XBTUSD".to_string(),
+            candles: 0,
+            gaps: 0,
+            missing: 0,
+            error: Some("timeout".to_string()),
+        });
+        report.coins.push(coin_report);
+
+        fs::create_dir_all(&dir).unwrap();
+        report.write(&path).unwrap();
+        let json =
+            serde_json::from_str::<serde_json::Value>(&fs::read_to_string(&path).unwrap()).unwrap();
+        let temp = dir.join("report.json.tmp").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(json["from"], "2024-01-01");
+        assert_eq!(json["force"], true);
+        assert_eq!(json["error"], serde_json::Value::Null);
+        assert_eq!(json["coins"][0]["symbol"], "BTC");
+        assert_eq!(json["coins"][0]["currency"], "USD");
+        assert_eq!(json["coins"][0]["exchanges"][0]["exchange"], "Kraken");
+        assert_eq!(json["coins"][0]["exchanges"][0]["error"], "timeout");
+        assert!(!temp);
+    }
+}
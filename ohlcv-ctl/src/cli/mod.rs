@@ -1,21 +1,65 @@
+use std::{fmt, str::FromStr};
+
 use clap::ArgMatches;
+use ohlcv::{Exchange, Timeframe};
+use time::macros::format_description;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
 
 pub mod command;
 
 /// Command line interface for the collector.
 ///
 /// Returns the matches from the command line arguments.
-#[allow(clippy::cognitive_complexity)]
 #[must_use]
 pub fn clargs() -> ArgMatches {
+    cli().get_matches()
+}
+
+/// The command line interface with the options of all commands.
+#[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
+fn cli() -> clap::Command {
     use std::path::PathBuf;
 
-    use clap::{arg, command, value_parser, ArgAction, Command};
+    use clap::{
+        arg,
+        builder::{PossibleValuesParser, TypedValueParser},
+        command, value_parser, ArgAction, Command,
+    };
 
-    let command = command!()
+    use crate::{command::OnDuplicate, format::Format};
+
+    command!()
+        .arg(
+            arg!(verbose: -v --verbose "log more details, repeat for even more")
+                .action(ArgAction::Count)
+                .global(true)
+                .display_order(100),
+        )
+        .arg(
+            arg!(quiet: -q --quiet "log only warnings and errors, no progress")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("verbose")
+                .global(true)
+                .display_order(101),
+        )
+        .arg(
+            arg!(log_format: --"log-format" <FORMAT> "format of the log messages")
+                .value_parser(
+                    PossibleValuesParser::new(LogFormat::NAMES)
+                        .try_map(|value| value.parse::<LogFormat>()),
+                )
+                .default_value("text")
+                .global(true)
+                .display_order(102),
+        )
         .subcommand(
             Command::new("init")
                 .about("Initialize the database tables")
+                .arg(
+                    arg!(sql_only: --"sql-only" "print the statements instead of executing them")
+                        .action(ArgAction::SetTrue),
+                )
                 .arg(
                     arg!(config: -c --config <FILE> "optional path to the configuration file")
                         .value_parser(value_parser!(PathBuf)),
@@ -25,6 +69,95 @@ pub fn clargs() -> ArgMatches {
             Command::new("drop")
                 .about("Remove the database tables")
                 .arg(arg!(all: -a --all "remove tables for all coins").action(ArgAction::SetTrue))
+                .arg(
+                    arg!(coin: --coin <SYMBOL> "remove only the table of this coin, can be repeated")
+                        .action(ArgAction::Append)
+                        .conflicts_with("all"),
+                )
+                .arg(arg!(yes: -y --yes "skip the confirmation prompt").action(ArgAction::SetTrue))
+                .arg(
+                    arg!(config: -c --config <FILE> "optional path to the configuration file")
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Export the candles of a coin")
+                .arg(
+                    arg!(coin: --coin <COIN> "symbol of the coin, optionally with currency like BTC/USD")
+                        .required_unless_present("all"),
+                )
+                .arg(
+                    arg!(all: -a --all "export all configured coins into one file per coin")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["coin", "output"])
+                        .requires("output_dir"),
+                )
+                .arg(
+                    arg!(timeframe: -t --timeframe <TIMEFRAME> "timeframe of the candles, 5m for a coin and all with --all if not set")
+                        .value_parser(
+                            PossibleValuesParser::new(Timeframe::NAMES)
+                                .try_map(|value| value.parse::<Timeframe>()),
+                        ),
+                )
+                .arg(
+                    arg!(format: -f --format <FORMAT> "format of the exported candles")
+                        .value_parser(
+                            PossibleValuesParser::new(Format::NAMES)
+                                .try_map(|value| value.parse::<Format>()),
+                        )
+                        .default_value("csv"),
+                )
+                .args(compress_args())
+                .arg(
+                    arg!(from: --from <TIMESTAMP> "export the candles from this RFC 3339 time on")
+                        .value_parser(parse_timestamp),
+                )
+                .arg(
+                    arg!(to: --to <TIMESTAMP> "export the candles before this RFC 3339 time")
+                        .value_parser(parse_timestamp),
+                )
+                .arg(
+                    arg!(output: -o --output <FILE> "file to write to instead of stdout")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(output_dir: --"output-dir" <DIR> "directory to write the files of --all to")
+                        .value_parser(value_parser!(PathBuf))
+                        .conflicts_with("coin"),
+                )
+                .arg(
+                    arg!(config: -c --config <FILE> "optional path to the configuration file")
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Import the candles of a coin")
+                .arg(
+                    arg!(input: <FILE> "file to read the candles from, - for stdin")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(coin: --coin <COIN> "symbol of the coin, optionally with currency like BTC/USD")
+                        .required(true),
+                )
+                .arg(
+                    arg!(format: -f --format <FORMAT> "format of the imported candles")
+                        .value_parser(
+                            PossibleValuesParser::new(Format::NAMES)
+                                .try_map(|value| value.parse::<Format>()),
+                        )
+                        .default_value("csv"),
+                )
+                .arg(
+                    arg!(on_duplicate: --"on-duplicate" <ACTION> "handling of candles with the same timestamp and timeframe")
+                        .value_parser(
+                            PossibleValuesParser::new(OnDuplicate::NAMES)
+                                .try_map(|value| value.parse::<OnDuplicate>()),
+                        )
+                        .default_value("error"),
+                )
                 .arg(
                     arg!(config: -c --config <FILE> "optional path to the configuration file")
                         .value_parser(value_parser!(PathBuf)),
@@ -33,11 +166,197 @@ pub fn clargs() -> ArgMatches {
         .subcommand(
             Command::new("fetch")
                 .about("Fetch data from the origin")
+                .arg(
+                    arg!(dry_run: --"dry-run" "download and merge without storing the candles")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(force: --force "download days with stored candles again and replace them")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(date: --date <DATE> "day to download as YYYY-MM-DD")
+                        .value_parser(parse_date)
+                        .conflicts_with_all(["from", "to"]),
+                )
+                .arg(
+                    arg!(from: --from <DATE> "first day of the range to download")
+                        .value_parser(parse_date),
+                )
+                .arg(
+                    arg!(to: --to <DATE> "last day of the range to download")
+                        .value_parser(parse_date),
+                )
+                .arg(
+                    arg!(parallelism: -j --parallelism <N> "number of coins to download at the same time")
+                        .value_parser(value_parser!(u16).range(1..))
+                        .default_value("4"),
+                )
+                .arg(
+                    arg!(strict: --strict "fail if an exchange quotes a coin in another currency")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(coin: --coin <SYMBOL> "download only this coin, can be repeated")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    arg!(exchange: --exchange <EXCHANGE> "download only from this exchange, can be repeated")
+                        .value_parser(
+                            PossibleValuesParser::new(Exchange::NAMES)
+                                .try_map(|value| value.parse::<Exchange>()),
+                        )
+                        .ignore_case(true)
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    arg!(report: --report <FILE> "write a summary of the run as JSON to this file")
+                        .value_parser(value_parser!(PathBuf)),
+                )
                 .arg(
                     arg!(config: -c --config <FILE> "optional path to the configuration file")
                         .value_parser(value_parser!(PathBuf)),
                 ),
-        );
+        )
+}
+
+/// Log format selected by the command line arguments.
+///
+/// Without the option `--log-format` the messages are logged as text.
+#[must_use]
+pub fn log_format(matches: &ArgMatches) -> LogFormat {
+    matches
+        .get_one::<LogFormat>("log_format")
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Format of the log messages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable text, one line per message.
+    #[default]
+    Text,
+    /// JSON, one object per line, e.g. for log aggregators.
+    Json,
+}
+
+impl LogFormat {
+    /// The names of the formats as accepted by [`LogFormat::from_str`].
+    pub const NAMES: [&'static str; 2] = ["text", "json"];
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// Log filter selected by the command line arguments.
+///
+/// The options `--quiet` and `--verbose` select the level of all log messages.
+/// Without these options the filter is read from the `RUST_LOG` environment
+/// variable, defaulting to the `info` level.
+#[must_use]
+pub fn log_filter(matches: &ArgMatches) -> EnvFilter {
+    filter(
+        matches,
+        std::env::var(EnvFilter::DEFAULT_ENV).ok().as_deref(),
+    )
+}
+
+/// Log filter selected by the arguments, or else by the directives of
+/// `RUST_LOG`, see [`log_filter`].
+fn filter(matches: &ArgMatches, rust_log: Option<&str>) -> EnvFilter {
+    let level = match (matches.get_flag("quiet"), matches.get_count("verbose")) {
+        (true, _) => LevelFilter::WARN,
+        (false, 0) => {
+            return EnvFilter::builder()
+                .with_default_directive(LevelFilter::INFO.into())
+                .parse_lossy(rust_log.unwrap_or_default())
+        }
+        (false, 1) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    };
+
+    EnvFilter::default().add_directive(level.into())
+}
+
+/// Options of the export command selecting the compression.
+#[cfg(feature = "gzip")]
+fn compress_args() -> Vec<clap::Arg> {
+    use clap::{
+        arg,
+        builder::{PossibleValuesParser, TypedValueParser},
+    };
+
+    use crate::format::Compression;
+
+    vec![
+        arg!(compress: --compress <COMPRESSION> "compression of the exported candles")
+            .value_parser(
+                PossibleValuesParser::new(Compression::NAMES)
+                    .try_map(|value| value.parse::<Compression>()),
+            )
+            .default_value("none"),
+    ]
+}
+
+/// Options of the export command selecting the compression, none without the
+/// feature `gzip`.
+#[cfg(not(feature = "gzip"))]
+const fn compress_args() -> Vec<clap::Arg> {
+    Vec::new()
+}
+
+/// Parse a timestamp in RFC 3339 and convert it to UTC.
+fn parse_timestamp(value: &str) -> Result<time::OffsetDateTime, time::error::Parse> {
+    time::OffsetDateTime::parse(value, &time::format_description::well_known::Rfc3339)
+        .map(|timestamp| timestamp.to_offset(time::UtcOffset::UTC))
+}
+
+/// Parse a date in the format `YYYY-MM-DD`.
+fn parse_date(value: &str) -> Result<time::Date, time::error::Parse> {
+    time::Date::parse(value, format_description!("[year]-[month]-[day]"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Log filter of the arguments given to the `init` command.
+    fn init_filter(args: &[&str], rust_log: Option<&str>) -> String {
+        let args = [&["ohlcv-ctl"], args, &["init"]].concat();
+        let matches = cli().try_get_matches_from(args).unwrap();
+
+        filter(&matches, rust_log).to_string()
+    }
 
-    command.get_matches()
+    #[test]
+    fn log_filter() {
+        assert_eq!(init_filter(&[], None), "info");
+        assert_eq!(init_filter(&[], Some("ohlcv=trace")), "ohlcv=trace");
+        assert_eq!(init_filter(&["-q"], Some("debug")), "warn");
+        assert_eq!(init_filter(&["-v"], Some("warn")), "debug");
+        assert_eq!(init_filter(&["-vv"], None), "trace");
+        assert_eq!(init_filter(&["-v", "-v", "-v"], None), "trace");
+        assert!(cli()
+            .try_get_matches_from(["ohlcv-ctl", "-q", "-v", "init"])
+            .is_err());
+    }
 }
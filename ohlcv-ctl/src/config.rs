@@ -1,21 +1,38 @@
 //! Configuration for ohlcv-ctl.
 
-use std::{collections::HashMap, fmt, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    num::NonZero,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use ohlcv::{database::DbType, Coin, Currency, Exchange};
+use ohlcv::{
+    database::DbType,
+    exchange::{Fetcher, RateLimit, Retry},
+    Coin, Currency, Exchange,
+};
 use serde::Deserialize;
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
 
 use crate::Error;
 
 /// Name of the default configuration file.
 pub const CONFIG_FILE: &str = concat!(env!("CARGO_PKG_NAME"), ".toml",);
 
-/// Default paths to search for the configuration file if not specified by the
-/// user either through a command-line argument or environment variable. The
-/// paths are appended with [`CONFIG_FILE`] to form the full path to the
-/// configuration file. Paths are searched in order, and the first file found is
-/// used.
+/// Environment variable holding the path to the configuration file.
+///
+/// The variable is consulted if no path is given on the command line, before
+/// searching the [`CONFIG_PATHS`].
+pub const CONFIG_ENV: &str = "OHLCV_CONFIG";
+
+/// Default paths to search for the configuration file.
+///
+/// The paths are used if the configuration file is not specified by the user
+/// either through a command-line argument or environment variable. The paths
+/// are appended with [`CONFIG_FILE`] to form the full path to the configuration
+/// file. Paths are searched in order, and the first file found is used.
 pub const CONFIG_PATHS: [&str; 2] = [".", "/etc/ohlcv"];
 
 const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
@@ -24,7 +41,13 @@ const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VE
 pub type ExchangeMap = HashMap<Exchange, String>;
 
 /// Configuration for a coin.
+///
+/// The exchanges are given either as table of the exchange names to the
+/// symbols of the coin on the exchanges, or as list of exchange names. The
+/// symbols of a list are derived from the symbol and currency of the coin by
+/// [`Exchange::format_symbol`].
 #[derive(Debug, Deserialize)]
+#[serde(from = "RawCoinConfig")]
 #[allow(clippy::module_name_repetitions, dead_code)]
 pub struct CoinConfig {
     symbol: String,
@@ -32,20 +55,115 @@ pub struct CoinConfig {
     currency: Currency,
     /// Map of exchange names to the coin's symbol on that exchange.
     pub exchanges: ExchangeMap,
+    /// Prefix of the table name, set from the top-level configuration.
+    table_prefix: Option<String>,
+}
+
+/// Exchanges of a coin as given in the configuration file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawExchanges {
+    /// Exchange names mapped to the symbols of the coin.
+    Symbols(ExchangeMap),
+    /// Exchange names with the symbols derived from the coin.
+    Derived(Vec<Exchange>),
+}
+
+/// Coin configuration as deserialized, before deriving the symbols.
+#[derive(Deserialize)]
+struct RawCoinConfig {
+    symbol: String,
+    name: String,
+    currency: Currency,
+    exchanges: RawExchanges,
+}
+
+impl From<RawCoinConfig> for CoinConfig {
+    fn from(config: RawCoinConfig) -> Self {
+        let exchanges = match config.exchanges {
+            RawExchanges::Symbols(symbols) => symbols,
+            RawExchanges::Derived(exchanges) => exchanges
+                .into_iter()
+                .map(|exchange| {
+                    let symbol = exchange.format_symbol(&config.symbol, config.currency);
+                    (exchange, symbol)
+                })
+                .collect(),
+        };
+
+        Self {
+            symbol: config.symbol,
+            name: config.name,
+            currency: config.currency,
+            exchanges,
+            table_prefix: None,
+        }
+    }
 }
 
 impl CoinConfig {
     /// Convert the configuration into a [`Coin`] instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the symbol or the table prefix of the coin is
+    /// invalid.
+    pub fn as_coin(&self) -> Result<Coin, ohlcv::Error> {
+        let coin = Coin::new(self.symbol.clone(), self.name.clone(), self.currency)?;
+
+        match &self.table_prefix {
+            Some(prefix) => coin.with_table_prefix(prefix.clone()),
+            None => Ok(coin),
+        }
+    }
+
+    /// Find the exchanges quoting the coin in another currency.
+    ///
+    /// The quote currency is inferred from the symbol on the exchange with
+    /// [`Currency::from_pair`]. Symbols without a known quote currency are
+    /// not reported. Returns the exchanges sorted by name together with their
+    /// quote currency.
     #[must_use]
-    pub fn as_coin(&self) -> ohlcv::Coin {
-        Coin::new(self.symbol.clone(), self.name.clone(), self.currency)
+    pub fn currency_mismatches(&self) -> Vec<(Exchange, Currency)> {
+        let mut mismatches = self
+            .exchanges
+            .iter()
+            .filter_map(|(exchange, symbol)| {
+                Currency::from_pair(symbol)
+                    .map(|(_, quote)| (*exchange, quote))
+                    .filter(|(_, quote)| *quote != self.currency)
+            })
+            .collect::<Vec<_>>();
+
+        mismatches.sort_unstable_by_key(|(exchange, _)| *exchange);
+        mismatches
     }
 }
 
+/// Map of exchange names to the rate limit of the exchange.
+pub type RateLimitMap = HashMap<Exchange, RateLimit>;
+
 /// Top-level configuration structure.
 #[derive(Debug, Deserialize)]
 pub struct Config {
     user_agent: Option<Box<str>>,
+    /// User agents overriding the user agent for single exchanges.
+    #[serde(default)]
+    user_agents: HashMap<Exchange, Box<str>>,
+    /// Rate limits overriding the defaults of the exchanges.
+    #[serde(default)]
+    pub rate_limits: RateLimitMap,
+    /// Retry policy for failed downloads.
+    #[serde(default)]
+    pub retry: Retry,
+    /// Timeout of a request to an exchange in seconds. Defaults to
+    /// [`Fetcher::DEFAULT_REQUEST_TIMEOUT`].
+    pub request_timeout_secs: Option<NonZero<u64>>,
+    /// Prefix of the table names, see [`Database::table_prefix`]. Several
+    /// datasets can share a database with different prefixes.
+    ///
+    /// [`Database::table_prefix`]: ohlcv::Database::table_prefix
+    table_prefix: Option<String>,
     /// Database connection information.
     pub database: DbType,
     /// List of coins to fetch.
@@ -55,25 +173,187 @@ pub struct Config {
 impl Config {
     /// Load the configuration from the specified file.
     ///
+    /// Without a path the file named by the environment variable
+    /// [`CONFIG_ENV`] is loaded, see [`config_path`]. Before parsing, every
+    /// `${NAME}` in the file is replaced by the value of the environment
+    /// variable `NAME`, see [`expand_env`].
+    ///
     /// # Errors
     ///
-    /// This function returns an error if the file cannot be read or if the
-    /// configuration is not valid TOML defined by the [`Config`] struct.
+    /// This function returns an error if the file cannot be read, if an
+    /// environment variable is not defined, if the configuration is not
+    /// valid TOML defined by the [`Config`] struct, if the table prefix is not
+    /// valid or if it fails the checks of [`Config::validate`].
     #[instrument]
     pub fn load(path: Option<impl AsRef<Path> + fmt::Debug>) -> Result<Self, Error> {
-        let path = path
-            .map(|p| p.as_ref().to_path_buf())
-            .or_else(|| {
-                CONFIG_PATHS
-                    .iter()
-                    .map(|p| Path::new(p).join(CONFIG_FILE))
-                    .find(|p| p.exists())
-            })
-            .ok_or_else(|| Error::ConfigFile)?;
+        let path = path.map(|path| path.as_ref().to_path_buf());
+        let path = config_path(path.as_deref(), |name| std::env::var(name).ok())?;
         info!("Loading configuration from {:?}", path);
         let source = std::fs::read_to_string(path)?;
+        let source = expand_env(&source, |name| std::env::var(name).ok())?;
+
+        let mut config = toml::from_str::<Self>(&source)?;
+
+        if let Some(prefix) = &config.table_prefix {
+            config.database = config.database.with_table_prefix(prefix.clone())?;
+            for coin_config in &mut config.coins {
+                coin_config.table_prefix = Some(prefix.clone());
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
 
-        toml::from_str(&source).map_err(Error::ConfigFormat)
+    /// Check the consistency of the configuration.
+    ///
+    /// The coins must be valid and no two coins may share the same table,
+    /// e.g. by having the same symbol and currency. A warning is logged for
+    /// every exchange quoting a coin in another currency than configured, see
+    /// [`CoinConfig::currency_mismatches`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a coin is invalid or if two coins share a table.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut tables = HashMap::<String, &str>::with_capacity(self.coins.len());
+
+        for coin_config in &self.coins {
+            let table = coin_config.as_coin()?.table_name();
+
+            if let Some(symbol) = tables.insert(table, &coin_config.symbol) {
+                return Err(Error::CoinDuplicate(
+                    symbol.into(),
+                    coin_config.symbol.clone(),
+                ));
+            }
+            for (exchange, quote) in coin_config.currency_mismatches() {
+                warn!(
+                    "Coin {} is configured in {} but quoted in {quote} on {exchange}",
+                    coin_config.symbol, coin_config.currency
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every exchange quotes the coins in the configured currency.
+    ///
+    /// Unlike [`Config::validate`] a mismatch is an error instead of a
+    /// warning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for the first exchange quoting a coin in another
+    /// currency.
+    pub fn check_currencies(&self) -> Result<(), Error> {
+        for coin_config in &self.coins {
+            if let Some((exchange, quote)) = coin_config.currency_mismatches().into_iter().next() {
+                return Err(Error::CurrencyMismatch(
+                    coin_config.symbol.clone(),
+                    coin_config.currency,
+                    exchange,
+                    quote,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Find a configured coin by its symbol.
+    ///
+    /// The symbol may be followed by the currency separated by a slash, e.g.
+    /// `BTC/USD`, to select one of several coins with the same symbol. The
+    /// comparison ignores the case.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no coin or more than one coin matches.
+    pub fn find_coin(&self, name: &str) -> Result<Coin, Error> {
+        Ok(self.coins[self.coin_index(name)?].as_coin()?)
+    }
+
+    /// Restrict the configured coins to the given names.
+    ///
+    /// The names are matched like in [`find_coin`](Self::find_coin). Without
+    /// names all coins are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a name matches no coin or more than one coin.
+    pub fn select_coins(&mut self, names: &[String]) -> Result<(), Error> {
+        if names.is_empty() {
+            return Ok(());
+        }
+
+        let selected = names
+            .iter()
+            .map(|name| self.coin_index(name))
+            .collect::<Result<HashSet<_>, _>>()?;
+        let mut indices = 0..;
+
+        self.coins.retain(|_| {
+            indices
+                .next()
+                .is_some_and(|index| selected.contains(&index))
+        });
+        Ok(())
+    }
+
+    /// Restrict the exchanges of the configured coins to the given exchanges.
+    ///
+    /// Coins without any of the exchanges are removed. Without exchanges all
+    /// exchanges are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an exchange is not configured for any coin.
+    pub fn select_exchanges(&mut self, exchanges: &[Exchange]) -> Result<(), Error> {
+        if exchanges.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(exchange) = exchanges.iter().find(|exchange| {
+            !self
+                .coins
+                .iter()
+                .any(|coin| coin.exchanges.contains_key(exchange))
+        }) {
+            return Err(Error::ExchangeUnknown(*exchange));
+        }
+        for coin in &mut self.coins {
+            coin.exchanges
+                .retain(|exchange, _| exchanges.contains(exchange));
+        }
+        self.coins.retain(|coin| !coin.exchanges.is_empty());
+        Ok(())
+    }
+
+    /// Index of the configured coin matching the name.
+    fn coin_index(&self, name: &str) -> Result<usize, Error> {
+        let mut coins = self
+            .coins
+            .iter()
+            .enumerate()
+            .filter(|(_, coin)| matches_name(&coin.symbol, coin.currency, name));
+
+        match (coins.next(), coins.next()) {
+            (Some((index, _)), None) => Ok(index),
+            (Some(_), Some(_)) => Err(Error::CoinAmbiguous(name.into())),
+            (None, _) => Err(Error::CoinUnknown(name.into())),
+        }
+    }
+
+    /// Get the timeout of a request to an exchange.
+    ///
+    /// Falls back to [`Fetcher::DEFAULT_REQUEST_TIMEOUT`] if no timeout is
+    /// configured.
+    #[must_use]
+    #[inline]
+    pub fn request_timeout(&self) -> Duration {
+        self.request_timeout_secs
+            .map_or(Fetcher::DEFAULT_REQUEST_TIMEOUT, |secs| {
+                Duration::from_secs(secs.get())
+            })
     }
 
     /// Get the user agent string to use for HTTP requests.
@@ -83,4 +363,453 @@ impl Config {
     pub fn user_agent(&self) -> &str {
         self.user_agent.as_deref().unwrap_or(USER_AGENT)
     }
+
+    /// Get the user agent string to use for HTTP requests to the exchange.
+    ///
+    /// Falls back to [`user_agent`](Self::user_agent) if no user agent is
+    /// configured for the exchange.
+    #[must_use]
+    #[inline]
+    #[instrument(skip(self))]
+    pub fn exchange_user_agent(&self, exchange: Exchange) -> &str {
+        self.user_agents
+            .get(&exchange)
+            .map_or_else(|| self.user_agent(), AsRef::as_ref)
+    }
+
+    /// Create the fetcher to download candles from the exchanges.
+    ///
+    /// The fetcher uses the configured user agents, request timeout, rate
+    /// limits and retry policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created.
+    pub fn fetcher(&self) -> Result<Fetcher, Error> {
+        let fetcher = Fetcher::new(self.user_agent())?
+            .with_request_timeout(self.request_timeout())?
+            .with_retry(self.retry);
+        let fetcher = self
+            .user_agents
+            .iter()
+            .fold(fetcher, |fetcher, (exchange, user_agent)| {
+                fetcher.with_user_agent(*exchange, user_agent)
+            });
+
+        Ok(self
+            .rate_limits
+            .iter()
+            .fold(fetcher, |fetcher, (exchange, limit)| {
+                fetcher.with_rate_limit(*exchange, *limit)
+            }))
+    }
+}
+
+/// Resolve the path of the configuration file.
+///
+/// The path is taken from the first of these sources:
+///
+/// 1. `path`, e.g. given by the option `--config`,
+/// 2. the variable [`CONFIG_ENV`] returned by `lookup`, unless it is empty,
+/// 3. the first existing [`CONFIG_FILE`] in the [`CONFIG_PATHS`].
+///
+/// A path given by the first two sources is returned even if the file does
+/// not exist, so loading it reports the missing file.
+///
+/// # Errors
+///
+/// Returns an error if no source provides a path.
+pub fn config_path(
+    path: Option<&Path>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<PathBuf, Error> {
+    path.map(Path::to_path_buf)
+        .or_else(|| {
+            lookup(CONFIG_ENV)
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+        })
+        .or_else(|| {
+            CONFIG_PATHS
+                .iter()
+                .map(|p| Path::new(p).join(CONFIG_FILE))
+                .find(|p| p.exists())
+        })
+        .ok_or(Error::ConfigFile)
+}
+
+/// Expand the environment variables in the source of the configuration.
+///
+/// Every `${NAME}` is replaced by the value of the variable `NAME` returned by
+/// `lookup`. Use `$${` for a literal `${`; an unclosed `${` is kept as is. The
+/// expansion is done on the raw text, so variables in comments must be
+/// defined as well.
+///
+/// # Errors
+///
+/// Returns an error if a variable is not defined.
+pub fn expand_env(source: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, Error> {
+    let mut expanded = String::with_capacity(source.len());
+    let mut rest = source;
+
+    while let Some(index) = rest.find("${") {
+        if rest[..index].ends_with('$') {
+            expanded.push_str(&rest[..index - 1]);
+            expanded.push_str("${");
+            rest = &rest[index + 2..];
+            continue;
+        }
+        expanded.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        let Some((name, tail)) = rest[2..].split_once('}') else {
+            break;
+        };
+        let value = lookup(name).ok_or_else(|| Error::ConfigVar(name.into()))?;
+
+        expanded.push_str(&value);
+        rest = tail;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Check if a coin matches a name given on the command line.
+///
+/// The name is the symbol, optionally followed by the currency separated by a
+/// slash, e.g. `BTC/USD`. The comparison ignores the case.
+#[must_use]
+pub fn matches_name(symbol: &str, currency: Currency, name: &str) -> bool {
+    let (name_symbol, name_currency) = name
+        .split_once('/')
+        .map_or((name, None), |(symbol, currency)| (symbol, Some(currency)));
+
+    symbol.eq_ignore_ascii_case(name_symbol)
+        && name_currency
+            .is_none_or(|name_currency| currency.to_string().eq_ignore_ascii_case(name_currency))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "DB_HOST" => Some("db.example.com".into()),
+            "DB_NAME" => Some("ohlcv".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn duplicate_coins() {
+        let source = r#"
+            [database]
+            type = "sqlite"
+            database = "ohlcv.db"
+
+            [[coins]]
+            name = "Bitcoin"
+            symbol = "BTC"
+            currency = "USD"
+            exchanges = { "Binance" = "BTCUSDC" }
+
+            [[coins]]
+            name = "Bitcoin (Kraken)"
+            symbol = "btc"
+            currency = "USD"
+            exchanges = { "Kraken" = "XBTUSD" }
+        "#;
+        let mut config = toml::from_str::<Config>(source).unwrap();
+
+        assert!(matches!(
+            config.validate(),
+            Err(Error::CoinDuplicate(first, second)) if first == "BTC" && second == "btc"
+        ));
+        config.coins.pop();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn user_agents() {
+        let source = r#"
+            user_agent = "collector/1.0"
+
+            [user_agents]
+            Kraken = "collector-kraken/1.0"
+
+            [database]
+            type = "sqlite"
+            database = "ohlcv.db"
+
+            [[coins]]
+            name = "Bitcoin"
+            symbol = "BTC"
+            currency = "USD"
+            exchanges = { "Kraken" = "XBTUSD" }
+        "#;
+        let mut config = toml::from_str::<Config>(source).unwrap();
+
+        assert_eq!(
+            config.exchange_user_agent(Exchange::Kraken),
+            "collector-kraken/1.0"
+        );
+        assert_eq!(
+            config.exchange_user_agent(Exchange::Binance),
+            "collector/1.0"
+        );
+        config.user_agent = None;
+        assert_eq!(config.exchange_user_agent(Exchange::Binance), USER_AGENT);
+    }
+
+    #[test]
+    fn request_timeout() {
+        let source = r#"
+            [database]
+            type = "sqlite"
+            database = "ohlcv.db"
+
+            [[coins]]
+            name = "Bitcoin"
+            symbol = "BTC"
+            currency = "USD"
+            exchanges = { "Kraken" = "XBTUSD" }
+        "#;
+        let config = toml::from_str::<Config>(source).unwrap();
+
+        assert_eq!(config.request_timeout(), Fetcher::DEFAULT_REQUEST_TIMEOUT);
+        let config =
+            toml::from_str::<Config>(&format!("request_timeout_secs = 10\n{source}")).unwrap();
+        assert_eq!(config.request_timeout(), Duration::from_secs(10));
+        assert!(toml::from_str::<Config>(&format!("request_timeout_secs = 0\n{source}")).is_err());
+    }
+
+    #[test]
+    fn currency_mismatches() {
+        let source = r#"
+            [database]
+            type = "sqlite"
+            database = "ohlcv.db"
+
+            [[coins]]
+            name = "Bitcoin"
+            symbol = "BTC"
+            currency = "USD"
+            exchanges = { "Binance" = "BTCUSDC", "Kraken" = "XBTUSD", "KuCoin" = "BTC-USDT" }
+
+            [[coins]]
+            name = "Ethereum"
+            symbol = "ETH"
+            currency = "EUR"
+            exchanges = { "Kraken" = "ETHEUR", "Coinbase" = "ETH-EURO" }
+        "#;
+        let mut config = toml::from_str::<Config>(source).unwrap();
+
+        assert_eq!(
+            config.coins[0].currency_mismatches(),
+            [
+                (Exchange::Binance, Currency::USDC),
+                (Exchange::KuCoin, Currency::USDT)
+            ]
+        );
+        assert_eq!(config.coins[1].currency_mismatches(), []);
+        assert!(config.validate().is_ok());
+        assert!(matches!(
+            config.check_currencies(),
+            Err(Error::CurrencyMismatch(symbol, Currency::USD, Exchange::Binance, Currency::USDC))
+                if symbol == "BTC"
+        ));
+        config.coins.remove(0);
+        assert!(config.check_currencies().is_ok());
+    }
+
+    #[test]
+    fn select_exchanges() {
+        let source = r#"
+            [database]
+            type = "sqlite"
+            database = "ohlcv.db"
+
+            [[coins]]
+            name = "Bitcoin"
+            symbol = "BTC"
+            currency = "USD"
+            exchanges = ["Coinbase", "Kraken"]
+
+            [[coins]]
+            name = "Ethereum"
+            symbol = "ETH"
+            currency = "USDT"
+            exchanges = ["Binance", "OKX"]
+        "#;
+        let mut config = toml::from_str::<Config>(source).unwrap();
+
+        assert!(matches!(
+            config.select_exchanges(&[Exchange::Kraken, Exchange::Bybit]),
+            Err(Error::ExchangeUnknown(Exchange::Bybit))
+        ));
+        config.select_exchanges(&[]).unwrap();
+        assert_eq!(config.coins.len(), 2);
+        config
+            .select_exchanges(&[Exchange::Kraken, Exchange::OKX])
+            .unwrap();
+        assert_eq!(
+            config
+                .coins
+                .iter()
+                .map(|coin| coin.exchanges.keys().copied().collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            [[Exchange::Kraken], [Exchange::OKX]]
+        );
+        config.select_exchanges(&[Exchange::OKX]).unwrap();
+        assert_eq!(config.coins.len(), 1);
+        assert_eq!(config.coins[0].symbol, "ETH");
+    }
+
+    #[test]
+    fn derived_symbols() {
+        let source = r#"
+            [database]
+            type = "sqlite"
+            database = "ohlcv.db"
+
+            [[coins]]
+            name = "Bitcoin"
+            symbol = "btc"
+            currency = "USDT"
+            exchanges = ["Binance", "Kraken", "OKX"]
+
+            [[coins]]
+            name = "Bitcoin"
+            symbol = "BTC"
+            currency = "USD"
+            exchanges = { "Kraken" = "XXBTZUSD" }
+        "#;
+        let config = toml::from_str::<Config>(source).unwrap();
+
+        assert_eq!(
+            config.coins[0].exchanges,
+            ExchangeMap::from([
+                (Exchange::Binance, "BTCUSDT".to_owned()),
+                (Exchange::Kraken, "XBTUSDT".to_owned()),
+                (Exchange::OKX, "BTC-USDT".to_owned()),
+            ])
+        );
+        assert_eq!(
+            config.coins[1].exchanges,
+            ExchangeMap::from([(Exchange::Kraken, "XXBTZUSD".to_owned())])
+        );
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn find_coin() {
+        let source = r#"
+            [database]
+            type = "sqlite"
+            database = "ohlcv.db"
+
+            [[coins]]
+            name = "Bitcoin"
+            symbol = "BTC"
+            currency = "USD"
+            exchanges = { "Binance" = "BTCUSDC" }
+
+            [[coins]]
+            name = "Bitcoin"
+            symbol = "BTC"
+            currency = "EUR"
+            exchanges = { "Kraken" = "XBTEUR" }
+
+            [[coins]]
+            name = "Ethereum"
+            symbol = "ETH"
+            currency = "USD"
+            exchanges = { "Binance" = "ETHUSDC" }
+        "#;
+        let mut config = toml::from_str::<Config>(source).unwrap();
+
+        assert_eq!(config.find_coin("eth").unwrap().symbol(), "ETH");
+        assert_eq!(
+            config.find_coin("btc/eur").unwrap().currency(),
+            Currency::EUR
+        );
+        assert!(matches!(
+            config.find_coin("BTC"),
+            Err(Error::CoinAmbiguous(name)) if name == "BTC"
+        ));
+        assert!(matches!(
+            config.find_coin("ETH/EUR"),
+            Err(Error::CoinUnknown(name)) if name == "ETH/EUR"
+        ));
+
+        assert!(matches!(
+            config.select_coins(&["eth".into(), "DOGE".into()]),
+            Err(Error::CoinUnknown(name)) if name == "DOGE"
+        ));
+        assert_eq!(config.coins.len(), 3);
+        config.select_coins(&[]).unwrap();
+        assert_eq!(config.coins.len(), 3);
+        config
+            .select_coins(&["eth".into(), "BTC/EUR".into()])
+            .unwrap();
+        assert_eq!(
+            config
+                .coins
+                .iter()
+                .map(|coin| (coin.symbol.as_str(), coin.currency))
+                .collect::<Vec<_>>(),
+            [("BTC", Currency::EUR), ("ETH", Currency::USD)]
+        );
+    }
+
+    #[test]
+    fn config_path() {
+        let env =
+            |value: &'static str| move |name: &str| (name == CONFIG_ENV).then(|| value.into());
+        let no_env = |_: &str| None;
+
+        assert_eq!(
+            super::config_path(Some(Path::new("cli.toml")), env("env.toml")).unwrap(),
+            Path::new("cli.toml")
+        );
+        assert_eq!(
+            super::config_path(None, env("env.toml")).unwrap(),
+            Path::new("env.toml")
+        );
+        // An empty variable falls back to the default paths.
+        assert_eq!(
+            super::config_path(None, env("")).ok(),
+            super::config_path(None, no_env).ok()
+        );
+        assert_eq!(
+            super::config_path(Some(Path::new("cli.toml")), no_env).unwrap(),
+            Path::new("cli.toml")
+        );
+    }
+
+    #[test]
+    fn expand() {
+        assert_eq!(
+            expand_env("host = \"${DB_HOST}\"\ndatabase = \"${DB_NAME}\"", lookup).unwrap(),
+            "host = \"db.example.com\"\ndatabase = \"ohlcv\""
+        );
+        assert_eq!(
+            expand_env("password = \"pa$$word$${DB_HOST}\"", lookup).unwrap(),
+            "password = \"pa$$word${DB_HOST}\""
+        );
+        assert_eq!(expand_env("no variables", lookup).unwrap(), "no variables");
+    }
+
+    #[test]
+    fn expand_missing() {
+        assert!(matches!(
+            expand_env("host = \"${DB_PORT}\"", lookup),
+            Err(Error::ConfigVar(name)) if name == "DB_PORT"
+        ));
+        assert_eq!(
+            expand_env("host = \"${DB_HOST\"", lookup).unwrap(),
+            "host = \"${DB_HOST\""
+        );
+    }
 }
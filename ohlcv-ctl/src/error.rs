@@ -1,17 +1,44 @@
 use std::{error::Error as StdError, fmt};
 
+use ohlcv::{Currency, Exchange};
+use time::{Date, OffsetDateTime};
+
 /// Error type for the CLI.
 #[derive(Debug)]
 #[allow(clippy::module_name_repetitions)]
 pub enum Error {
     /// Failed to ask password.
     AskPassword(String, Box<inquire::error::InquireError>),
+    /// Failed to ask for confirmation.
+    Confirm(Box<inquire::error::InquireError>),
+    /// More than one configured coin matches the name.
+    CoinAmbiguous(String),
+    /// Two coins of the configuration share the same table.
+    CoinDuplicate(String, String),
+    /// No configured coin matches the name.
+    CoinUnknown(String),
     /// Unknown command name.
     CommandName(String),
     /// Configuration file is missing.
     ConfigFile,
+    /// Date is not complete yet.
+    DateFuture(Date),
+    /// Start date is after the end date.
+    DateOrder(Date, Date),
+    /// Start of a time range is after its end.
+    TimestampOrder(OffsetDateTime, OffsetDateTime),
     /// Failed to parse configuration file.
     ConfigFormat(toml::de::Error),
+    /// Environment variable in the configuration file is not defined.
+    ConfigVar(String),
+    /// Exchange quotes a coin in another currency than configured.
+    CurrencyMismatch(String, Currency, Exchange, Currency),
+    /// Exchange is not configured for any coin.
+    ExchangeUnknown(Exchange),
+    /// Line of the imported candles duplicates the candle of an earlier line.
+    ImportDuplicate(usize, usize),
+    /// Line of the imported candles could not be parsed.
+    ImportLine(usize, String),
     /// Failed to read or write to a file.
     Io(std::io::Error),
     /// Error returned by the OHLCV crate.
@@ -22,8 +49,20 @@ impl StdError for Error {
     #[inline]
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
-            Self::AskPassword(_, err) => Some(err.as_ref()),
-            Self::CommandName(_) | Self::ConfigFile => None,
+            Self::AskPassword(_, err) | Self::Confirm(err) => Some(err.as_ref()),
+            Self::CoinAmbiguous(_)
+            | Self::CoinDuplicate(_, _)
+            | Self::CoinUnknown(_)
+            | Self::CommandName(_)
+            | Self::ConfigFile
+            | Self::ConfigVar(_)
+            | Self::CurrencyMismatch(..)
+            | Self::ExchangeUnknown(_)
+            | Self::DateFuture(_)
+            | Self::DateOrder(_, _)
+            | Self::TimestampOrder(_, _)
+            | Self::ImportDuplicate(_, _)
+            | Self::ImportLine(_, _) => None,
             Self::ConfigFormat(err) => Some(err),
             Self::Io(err) => Some(err),
             Self::Ohlcv(err) => Some(err),
@@ -37,9 +76,44 @@ impl fmt::Display for Error {
             Self::AskPassword(name, err) => {
                 write!(f, "Failed to ask password for '{name}': {err}")
             }
+            Self::Confirm(err) => write!(f, "Failed to ask for confirmation: {err}"),
+            Self::CoinAmbiguous(name) => write!(
+                f,
+                "Coin '{name}' is ambiguous, add the currency like '{name}/USD'"
+            ),
+            Self::CoinUnknown(name) => write!(f, "Coin '{name}' is not configured"),
+            Self::CoinDuplicate(first, second) => write!(
+                f,
+                "Coins '{first}' and '{second}' share the same table in the configuration"
+            ),
             Self::CommandName(name) => write!(f, "Unknown command name: '{name}'"),
             Self::ConfigFile => write!(f, "Configuration file is missing"),
+            Self::DateFuture(date) => {
+                write!(f, "Candles of {date} are not complete yet")
+            }
+            Self::DateOrder(from, to) => {
+                write!(f, "Start date {from} is after end date {to}")
+            }
+            Self::TimestampOrder(from, to) => {
+                write!(f, "Start {from} is after end {to}")
+            }
             Self::ConfigFormat(err) => err.fmt(f),
+            Self::ConfigVar(name) => write!(
+                f,
+                "Environment variable '{name}' of the configuration file is not defined"
+            ),
+            Self::ImportDuplicate(line, first) => write!(
+                f,
+                "Candle in line {line} has the same timestamp and timeframe as in line {first}"
+            ),
+            Self::ImportLine(line, reason) => write!(f, "Invalid candle in line {line}: {reason}"),
+            Self::CurrencyMismatch(symbol, currency, exchange, quote) => write!(
+                f,
+                "Coin '{symbol}' is configured in {currency} but quoted in {quote} on {exchange}"
+            ),
+            Self::ExchangeUnknown(exchange) => {
+                write!(f, "Exchange '{exchange}' is not configured for any coin")
+            }
             Self::Io(err) => err.fmt(f),
             Self::Ohlcv(err) => err.fmt(f),
         }
@@ -0,0 +1,340 @@
+//! File formats of exported and imported candles.
+
+use std::{
+    fmt,
+    io::{self, Write},
+    num::NonZero,
+    str::FromStr,
+};
+
+use ohlcv::{Candle, Timeframe};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime, UtcOffset};
+
+/// Header of the CSV format.
+const CSV_HEADER: &str = "timestamp,timeframe,sources,open,high,low,close,volume";
+
+/// Format of exported and imported candles.
+///
+/// In both formats the timestamp is written in RFC 3339 and the prices and
+/// volume are written as decimal strings without loss of precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Comma-separated values with a header line.
+    #[default]
+    Csv,
+    /// JSON Lines, one JSON object per candle.
+    Json,
+}
+
+impl Format {
+    /// The names of the formats as accepted by [`Format::from_str`].
+    pub const NAMES: [&'static str; 2] = ["csv", "json"];
+
+    /// The extension of files in the format.
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "jsonl",
+        }
+    }
+
+    /// Write the header preceding the candles, if the format has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header cannot be written.
+    pub fn write_header(self, writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            Self::Csv => writeln!(writer, "{CSV_HEADER}"),
+            Self::Json => Ok(()),
+        }
+    }
+
+    /// Write a single candle.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candle cannot be formatted or written.
+    pub fn write_candle(self, writer: &mut impl Write, candle: &Candle) -> io::Result<()> {
+        let timestamp = candle
+            .timestamp
+            .format(&Rfc3339)
+            .map_err(io::Error::other)?;
+
+        match self {
+            Self::Csv => writeln!(
+                writer,
+                "{timestamp},{},{},{},{},{},{},{}",
+                candle.timeframe,
+                candle.sources,
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+                candle.volume
+            ),
+            Self::Json => {
+                let record = Record {
+                    timestamp,
+                    timeframe: candle.timeframe.to_string(),
+                    sources: candle.sources(),
+                    open: candle.open,
+                    high: candle.high,
+                    low: candle.low,
+                    close: candle.close,
+                    volume: candle.volume,
+                };
+
+                serde_json::to_writer(&mut *writer, &record)?;
+                writeln!(writer)
+            }
+        }
+    }
+
+    /// Read a single candle from a line written by
+    /// [`write_candle`](Self::write_candle).
+    ///
+    /// Returns `None` for empty lines and the header line of the CSV format.
+    /// Timestamps with an offset are converted to UTC.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason if the line cannot be parsed or the candle is not
+    /// valid.
+    pub fn read_candle(self, line: &str) -> Result<Option<Candle>, String> {
+        let line = line.trim();
+
+        if line.is_empty() || (self == Self::Csv && line == CSV_HEADER) {
+            return Ok(None);
+        }
+
+        let record = match self {
+            Self::Csv => {
+                let values = line.split(',').collect::<Vec<_>>();
+                let [timestamp, timeframe, sources, open, high, low, close, volume] = values[..]
+                else {
+                    return Err(format!("expected 8 values, found {}", values.len()));
+                };
+                let decimal = |value: &str| {
+                    Decimal::from_str(value).map_err(|err| format!("value `{value}`: {err}"))
+                };
+
+                Record {
+                    timestamp: timestamp.to_owned(),
+                    timeframe: timeframe.to_owned(),
+                    sources: sources
+                        .parse()
+                        .map_err(|err| format!("sources `{sources}`: {err}"))?,
+                    open: decimal(open)?,
+                    high: decimal(high)?,
+                    low: decimal(low)?,
+                    close: decimal(close)?,
+                    volume: decimal(volume)?,
+                }
+            }
+            Self::Json => serde_json::from_str(line).map_err(|err| err.to_string())?,
+        };
+
+        record.into_candle().map(Some)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Csv => write!(f, "csv"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// Compression of exported and imported files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Plain text.
+    #[default]
+    None,
+    /// Gzip, requires the feature `gzip`.
+    #[cfg(feature = "gzip")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+    Gzip,
+}
+
+impl Compression {
+    /// The names of the compressions as accepted by [`Compression::from_str`].
+    #[cfg(feature = "gzip")]
+    pub const NAMES: [&'static str; 2] = ["none", "gzip"];
+
+    /// The extension appended to the names of compressed files, e.g. `gz`.
+    #[must_use]
+    pub const fn extension(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Some("gz"),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => write!(f, "gzip"),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            #[cfg(feature = "gzip")]
+            "gzip" => Ok(Self::Gzip),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// Candle as written in the JSON format.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    timestamp: String,
+    timeframe: String,
+    sources: usize,
+    open: Decimal,
+    high: Decimal,
+    low: Decimal,
+    close: Decimal,
+    volume: Decimal,
+}
+
+impl Record {
+    /// Convert the record into a validated candle.
+    fn into_candle(self) -> Result<Candle, String> {
+        let timestamp = OffsetDateTime::parse(&self.timestamp, &Rfc3339)
+            .map_err(|err| format!("timestamp `{}`: {err}", self.timestamp))?
+            .to_offset(UtcOffset::UTC);
+        let timeframe = self
+            .timeframe
+            .parse::<Timeframe>()
+            .map_err(|timeframe| format!("unknown timeframe `{timeframe}`"))?;
+        let mut candle = Candle::new(
+            timestamp,
+            timeframe,
+            self.open,
+            self.high,
+            self.low,
+            self.close,
+            self.volume,
+        )
+        .map_err(|err| err.to_string())?;
+
+        candle.sources = NonZero::new(self.sources).ok_or("sources must be positive")?;
+        Ok(candle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ohlcv::Timeframe;
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn write(format: Format) -> String {
+        let candle = Candle::new(
+            datetime!(2024-01-01 00:05 UTC),
+            Timeframe::FiveMinutes,
+            dec!(100.5),
+            dec!(110),
+            dec!(0.000000012345678901),
+            dec!(105),
+            dec!(5.25),
+        )
+        .unwrap();
+        let mut output = Vec::new();
+
+        format.write_header(&mut output).unwrap();
+        format.write_candle(&mut output, &candle).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            write(Format::Csv),
+            "timestamp,timeframe,sources,open,high,low,close,volume\n\
+            2024-01-01T00:05:00Z,5m,1,100.5,110,0.000000012345678901,105,5.25\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            write(Format::Json),
+            "{\"timestamp\":\"2024-01-01T00:05:00Z\",\"timeframe\":\"5m\",\"sources\":1,\
+            \"open\":\"100.5\",\"high\":\"110\",\"low\":\"0.000000012345678901\",\
+            \"close\":\"105\",\"volume\":\"5.25\"}\n"
+        );
+    }
+
+    #[test]
+    fn read() {
+        for format in [Format::Csv, Format::Json] {
+            let output = write(format);
+            let candles = output
+                .lines()
+                .map(|line| format.read_candle(line).unwrap())
+                .collect::<Vec<_>>();
+            let candle = candles.last().unwrap().unwrap();
+
+            assert_eq!(candles.len(), if format == Format::Csv { 2 } else { 1 });
+            assert_eq!(candle.timestamp, datetime!(2024-01-01 00:05 UTC));
+            assert_eq!(candle.timeframe, Timeframe::FiveMinutes);
+            assert_eq!(candle.sources(), 1);
+            assert_eq!(candle.low, dec!(0.000000012345678901));
+            assert_eq!(candle.volume, dec!(5.25));
+        }
+        assert_eq!(Format::Csv.read_candle(""), Ok(None));
+        assert!(Format::Csv
+            .read_candle("2024-01-01T00:05:00Z,5m,0,1,1,1,1,1")
+            .is_err());
+        assert!(Format::Csv
+            .read_candle("2024-01-01T00:05:00Z,5m,1,1,0,1,1,1")
+            .is_err());
+        assert!(Format::Json.read_candle("{}").is_err());
+    }
+
+    #[test]
+    fn compression() {
+        assert_eq!("none".parse(), Ok(Compression::None));
+        assert_eq!(Compression::None.extension(), None);
+        #[cfg(feature = "gzip")]
+        for name in Compression::NAMES {
+            assert_eq!(name.parse::<Compression>().unwrap().to_string(), name);
+        }
+        #[cfg(feature = "gzip")]
+        assert_eq!(Compression::Gzip.extension(), Some("gz"));
+        assert!("zip".parse::<Compression>().is_err());
+    }
+}
@@ -9,9 +9,9 @@
 //!     
 //! - [x] Initialize the database schema, command `init`.
 //! - [x] Drop the database schema, command `drop`.
-//! - [ ] Download historical OHLCV data, command `fetch`.
-//! - [ ] Export the data to a CSV or JSON file, command `export`.
-//! - [ ] Import the data from a CSV or JSON file, command `import`.
+//! - [x] Download historical OHLCV data, command `fetch`.
+//! - [x] Export the data to a CSV or JSON file, command `export`.
+//! - [x] Import the data from a CSV or JSON file, command `import`.
 //!
 //! ## Overview
 //!
@@ -19,10 +19,22 @@
 //! provides the following functionality:
 //!
 //! - Download historical OHLCV data from various cryptocurrency exchanges.
-//! - Export the data to a CSV file.
+//! - Export the data to a CSV or JSON file.
+//! - Import the data from a CSV or JSON file.
 //! - Initialize the database schema.
 //! - Drop the database schema.
 //!
+//! By default messages of the `info` level and above are logged. The
+//! `RUST_LOG` environment variable overrides the default, e.g.
+//! `RUST_LOG=ohlcv=debug`. The options `--verbose` and `--quiet` take
+//! precedence over `RUST_LOG`: `-v` logs debug messages, `-vv` trace messages
+//! and `-q` only warnings and errors.
+//!
+//! With `--log-format json` every message is logged as a JSON object on a line
+//! of its own, including the fields and spans of the message. This suits log
+//! aggregators and journald pipelines parsing JSON, e.g. for the daily run
+//! from cron. The default `text` is meant for interactive use.
+//!
 //! The `fetch` command is used to download historical OHLCV data from various
 //! cryptocurrency exchanges. The data is downloaded in a 5-minute interval of
 //! the previous day, resulting in 288 candles per day. The candles are
@@ -32,7 +44,9 @@
 //! The data can be downloaded for multiple trading pairs and multiple exchanges
 //! at the same time. The data is downloaded in parallel to speed up the
 //! process. Care is taken to avoid rate limiting and to handle errors
-//! gracefully.
+//! gracefully. The option `--parallelism` limits the number of trading pairs
+//! downloaded at the same time, by default four. Every request still respects
+//! the rate limit of its exchange.
 //!
 //! To get a consistent time-series of the data, the command line tool must be
 //! run at least once a day. The tool will download the data for the previous
@@ -40,12 +54,111 @@
 //! day, it will only download the missing trading pairs. All times are in UTC
 //! only.
 //!
+//! Trading pairs with all candles of a day already stored are skipped. With the
+//! `--force` option they are downloaded again and the stored candles of the day
+//! are replaced, e.g. after an exchange corrected its historical data. The log
+//! tells for every trading pair whether stored candles were overwritten.
+//!
+//! With the `--dry-run` option the data is downloaded, validated and merged,
+//! but not stored in the database. Instead a summary is logged for every
+//! trading pair.
+//!
+//! The progress is logged after every trading pair with the number of
+//! processed trading pairs and fetched candles. Use the global `--quiet`
+//! option to suppress these reports, e.g. when running from cron.
+//!
+//! To backfill historical data, a single day can be selected with `--date` or
+//! a range of days with `--from` and `--to`. The days are downloaded one after
+//! the other. Days that are not complete yet are rejected.
+//!
+//! To backfill a newly added coin, the download can be restricted to single
+//! coins with the repeatable option `--coin`, e.g. `--coin BTC --coin ETH/EUR`.
+//! The currency after the slash selects one of several coins with the same
+//! symbol. Coins that are not configured are rejected.
+//! Likewise, the repeatable option `--exchange` restricts the download to the
+//! given exchanges, e.g. `--exchange kraken`. Coins without any of them are
+//! skipped, exchanges not configured for any coin are rejected.
+//!
+//! The quote currency of a trading pair is inferred from its symbol on the
+//! exchange, e.g. `USDT` for `BTC-USDT`. If it differs from the configured
+//! currency of the coin, a warning is logged, as the candles would be stored
+//! under the wrong currency. With the `--strict` option the command fails
+//! instead.
+//!
+//! A long backfill can be interrupted with Ctrl-C. The pending downloads are
+//! cancelled, but trading pairs already downloaded are still stored. Every
+//! trading pair is stored in a single transaction, so no day is left
+//! half-written.
+//!
+//! For auditing, `--report FILE` writes a JSON summary of the run: per coin
+//! and day the candles, gaps and errors of every exchange, the candles after
+//! merging, the remaining gaps and whether the candles were stored. The file
+//! is replaced atomically, also if the run is interrupted or fails.
+//!
 //! The `init` command is used to initialize the database schema. The schema
 //! includes tables for the candles of the trading pairs.
+//! The version of the schema is recorded, and a database initialized by a
+//! newer release is left untouched.
+//! The `--sql-only` option prints the statements to stdout instead of
+//! executing them, e.g. to review them or to apply them with a migration
+//! tool. No connection to the database is made then.
 //!
 //! The `drop` command is used to drop the database schema. This will remove the
 //! tables and data from the database of the defined trading pairs. If the
-//! `--all` option is used, all tables for all coins will be removed.
+//! `--all` option is used, all tables for all coins will be removed. The
+//! repeatable option `--coin` removes only the tables of the given coins, e.g.
+//! `--coin BTC/USD`. Coins removed from the configuration are looked up in the
+//! database.
+//! Before dropping, the command asks for confirmation showing the number of
+//! tables to remove. Use the `--yes` option to skip the prompt in scripts.
+//!
+//! The `export` command writes the candles of a configured coin to a file or
+//! to stdout. The coin is selected by its symbol, optionally followed by the
+//! currency, e.g. `--coin BTC/USD`. The candles are written as CSV with a
+//! header line or as JSON Lines with one object per candle. Timestamps are
+//! written in RFC 3339 and prices as exact decimals. The candles are streamed
+//! from the database, so large tables can be exported without loading them
+//! into memory. Log messages are written to stderr and do not mix with the
+//! exported candles.
+//!
+//! The options `--from` and `--to` limit the export to a time range given in
+//! RFC 3339, e.g. `--from 2024-01-01T00:00:00Z --to 2024-02-01T00:00:00Z` for
+//! the candles of January. The start is inclusive, so the candle containing it
+//! is exported, and the end is exclusive. Without the options all candles up to
+//! the last completed one are exported.
+//!
+//! The option `--all` exports every configured coin into its own file in the
+//! directory given by `--output-dir`, e.g. for periodic backups. The files are
+//! named by the table of the coin and the extension of the format, like
+//! `candles_btc_usd.csv` or `candles_btc_usd.jsonl`. Without `--timeframe` the
+//! files hold the candles of all timeframes. The number of exported candles is
+//! logged per coin.
+//!
+//! With the feature `gzip` enabled at compilation, e.g. `cargo install
+//! ohlcv-ctl --features gzip`, the option `--compress gzip` compresses the
+//! exported candles. The compression is streamed like the export, so the memory
+//! usage stays bounded. The files of `--all` then get the extension `.gz`
+//! appended.
+//!
+//! The `import` command reads the candles of a configured coin in the formats
+//! written by the `export` command. The coin is selected with `--coin` like for
+//! the export. If the file is `-`, the candles are read from stdin, so the
+//! commands can be combined in shell pipelines, e.g.
+//! `curl ... | ohlcv-ctl import - --coin BTC`. The input is read line by line
+//! and inserted in batches. To detect duplicates, the timestamp, timeframe and
+//! line number of every candle are kept, so the memory usage grows with the
+//! number of candles, but far less than the file. Existing candles with the
+//! same timestamp and timeframe are replaced.
+//!
+//! Duplicate candles within the input, i.e. lines with the same timestamp and
+//! timeframe, abort the import by default, so corrupt files are noticed before
+//! the database replaces the first candle. The option `--on-duplicate skip`
+//! keeps the first candle and `--on-duplicate overwrite` the last one, both
+//! with a warning naming the lines. Candles that are not in ascending order of
+//! their timestamps are imported, but reported with a warning.
+//!
+//! With the feature `gzip`, files ending with `.gz` are decompressed while
+//! reading.
 //!
 //! ## Configuration
 //!
@@ -58,27 +171,91 @@
 //! # will be used.
 //! user_agent = "<optional user-agent>"
 //!
+//! # Optional prefix of the table names, `candles` by default. It must be a
+//! # lowercase letter followed by lowercase letters and digits. Different prefixes
+//! # let several datasets share a database.
+//! # table_prefix = "ohlcv"
+//!
+//! # Optional timeout of a request to an exchange in seconds, 30 by default.
+//! # A timed out request is retried like a failed one.
+//! # request_timeout_secs = 30
+//!
+//! # Optional user agents overriding `user_agent` for single exchanges.
+//! [user_agents]
+//! Kraken = "<optional user-agent>"
+//!
+//! # Optional rate limits overriding the defaults of the exchanges.
+//! [rate_limits]
+//! Binance = { requests = 600, seconds = 60 }
+//!
+//! # Optional retry policy for failed downloads. The delay in milliseconds
+//! # before the first retry doubles with every further retry.
+//! [retry]
+//! attempts = 3
+//! delay_ms = 1000
+//!
 //! [database]
 //! type = "mysql"
 //! address = "localhost"
 //! database = "ohlcv"
 //! username = "<ohlcv user>"
 //! password = "<secret password>"
+//! # Instead of the password, a file containing the password may be given.
+//! # password_file = "/run/secrets/ohlcv-password"
+//! # Instead of the connection fields above, a database URL may be given.
+//! # url = "mysql://<ohlcv user>:<secret password>@localhost/ohlcv"
+//! # Optional TLS settings for MySQL and PostgreSQL.
+//! # ssl_mode = "required"
+//! # ssl_ca = "/etc/ssl/certs/db-ca.pem"
+//! # Optional size of the connection pool and connect timeout in seconds.
+//! # max_connections = 5
+//! # connect_timeout_secs = 30
+//! # Optional read-only access and journal mode for SQLite.
+//! # read_only = true
+//! # journal_mode = "wal"
+//! # Optional digits and fractional digits of the prices for MySQL and PostgreSQL.
+//! # price_precision = 20
+//! # price_scale = 10
 //!
 //! [[coins]]
 //! name = "Bitcoin"
 //! symbol = "BTC"
-//! currency = "USD"
+//! currency = "USDC"
 //! exchanges = { "Binance" = "BTCUSDC" }
+//!
+//! [[coins]]
+//! name = "Ethereum"
+//! symbol = "ETH"
+//! currency = "USDT"
+//! # Instead of the symbols on the exchanges, a list of exchanges may be given.
+//! # The symbols are derived from the symbol and currency of the coin, e.g.
+//! # `ETHUSDT` on Binance, `ETH-USDT` on OKX and `XBTUSDT` for Bitcoin on Kraken.
+//! exchanges = ["Binance", "OKX"]
 //! ```
 //!
 //! See the implementation of the database configuration for more details about
 //! the fields in the `OHLCV` crate.
+//!
+//! The configuration file is looked up in this order:
+//!
+//! 1. the path given by the option `--config`,
+//! 2. the path in the environment variable `OHLCV_CONFIG`, unless it is empty,
+//! 3. the file `ohlcv-ctl.toml` in the current working directory or in
+//!    `/etc/ohlcv`.
+//!
+//! Environment variables can be referenced as `${NAME}` anywhere in the
+//! configuration file, e.g. `host = "${DB_HOST}"`. They are expanded before
+//! the file is parsed, so an expanded `password` takes precedence over
+//! `password_file` and the variable `OHLCV_<username>_PASSWORD`. Loading fails
+//! if a referenced variable is not defined, including variables in comments.
+//! Use `$${` for a literal `${`.
 
 mod cli;
-pub use cli::{clargs, command};
+pub use cli::{clargs, command, log_filter, log_format, LogFormat};
 
 pub mod config;
 
 mod error;
 pub use error::Error;
+
+pub mod format;
@@ -1,7 +1,6 @@
 #![allow(clippy::doc_markdown, clippy::multiple_crate_versions)]
 
-use ohlcv_ctl::{clargs, command};
-use tracing::Level;
+use ohlcv_ctl::{clargs, command, log_filter, log_format, LogFormat};
 use tracing_subscriber::FmtSubscriber;
 
 #[cfg(not(any(feature = "mysql", feature = "postgres", feature = "sqlite")))]
@@ -9,16 +8,18 @@ compile_error!("At least one of the features 'mysql', 'postgres', or 'sqlite' mu
 
 #[tokio::main]
 async fn main() {
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::TRACE)
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
-
     let matches = clargs();
-    let command = matches.subcommand();
+    let builder = FmtSubscriber::builder()
+        .with_env_filter(log_filter(&matches))
+        .with_writer(std::io::stderr);
+    let result = match log_format(&matches) {
+        LogFormat::Text => tracing::subscriber::set_global_default(builder.finish()),
+        LogFormat::Json => tracing::subscriber::set_global_default(builder.json().finish()),
+    };
+
+    result.expect("setting default subscriber failed");
 
-    if let Err(err) = command::execute(command).await {
+    if let Err(err) = command::execute(&matches).await {
         eprintln!("Error: {err}");
     }
 }
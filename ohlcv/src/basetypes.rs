@@ -18,6 +18,76 @@ pub enum Currency {
     GBP,
     /// Japanese Yen
     JPY,
+    /// Tether, a stablecoin pegged to the US-Dollar
+    USDT,
+    /// USD Coin, a stablecoin pegged to the US-Dollar
+    USDC,
+}
+
+impl Currency {
+    /// Get the symbol of the currency, e.g. `$` for the US-Dollar.
+    #[must_use]
+    pub const fn symbol(&self) -> &'static str {
+        match self {
+            Self::USD | Self::USDC => "$",
+            Self::EUR => "€",
+            Self::GBP => "£",
+            Self::JPY => "¥",
+            Self::USDT => "₮",
+        }
+    }
+
+    /// Get the numeric code of the currency according to ISO 4217.
+    ///
+    /// Returns `None` for currencies without an ISO code, like stablecoins.
+    #[must_use]
+    pub const fn iso_numeric(&self) -> Option<u16> {
+        match self {
+            Self::USD => Some(840),
+            Self::EUR => Some(978),
+            Self::GBP => Some(826),
+            Self::JPY => Some(392),
+            Self::USDT | Self::USDC => None,
+        }
+    }
+
+    /// Split the symbol of a trading pair on an exchange into the base symbol
+    /// and the quote currency.
+    ///
+    /// The quote currency is recognized by its code at the end of the symbol,
+    /// ignoring case. Longer codes are tried first, so `BTCUSDT` is quoted in
+    /// [`USDT`](Self::USDT) and not in [`USD`](Self::USD). Separators between
+    /// base and quote, like in `ETH-USDT` or `ETH/USDT`, are removed from the
+    /// base symbol.
+    ///
+    /// Returns `None` if the symbol does not end with a known currency or the
+    /// base symbol is empty.
+    ///
+    /// ```
+    /// # use ohlcv::Currency;
+    /// assert_eq!(Currency::from_pair("BTCUSDC"), Some(("BTC", Currency::USDC)));
+    /// assert_eq!(Currency::from_pair("ETH-USD"), Some(("ETH", Currency::USD)));
+    /// assert_eq!(Currency::from_pair("BTCCHF"), None);
+    /// ```
+    #[must_use]
+    pub fn from_pair(symbol: &str) -> Option<(&str, Self)> {
+        const BY_LENGTH: [Currency; 6] = [
+            Currency::USDT,
+            Currency::USDC,
+            Currency::USD,
+            Currency::EUR,
+            Currency::GBP,
+            Currency::JPY,
+        ];
+
+        BY_LENGTH.into_iter().find_map(|currency| {
+            let code = currency.to_string();
+            let (base, quote) = symbol.split_at_checked(symbol.len().checked_sub(code.len())?)?;
+            let base = base.trim_end_matches(['-', '/', '_', ':']);
+
+            (quote.eq_ignore_ascii_case(&code) && !base.is_empty()).then_some((base, currency))
+        })
+    }
 }
 
 impl fmt::Display for Currency {
@@ -27,6 +97,8 @@ impl fmt::Display for Currency {
             Self::EUR => write!(f, "EUR"),
             Self::GBP => write!(f, "GBP"),
             Self::JPY => write!(f, "JPY"),
+            Self::USDT => write!(f, "USDT"),
+            Self::USDC => write!(f, "USDC"),
         }
     }
 }
@@ -40,6 +112,8 @@ impl FromStr for Currency {
             "EUR" => Ok(Self::EUR),
             "GBP" => Ok(Self::GBP),
             "JPY" => Ok(Self::JPY),
+            "USDT" => Ok(Self::USDT),
+            "USDC" => Ok(Self::USDC),
             _ => Err(s.to_string()),
         }
     }
@@ -48,8 +122,9 @@ impl FromStr for Currency {
 /// The type of timeframe.
 ///
 /// Timeframes are used to group the data into intervals of time.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Timeframe {
+    #[default]
     #[serde(alias = "5m")]
     FiveMinutes,
     #[serde(alias = "15m")]
@@ -69,6 +144,17 @@ const DURATION_4H: Duration = Duration::from_secs(4 * 60 * 60);
 const DURATION_1D: Duration = Duration::from_secs(24 * 60 * 60);
 
 impl Timeframe {
+    /// All timeframes in ascending order of their duration.
+    pub const ALL: [Self; 5] = [
+        Self::FiveMinutes,
+        Self::Quarters,
+        Self::OneHour,
+        Self::FourHours,
+        Self::OneDay,
+    ];
+    /// The names of the timeframes as accepted by [`Timeframe::from_str`].
+    pub const NAMES: [&'static str; 5] = ["5m", "15m", "1h", "4h", "1d"];
+
     /// Get the duration of the timeframe.
     #[must_use]
     pub const fn duration(&self) -> Duration {
@@ -81,6 +167,37 @@ impl Timeframe {
         }
     }
 
+    /// Get the number of candles of the timeframe in a day, e.g. 288 for
+    /// [`FiveMinutes`](Self::FiveMinutes).
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn candles_per_day(&self) -> u32 {
+        (DURATION_1D.as_secs() / self.duration().as_secs()) as u32
+    }
+
+    /// Check if the duration of the timeframe evenly divides the duration of
+    /// the `other` timeframe.
+    ///
+    /// Candles of the timeframe can then be aggregated into candles of the
+    /// `other` timeframe, e.g. 5-minute candles into 1-hour candles. Every
+    /// timeframe subdivides itself.
+    #[must_use]
+    pub const fn subdivides(&self, other: Self) -> bool {
+        other
+            .duration()
+            .as_secs()
+            .is_multiple_of(self.duration().as_secs())
+    }
+
+    /// Check if a candle of the timeframe consists of whole candles of the
+    /// `other` timeframe.
+    ///
+    /// This is the reverse of [`subdivides`](Self::subdivides).
+    #[must_use]
+    pub const fn contains(&self, other: Self) -> bool {
+        other.subdivides(*self)
+    }
+
     /// Round the given time down to the nearest timeframe.
     #[must_use]
     #[allow(clippy::missing_panics_doc, clippy::cast_possible_wrap)]
@@ -94,12 +211,18 @@ impl Timeframe {
     }
 
     /// Round the given time up to the nearest timeframe.
+    ///
+    /// Like [`round_down`](Self::round_down), a time already on a timeframe
+    /// boundary is returned unchanged.
     #[must_use]
     #[allow(clippy::missing_panics_doc, clippy::cast_possible_wrap)]
     pub fn round_up(&self, time: OffsetDateTime) -> OffsetDateTime {
         let duration = self.duration().as_secs() as i64;
         let seconds = time.unix_timestamp();
-        let seconds = seconds + duration - seconds.rem_euclid(duration);
+        let seconds = match seconds.rem_euclid(duration) {
+            0 if time.nanosecond() == 0 => seconds,
+            remainder => seconds + duration - remainder,
+        };
 
         // This always succeeds, as the seconds are valid.
         OffsetDateTime::from_unix_timestamp(seconds).unwrap()
@@ -107,30 +230,89 @@ impl Timeframe {
 
     /// Return the start and end time of range.
     ///
-    /// The start time is rounded down to the nearest timeframe if the bound is
-    /// included and rounded up if the bound is excluded. The end time is
-    /// rounded up to the nearest timeframe if the bound is included and rounded
-    /// down if the bound is excluded. If unbound the start time is the start of
-    /// the Unix epoch and the end time is the end of the current excluded
-    /// timeframe.
+    /// The returned times are timeframe boundaries. The start is included and
+    /// the end is excluded, so the candles with a timestamp from the start up
+    /// to before the end are the candles containing any time of the range:
+    ///
+    /// * An included or excluded start is rounded down, as the candle
+    ///   containing it also contains the times right after it.
+    /// * An included end is rounded down and advanced by one timeframe, so the
+    ///   candle containing it is part of the range.
+    /// * An excluded end is rounded up, so the candle containing the times
+    ///   right before it is part of the range. An end already on a boundary is
+    ///   kept, as the candle starting there is not part of the range.
+    ///
+    /// So the range `t1..=t2` includes the candle at `round_down(t1)` and the
+    /// candle at `round_down(t2)`. If `t2` is within a candle, the candle at
+    /// `round_up(t2)` is not included, as it contains no time of the range.
+    ///
+    /// If unbound the start time is the start of the Unix epoch and the end
+    /// time is the start of the current timeframe, so the incomplete current
+    /// candle is excluded.
+    ///
+    /// An empty range, e.g. `t..t` or a start after the end, returns the
+    /// rounded start as both start and end, so it contains no candles.
     #[must_use]
     pub fn range<R>(&self, range: R) -> (OffsetDateTime, OffsetDateTime)
     where
         R: RangeBounds<OffsetDateTime>,
     {
         let start = match range.start_bound() {
-            std::ops::Bound::Included(start) => self.round_down(*start),
-            std::ops::Bound::Excluded(start) => self.round_up(*start),
+            std::ops::Bound::Included(start) | std::ops::Bound::Excluded(start) => {
+                self.round_down(*start)
+            }
             std::ops::Bound::Unbounded => OffsetDateTime::UNIX_EPOCH,
         };
 
         let end = match range.end_bound() {
-            std::ops::Bound::Included(end) => self.round_up(*end),
-            std::ops::Bound::Excluded(end) => self.round_down(*end),
+            std::ops::Bound::Included(end) => self.round_down(*end) + self.duration(),
+            std::ops::Bound::Excluded(end) => self.round_up(*end),
             std::ops::Bound::Unbounded => self.round_down(OffsetDateTime::now_utc()),
         };
 
-        (start, end)
+        let empty = match (range.start_bound(), range.end_bound()) {
+            (std::ops::Bound::Included(first), std::ops::Bound::Included(last)) => first > last,
+            (
+                std::ops::Bound::Included(first) | std::ops::Bound::Excluded(first),
+                std::ops::Bound::Included(last) | std::ops::Bound::Excluded(last),
+            ) => first >= last,
+            _ => false,
+        };
+
+        if empty {
+            (start, start)
+        } else {
+            (start, end.max(start))
+        }
+    }
+
+    /// Iterate over the start times of the candles of the timeframe in the
+    /// range.
+    ///
+    /// The start times are the timeframe boundaries within the range in
+    /// ascending order, e.g. to enumerate the expected candles of a day and
+    /// find the missing ones. If unbound the range starts at the Unix epoch
+    /// and ends before the current timeframe, like [`range`](Self::range).
+    pub fn iter_slots<R>(&self, range: R) -> impl Iterator<Item = OffsetDateTime>
+    where
+        R: RangeBounds<OffsetDateTime>,
+    {
+        let start = match range.start_bound() {
+            std::ops::Bound::Included(start) => self.round_up(*start),
+            std::ops::Bound::Excluded(start) => self.round_down(*start) + self.duration(),
+            std::ops::Bound::Unbounded => OffsetDateTime::UNIX_EPOCH,
+        };
+        let end = match range.end_bound() {
+            std::ops::Bound::Included(end) => std::ops::Bound::Included(*end),
+            std::ops::Bound::Excluded(end) => std::ops::Bound::Excluded(*end),
+            std::ops::Bound::Unbounded => {
+                std::ops::Bound::Excluded(self.round_down(OffsetDateTime::now_utc()))
+            }
+        };
+        let duration = self.duration();
+
+        std::iter::successors(Some(start), move |slot| Some(*slot + duration))
+            .take_while(move |slot| (std::ops::Bound::Unbounded, end).contains(slot))
     }
 }
 
@@ -156,7 +338,7 @@ impl Ord for Timeframe {
 impl PartialOrd for Timeframe {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.duration().cmp(&other.duration()))
+        Some(self.cmp(other))
     }
 }
 
@@ -190,8 +372,241 @@ impl TryFrom<Duration> for Timeframe {
     }
 }
 
-impl Default for Timeframe {
-    fn default() -> Self {
-        Self::FiveMinutes
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn candles_per_day() {
+        assert_eq!(Timeframe::FiveMinutes.candles_per_day(), 288);
+        assert_eq!(Timeframe::Quarters.candles_per_day(), 96);
+        assert_eq!(Timeframe::OneHour.candles_per_day(), 24);
+        assert_eq!(Timeframe::FourHours.candles_per_day(), 6);
+        assert_eq!(Timeframe::OneDay.candles_per_day(), 1);
+    }
+
+    #[test]
+    fn subdivides() {
+        use Timeframe::{FiveMinutes, FourHours, OneDay, OneHour, Quarters};
+
+        let timeframes = [FiveMinutes, Quarters, OneHour, FourHours, OneDay];
+
+        for (index, timeframe) in timeframes.iter().enumerate() {
+            for (other_index, other) in timeframes.iter().enumerate() {
+                assert_eq!(
+                    timeframe.subdivides(*other),
+                    index <= other_index,
+                    "{timeframe:?} subdivides {other:?}"
+                );
+                assert_eq!(
+                    other.contains(*timeframe),
+                    timeframe.subdivides(*other),
+                    "{other:?} contains {timeframe:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn currency() {
+        let currencies = [
+            (Currency::USD, "USD", "$", Some(840)),
+            (Currency::EUR, "EUR", "€", Some(978)),
+            (Currency::GBP, "GBP", "£", Some(826)),
+            (Currency::JPY, "JPY", "¥", Some(392)),
+            (Currency::USDT, "USDT", "₮", None),
+            (Currency::USDC, "USDC", "$", None),
+        ];
+
+        for (currency, code, symbol, numeric) in currencies {
+            assert_eq!(currency.to_string(), code);
+            assert_eq!(code.parse::<Currency>(), Ok(currency));
+            assert_eq!(currency.symbol(), symbol);
+            assert_eq!(currency.iso_numeric(), numeric);
+        }
+    }
+
+    #[test]
+    fn from_pair() {
+        assert_eq!(
+            Currency::from_pair("BTCUSDT"),
+            Some(("BTC", Currency::USDT))
+        );
+        assert_eq!(Currency::from_pair("BTCUSD"), Some(("BTC", Currency::USD)));
+        assert_eq!(
+            Currency::from_pair("ETH-USDC"),
+            Some(("ETH", Currency::USDC))
+        );
+        assert_eq!(Currency::from_pair("xbt/eur"), Some(("xbt", Currency::EUR)));
+        assert_eq!(
+            Currency::from_pair("1INCH_JPY"),
+            Some(("1INCH", Currency::JPY))
+        );
+        assert_eq!(Currency::from_pair("USDT"), None);
+        assert_eq!(Currency::from_pair("-USD"), None);
+        assert_eq!(Currency::from_pair("BTCCHF"), None);
+        assert_eq!(Currency::from_pair("€UR"), None);
+    }
+
+    #[test]
+    fn iter_slots() {
+        let slots = Timeframe::Quarters
+            .iter_slots(datetime!(2024-01-01 00:10 UTC)..datetime!(2024-01-01 01:00 UTC))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            slots,
+            [
+                datetime!(2024-01-01 00:15 UTC),
+                datetime!(2024-01-01 00:30 UTC),
+                datetime!(2024-01-01 00:45 UTC)
+            ]
+        );
+
+        let slots = Timeframe::Quarters
+            .iter_slots(datetime!(2024-01-01 00:15 UTC)..=datetime!(2024-01-01 00:45 UTC))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            slots,
+            [
+                datetime!(2024-01-01 00:15 UTC),
+                datetime!(2024-01-01 00:30 UTC),
+                datetime!(2024-01-01 00:45 UTC)
+            ]
+        );
+
+        let slots = Timeframe::Quarters
+            .iter_slots((
+                std::ops::Bound::Excluded(datetime!(2024-01-01 00:15 UTC)),
+                std::ops::Bound::Excluded(datetime!(2024-01-01 00:45 UTC)),
+            ))
+            .collect::<Vec<_>>();
+        assert_eq!(slots, [datetime!(2024-01-01 00:30 UTC)]);
+
+        let start = datetime!(2024-01-01 00:00 UTC);
+        assert_eq!(
+            Timeframe::FiveMinutes
+                .iter_slots(start..start + Timeframe::OneDay.duration())
+                .count(),
+            288
+        );
+        assert_eq!(Timeframe::OneHour.iter_slots(start..start).count(), 0);
+    }
+
+    #[test]
+    fn round() {
+        let aligned = datetime!(2024-01-01 12:00 UTC);
+        let unaligned = datetime!(2024-01-01 12:00:01 UTC);
+
+        assert_eq!(Timeframe::OneHour.round_down(aligned), aligned);
+        assert_eq!(Timeframe::OneHour.round_up(aligned), aligned);
+        assert_eq!(Timeframe::OneHour.round_down(unaligned), aligned);
+        assert_eq!(
+            Timeframe::OneHour.round_up(unaligned),
+            datetime!(2024-01-01 13:00 UTC)
+        );
+        assert_eq!(
+            Timeframe::OneHour.round_up(datetime!(2024-01-01 12:00:00.5 UTC)),
+            datetime!(2024-01-01 13:00 UTC)
+        );
+        assert_eq!(
+            Timeframe::OneDay.round_up(datetime!(2024-01-01 12:00 +02:00)),
+            datetime!(2024-01-02 00:00 UTC)
+        );
+    }
+
+    #[test]
+    fn range() {
+        use std::ops::Bound::{Excluded, Included};
+
+        let hour = Timeframe::OneHour;
+        let t1 = datetime!(2024-01-01 12:00 UTC);
+        let t2 = datetime!(2024-01-01 14:00 UTC);
+        let u1 = datetime!(2024-01-01 12:30 UTC);
+        let u2 = datetime!(2024-01-01 13:30 UTC);
+
+        // Bounds on timeframe boundaries.
+        assert_eq!(hour.range(t1..t2), (t1, t2));
+        assert_eq!(hour.range(t1..=t2), (t1, t2 + hour.duration()));
+        assert_eq!(hour.range((Excluded(t1), Excluded(t2))), (t1, t2));
+        assert_eq!(
+            hour.range((Excluded(t1), Included(t2))),
+            (t1, t2 + hour.duration())
+        );
+
+        // Bounds within candles include the candles containing them.
+        assert_eq!(hour.range(u1..u2), (t1, t2));
+        assert_eq!(hour.range(u1..=u2), (t1, t2));
+        assert_eq!(hour.range((Excluded(u1), Excluded(u2))), (t1, t2));
+        assert_eq!(hour.range((Excluded(u1), Included(u2))), (t1, t2));
+
+        // An included end within a candle includes the candle at the rounded
+        // down end, but not the candle at the rounded up end.
+        let (start, end) = hour.range(u1..=u2);
+        assert!(start <= hour.round_down(u1) && hour.round_down(u1) < end);
+        assert!(start <= hour.round_down(u2) && hour.round_down(u2) < end);
+        assert_eq!(hour.round_up(u2), end);
+
+        // A single time within a candle contains that candle.
+        assert_eq!(hour.range(u1..=u1), (t1, datetime!(2024-01-01 13:00 UTC)));
+
+        // Empty ranges contain no candles.
+        assert_eq!(hour.range(u1..u1), (t1, t1));
+        assert_eq!(hour.range(t1..t1), (t1, t1));
+        assert_eq!(hour.range((Excluded(u1), Included(u1))), (t1, t1));
+        assert_eq!(hour.range(t2..t1), (t2, t2));
+        assert_eq!(hour.range(t2..=t1), (t2, t2));
+
+        let (start, end) = hour.range(..);
+        assert_eq!(start, OffsetDateTime::UNIX_EPOCH);
+        assert_eq!(end, hour.round_down(end));
+        assert!(end <= OffsetDateTime::now_utc());
+    }
+
+    #[test]
+    fn timeframe_serde() {
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct Config {
+            timeframe: Timeframe,
+        }
+
+        let timeframes = [
+            (Timeframe::FiveMinutes, "FiveMinutes"),
+            (Timeframe::Quarters, "Quarters"),
+            (Timeframe::OneHour, "OneHour"),
+            (Timeframe::FourHours, "FourHours"),
+            (Timeframe::OneDay, "OneDay"),
+        ];
+        let mut names = std::collections::HashSet::new();
+
+        assert_eq!(timeframes.map(|(timeframe, _)| timeframe), Timeframe::ALL);
+        for ((timeframe, variant), alias) in timeframes.into_iter().zip(Timeframe::NAMES) {
+            assert_eq!(timeframe.to_string(), alias);
+            for name in [variant, alias] {
+                assert!(names.insert(name), "{name} is not unique");
+                assert_eq!(
+                    serde_json::from_str::<Timeframe>(&format!("\"{name}\"")).unwrap(),
+                    timeframe
+                );
+                assert_eq!(
+                    toml::from_str::<Config>(&format!("timeframe = \"{name}\"")).unwrap(),
+                    Config { timeframe }
+                );
+            }
+
+            let json = serde_json::to_string(&timeframe).unwrap();
+            assert_eq!(json, format!("\"{variant}\""));
+            assert_eq!(serde_json::from_str::<Timeframe>(&json).unwrap(), timeframe);
+
+            let config = toml::to_string(&Config { timeframe }).unwrap();
+            assert_eq!(
+                toml::from_str::<Config>(&config).unwrap(),
+                Config { timeframe }
+            );
+        }
+        assert!(serde_json::from_str::<Timeframe>("\"5M\"").is_err());
+        assert!(serde_json::from_str::<Timeframe>("\"fiveminutes\"").is_err());
     }
 }
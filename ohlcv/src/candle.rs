@@ -1,17 +1,39 @@
-use std::{fmt, num::NonZero};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    num::NonZero,
+    ops::RangeBounds,
+    str::FromStr,
+};
 
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use time::OffsetDateTime;
+use time::{
+    format_description::well_known::{iso8601, Iso8601, Rfc3339},
+    Date, OffsetDateTime, UtcOffset,
+};
 
 use crate::{Error, Timeframe};
 
+/// ISO 8601 configuration for timestamps RFC 3339 cannot represent.
+const EXPANDED_ISO8601: iso8601::EncodedConfig = iso8601::Config::DEFAULT
+    .set_year_is_six_digits(true)
+    .encode();
+
 /// Represents a candlestick in a trading pair.
 ///
 /// A candlestick is a type of price chart that displays the high, low, open,
 /// and closing prices of a cryptocurrency over a specific period of time. If
 /// the price of the cryptocurrency increased over the period, the candlestick
-/// is green. If the price decreased, the candlestick is red.
+/// is green. If the price decreased, the candlestick is red. If the price did
+/// not change, the candlestick is a doji.
+///
+/// **Equality ignores the prices, the volume and the sources.** Two candles
+/// are equal if they start at the same instant and have the same timeframe,
+/// i.e. if they describe the same period. [`Hash`] and [`PartialOrd`] follow
+/// this identity, so a map keyed by candles holds one candle per period. Use
+/// [`Candle::content_eq`] to compare all fields or [`Candle::approx_eq`] to
+/// compare the prices within a tolerance.
 #[derive(Clone, Copy, Debug, Eq, Deserialize, Serialize)]
 pub struct Candle {
     /// Start time of the candle in UTC
@@ -33,13 +55,216 @@ pub struct Candle {
 }
 
 impl Candle {
+    /// Creates a new candle from a single source.
+    ///
+    /// The candle is validated before it is returned. The high price must not
+    /// be less than any other price and the low price must not be greater than
+    /// any other price. The volume must not be negative.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidCandle`] describing the violated invariant if
+    /// the candle is not valid.
+    pub fn new(
+        timestamp: OffsetDateTime,
+        timeframe: Timeframe,
+        open: Decimal,
+        high: Decimal,
+        low: Decimal,
+        close: Decimal,
+        volume: Decimal,
+    ) -> Result<Self, Error> {
+        let candle = Self {
+            timestamp,
+            timeframe,
+            sources: NonZero::<usize>::MIN,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        };
+
+        candle.validate()?;
+        Ok(candle)
+    }
+
+    /// Checks if the candle satisfies the invariants of [`Candle::new`].
+    ///
+    /// Unlike the constructor this does not fail, so candles read from a
+    /// database or another external source can be checked before use.
+    #[must_use]
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Checks if all fields of the candles are equal.
+    ///
+    /// Unlike the equality of [`Candle`], which only compares the period, this
+    /// compares the sources, the prices and the volume as well, e.g. to check
+    /// if a candle fetched again differs from the stored one. The prices and
+    /// the volume are compared by value, so `1.0` equals `1.00`.
+    #[must_use]
+    pub fn content_eq(&self, other: &Self) -> bool {
+        self == other
+            && self.sources == other.sources
+            && self.open == other.open
+            && self.high == other.high
+            && self.low == other.low
+            && self.close == other.close
+            && self.volume == other.volume
+    }
+
+    /// Checks if the candles describe the same period with prices within
+    /// `epsilon` of each other.
+    ///
+    /// Merged prices are volume-weighted averages with long decimal expansions,
+    /// which a database truncates to the scale of its columns, e.g. ten
+    /// fractional digits for `DECIMAL(20, 10)`. A merged candle therefore
+    /// differs in the last digits from the same candle read back from storage
+    /// or merged again. With an `epsilon` of `0.0000000001` such candles are
+    /// considered equal. The difference of every price may be up to and
+    /// including `epsilon`. The sources and the volume are not compared.
+    #[must_use]
+    pub fn approx_eq(&self, other: &Self, epsilon: Decimal) -> bool {
+        let near = |a: Decimal, b: Decimal| {
+            a.checked_sub(b)
+                .is_some_and(|difference| difference.abs() <= epsilon)
+        };
+
+        self == other
+            && near(self.open, other.open)
+            && near(self.high, other.high)
+            && near(self.low, other.low)
+            && near(self.close, other.close)
+    }
+
+    /// Formats the candle as a line of comma-separated values.
+    ///
+    /// The line consists of the timestamp in RFC 3339 followed by the open,
+    /// high, low and close prices and the volume, e.g.
+    /// `2024-01-01T00:00:00Z,100,110,90,105,5`. The timeframe and sources are
+    /// not part of the line. Unlike the serialization with `serde` the format
+    /// does not change with the fields of the candle.
+    ///
+    /// The timestamp is converted to UTC and kept at its offset if the
+    /// conversion exceeds the supported range of years. A timestamp RFC 3339
+    /// cannot represent, e.g. one with a negative year, is written in ISO 8601
+    /// with a signed six-digit year, e.g. `-000001-01-01T00:00:00.000000000Z`.
+    /// [`Candle::from_ohlcv_line`] only parses timestamps in RFC 3339 that can
+    /// be converted to UTC.
+    #[must_use]
+    pub fn to_ohlcv_line(&self) -> String {
+        let timestamp = self
+            .timestamp
+            .checked_to_offset(UtcOffset::UTC)
+            .unwrap_or(self.timestamp);
+        // ISO 8601 cannot represent an offset with seconds either.
+        let timestamp = timestamp
+            .format(&Rfc3339)
+            .or_else(|_| timestamp.format(&Iso8601::<EXPANDED_ISO8601>))
+            .unwrap_or_else(|_| timestamp.to_string());
+
+        format!(
+            "{timestamp},{},{},{},{},{}",
+            self.open, self.high, self.low, self.close, self.volume
+        )
+    }
+
+    /// Parses a line formatted by [`Candle::to_ohlcv_line`].
+    ///
+    /// As the line does not contain the timeframe, it must be given. The
+    /// timestamp is converted to UTC and the candle is validated like in
+    /// [`Candle::new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidLine`] if the line does not consist of six
+    /// values or a value cannot be parsed, and [`Error::InvalidCandle`] if the
+    /// candle is not valid.
+    pub fn from_ohlcv_line(line: &str, timeframe: Timeframe) -> Result<Self, Error> {
+        let values = line.trim().split(',').collect::<Vec<_>>();
+        let [timestamp, open, high, low, close, volume] = values[..] else {
+            return Err(Error::InvalidLine(format!(
+                "expected 6 values, found {}",
+                values.len()
+            )));
+        };
+        let timestamp = OffsetDateTime::parse(timestamp, &Rfc3339)
+            .map_err(|err| err.to_string())
+            .and_then(|parsed| {
+                parsed
+                    .checked_to_offset(UtcOffset::UTC)
+                    .ok_or_else(|| "out of range in UTC".to_owned())
+            })
+            .map_err(|err| Error::InvalidLine(format!("timestamp `{timestamp}`: {err}")))?;
+        let decimal = |value: &str| {
+            Decimal::from_str(value)
+                .map_err(|err| Error::InvalidLine(format!("value `{value}`: {err}")))
+        };
+
+        Self::new(
+            timestamp,
+            timeframe,
+            decimal(open)?,
+            decimal(high)?,
+            decimal(low)?,
+            decimal(close)?,
+            decimal(volume)?,
+        )
+    }
+
+    /// Checks the invariants of the candle.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        let invalid = |reason: String| Err(Error::InvalidCandle(self.timestamp, reason));
+
+        if self.high < self.low {
+            return invalid(format!("high {} is less than low {}", self.high, self.low));
+        }
+        if self.high < self.open {
+            return invalid(format!(
+                "high {} is less than open {}",
+                self.high, self.open
+            ));
+        }
+        if self.high < self.close {
+            return invalid(format!(
+                "high {} is less than close {}",
+                self.high, self.close
+            ));
+        }
+        if self.low > self.open {
+            return invalid(format!(
+                "low {} is greater than open {}",
+                self.low, self.open
+            ));
+        }
+        if self.low > self.close {
+            return invalid(format!(
+                "low {} is greater than close {}",
+                self.low, self.close
+            ));
+        }
+        if self.volume < Decimal::ZERO {
+            return invalid(format!("volume {} is negative", self.volume));
+        }
+        Ok(())
+    }
+
     /// Merges many candles with the same timestamp and timeframe into a single
     /// candle.
     ///
-    /// The price components (open, high, low, close) of the new candle are
-    /// calculated by averaging the prices of the input candles weighted by
-    /// their volumes (volume-weighted average, VWAP). The volume of the new
-    /// candle is the sum of the volumes of the input candles.
+    /// The open and close prices of the new candle are calculated by averaging
+    /// the prices of the input candles weighted by their volumes
+    /// (volume-weighted average, VWAP). The high and low prices are the
+    /// extremes of the input candles, i.e. the maximum high and the minimum
+    /// low. The volume of the new candle is the sum of the volumes of the input
+    /// candles.
+    ///
+    /// If the total volume of the input candles is zero, e.g. for flat
+    /// no-trade periods, the open and close prices are calculated as the
+    /// unweighted arithmetic mean of the input candles instead.
     ///
     /// # Errors
     ///
@@ -52,9 +277,11 @@ impl Candle {
     {
         let mut timestamp = Option::<OffsetDateTime>::None;
         let mut timeframe = Option::<Timeframe>::None;
+        let mut count = 0u32;
         let mut sources = 0;
+        let mut mean = [Decimal::ZERO; 2];
         let mut open = Decimal::ZERO;
-        let mut high = Decimal::ZERO;
+        let mut high = Decimal::MIN;
         let mut low = Decimal::MAX;
         let mut close = Decimal::ZERO;
         let mut volume = Decimal::ZERO;
@@ -76,18 +303,26 @@ impl Candle {
                 timeframe = Some(candle.timeframe);
             }
 
-            sources += candle.sources.get();
+            count += 1;
+            sources += candle.sources();
+            mean[0] += candle.open;
+            mean[1] += candle.close;
             volume += candle.volume;
             open += candle.open * candle.volume;
-            high += candle.high * candle.volume;
-            low += candle.low * candle.volume;
+            high = high.max(candle.high);
+            low = low.min(candle.low);
             close += candle.close * candle.volume;
         }
 
-        let open = open / volume;
-        let high = high / volume;
-        let low = low / volume;
-        let close = close / volume;
+        let (open, close) = if volume.is_zero() {
+            if count == 0 {
+                return Err(Error::MergeEmpty);
+            }
+            let count = Decimal::from(count);
+            (mean[0] / count, mean[1] / count)
+        } else {
+            (open / volume, close / volume)
+        };
 
         match (timestamp, timeframe) {
             (Some(timestamp), Some(timeframe)) => Ok(Self {
@@ -106,13 +341,133 @@ impl Candle {
         }
     }
 
+    /// Transforms a series of candles into Heikin-Ashi candles.
+    ///
+    /// The Heikin-Ashi candles smooth the price movement of the series, which
+    /// makes trends easier to read on a chart. The prices of a transformed
+    /// candle are calculated from the candle and its transformed predecessor:
+    ///
+    /// * close: the mean of the open, high, low and close prices
+    /// * open: the mean of the open and close prices of the predecessor
+    /// * high: the maximum of the high price and the new open and close prices
+    /// * low: the minimum of the low price and the new open and close prices
+    ///
+    /// The first candle has no predecessor, so its open price is the mean of
+    /// its own open and close prices. The timestamp, timeframe, sources and
+    /// volume are kept. The candles are expected to be consecutive and sorted
+    /// by timestamp.
+    #[must_use]
+    pub fn heikin_ashi(candles: &[Self]) -> Vec<Self> {
+        let two = Decimal::TWO;
+        let four = Decimal::from(4);
+
+        candles
+            .iter()
+            .scan(None, |previous: &mut Option<Self>, candle| {
+                let close = (candle.open + candle.high + candle.low + candle.close) / four;
+                let open = previous.map_or_else(
+                    || (candle.open + candle.close) / two,
+                    |previous| (previous.open + previous.close) / two,
+                );
+                let transformed = Self {
+                    open,
+                    high: candle.high.max(open).max(close),
+                    low: candle.low.min(open).min(close),
+                    close,
+                    ..*candle
+                };
+
+                *previous = Some(transformed);
+                Some(transformed)
+            })
+            .collect()
+    }
+
+    /// Narrows a series of candles to the candles within the range.
+    ///
+    /// The range is resolved by [`Timeframe::range`] with the timeframe of
+    /// the first candle, so the candles containing any time of the range are
+    /// kept, like a query of the database for the range would return them. An
+    /// unbounded end excludes the incomplete current candle. A caller holding
+    /// a superset in memory can take a sub-window this way without querying
+    /// the database again.
+    ///
+    /// The candles are expected to have the same timeframe and to be sorted
+    /// by timestamp. The bounds are found by binary search.
+    #[must_use]
+    pub fn trim_to_range<R>(candles: &[Self], range: R) -> &[Self]
+    where
+        R: RangeBounds<OffsetDateTime>,
+    {
+        let Some(first) = candles.first() else {
+            return candles;
+        };
+        let (start, end) = first.timeframe.range(range);
+        let start = candles.partition_point(|candle| candle.timestamp < start);
+        let end = candles.partition_point(|candle| candle.timestamp < end);
+
+        &candles[start..end.max(start)]
+    }
+
+    /// Returns the number of sources (exchanges) that contributed to the
+    /// candle.
+    #[must_use]
+    #[inline]
+    pub const fn sources(&self) -> usize {
+        self.sources.get()
+    }
+
+    /// Returns the start time of the candle in the given offset.
+    ///
+    /// The result denotes the same instant as the timestamp, which stays in
+    /// UTC. Only the presented date and time shift, e.g. to show the candles
+    /// of an exchange in its local time.
+    #[must_use]
+    #[inline]
+    pub const fn local_timestamp(&self, offset: UtcOffset) -> OffsetDateTime {
+        self.timestamp.to_offset(offset)
+    }
+
+    /// Returns the day of the start time of the candle in the given offset.
+    ///
+    /// Grouping candles by this date buckets them by a local trading day
+    /// instead of the UTC day. For an offset east of UTC the local day starts
+    /// before midnight in UTC, for an offset west of UTC after.
+    #[must_use]
+    #[inline]
+    pub const fn local_date(&self, offset: UtcOffset) -> Date {
+        self.local_timestamp(offset).date()
+    }
+
+    /// Returns the period covered by the candle.
+    ///
+    /// The period is the half-open interval from the timestamp inclusive to
+    /// the timestamp plus the duration of the timeframe exclusive, which is
+    /// the timestamp of the following candle.
+    #[must_use]
+    #[inline]
+    pub fn span(&self) -> (OffsetDateTime, OffsetDateTime) {
+        (self.timestamp, self.timestamp + self.timeframe.duration())
+    }
+
+    /// Checks if the candle was merged from more than one source.
+    ///
+    /// See [`Candle::merge`].
+    #[must_use]
+    #[inline]
+    pub const fn was_merged(&self) -> bool {
+        self.sources() > 1
+    }
+
     /// Returns the color of the candlestick.
+    ///
+    /// If the open and close prices are equal, the candlestick is a doji.
     #[must_use]
     pub fn color(&self) -> Color {
-        if self.close > self.open {
-            Color::Green
-        } else {
-            Color::Red
+        match self.close.cmp(&self.open) {
+            std::cmp::Ordering::Greater => Color::Green,
+            std::cmp::Ordering::Less => Color::Red,
+            std::cmp::Ordering::Equal => Color::Doji,
         }
     }
 
@@ -140,6 +495,15 @@ impl Candle {
         self.high - self.low
     }
 
+    /// Returns the change from the open to the close price in percent.
+    ///
+    /// If the open price is zero, the change is undefined and zero is
+    /// returned.
+    #[must_use]
+    pub fn percent_change(&self) -> Decimal {
+        percent_change(self.open, self.close)
+    }
+
     /// Returns the upper shadow of the candlestick.
     #[must_use]
     pub fn upper_shadow(&self) -> Decimal {
@@ -153,12 +517,21 @@ impl Candle {
     }
 }
 
+/// Compares the timestamp and the timeframe only, see [`Candle`].
 impl PartialEq for Candle {
     fn eq(&self, other: &Self) -> bool {
         self.timestamp == other.timestamp && self.timeframe == other.timeframe
     }
 }
 
+/// Hashes the timestamp and the timeframe only, consistent with the equality.
+impl Hash for Candle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.timestamp.hash(state);
+        self.timeframe.hash(state);
+    }
+}
+
 impl PartialOrd for Candle {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         match self.timestamp.cmp(&other.timestamp) {
@@ -192,6 +565,9 @@ pub enum Color {
     /// The candlestick is red. This means that the price of the candlestick
     /// is lower than the opening price.
     Red,
+    /// The candlestick is a doji. This means that the closing price of the
+    /// candlestick is equal to the opening price.
+    Doji,
 }
 
 impl fmt::Display for Color {
@@ -199,6 +575,625 @@ impl fmt::Display for Color {
         match self {
             Self::Green => write!(f, "green"),
             Self::Red => write!(f, "red"),
+            Self::Doji => write!(f, "doji"),
         }
     }
 }
+
+/// Change from `from` to `to` in percent, zero if `from` is zero.
+pub fn percent_change(from: Decimal, to: Decimal) -> Decimal {
+    (to - from)
+        .checked_div(from)
+        .and_then(|ratio| ratio.checked_mul(Decimal::ONE_HUNDRED))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use rust_decimal_macros::dec;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn span() {
+        let candle = Candle {
+            timestamp: time::macros::datetime!(2024-01-01 12:00 UTC),
+            ..Candle::default()
+        };
+
+        assert_eq!(
+            candle.span(),
+            (
+                time::macros::datetime!(2024-01-01 12:00 UTC),
+                time::macros::datetime!(2024-01-01 12:05 UTC)
+            )
+        );
+        assert_eq!(
+            Candle {
+                timeframe: Timeframe::OneDay,
+                ..candle
+            }
+            .span()
+            .1,
+            time::macros::datetime!(2024-01-02 12:00 UTC)
+        );
+    }
+
+    #[test]
+    fn content_eq() {
+        let candle = Candle {
+            open: dec!(10),
+            high: dec!(12),
+            low: dec!(9),
+            close: dec!(11),
+            volume: dec!(5),
+            ..Candle::default()
+        };
+        let changed = Candle {
+            close: dec!(11.5),
+            ..candle
+        };
+
+        assert!(candle.content_eq(&candle));
+        assert!(candle.content_eq(&Candle {
+            close: dec!(11.00),
+            ..candle
+        }));
+        assert_eq!(candle, changed);
+        assert!(!candle.content_eq(&changed));
+        assert!(!candle.content_eq(&Candle {
+            sources: NonZero::new(2).unwrap(),
+            ..candle
+        }));
+        assert!(!candle.content_eq(&Candle {
+            timeframe: Timeframe::OneHour,
+            ..candle
+        }));
+    }
+
+    #[test]
+    fn approx_eq() {
+        let epsilon = dec!(0.0000000001);
+        let candle = Candle {
+            open: dec!(1),
+            high: dec!(1),
+            low: dec!(1),
+            close: dec!(1),
+            ..Candle::default()
+        };
+
+        assert!(candle.approx_eq(&candle, Decimal::ZERO));
+        assert!(candle.approx_eq(
+            &Candle {
+                close: dec!(1.0000000001),
+                ..candle
+            },
+            epsilon
+        ));
+        assert!(candle.approx_eq(
+            &Candle {
+                low: dec!(0.9999999999),
+                ..candle
+            },
+            epsilon
+        ));
+        assert!(!candle.approx_eq(
+            &Candle {
+                close: dec!(1.0000000002),
+                ..candle
+            },
+            epsilon
+        ));
+        assert!(!candle.approx_eq(
+            &Candle {
+                timeframe: Timeframe::OneHour,
+                ..candle
+            },
+            epsilon
+        ));
+    }
+
+    #[test]
+    fn approx_eq_truncated() {
+        let epsilon = dec!(0.0000000001);
+        let first = Candle {
+            open: dec!(100),
+            high: dec!(100),
+            low: dec!(100),
+            close: dec!(100),
+            volume: dec!(1),
+            ..Candle::default()
+        };
+        let second = Candle {
+            open: dec!(101),
+            high: dec!(101),
+            low: dec!(101),
+            close: dec!(101),
+            volume: dec!(2),
+            ..first
+        };
+        let merged = Candle::merge(&[first, second]).unwrap();
+        // Stored in a DECIMAL(20, 10) column.
+        let stored = Candle {
+            open: merged.open.trunc_with_scale(10),
+            high: merged.high.trunc_with_scale(10),
+            low: merged.low.trunc_with_scale(10),
+            close: merged.close.trunc_with_scale(10),
+            ..merged
+        };
+
+        assert!(merged.open.scale() > 10);
+        assert!(!merged.content_eq(&stored));
+        assert!(merged.approx_eq(&stored, epsilon));
+        assert!(!merged.approx_eq(&stored, epsilon / dec!(10)));
+    }
+
+    #[test]
+    fn merge_zero_volume() {
+        let a = Candle {
+            open: dec!(10),
+            high: dec!(12),
+            low: dec!(9),
+            close: dec!(11),
+            ..Candle::default()
+        };
+        let b = Candle {
+            open: dec!(20),
+            high: dec!(22),
+            low: dec!(19),
+            close: dec!(21),
+            ..Candle::default()
+        };
+
+        let merged = Candle::merge([&a, &b]).unwrap();
+        assert_eq!(merged.sources(), 2);
+        assert!(merged.was_merged());
+        assert_eq!(merged.open, dec!(15));
+        assert_eq!(merged.high, dec!(22));
+        assert_eq!(merged.low, dec!(9));
+        assert_eq!(merged.close, dec!(16));
+        assert_eq!(merged.volume, Decimal::ZERO);
+    }
+
+    #[test]
+    fn merge_high_low_extremes() {
+        let a = Candle {
+            open: dec!(100),
+            high: dec!(110),
+            low: dec!(95),
+            close: dec!(105),
+            volume: dec!(1000),
+            ..Candle::default()
+        };
+        let b = Candle {
+            open: dec!(101),
+            high: dec!(120),
+            low: dec!(90),
+            close: dec!(106),
+            volume: dec!(1),
+            ..Candle::default()
+        };
+
+        let merged = Candle::merge([&a, &b]).unwrap();
+        assert_eq!(merged.high, dec!(120));
+        assert_eq!(merged.low, dec!(90));
+        assert_eq!(merged.open, dec!(100101) / dec!(1001));
+        assert_eq!(merged.close, dec!(105106) / dec!(1001));
+        assert_eq!(merged.volume, dec!(1001));
+    }
+
+    #[test]
+    fn trim_to_range() {
+        let candles = (0..6)
+            .map(|minutes| Candle {
+                timestamp: datetime!(2024-01-01 00:00 UTC) + time::Duration::minutes(minutes * 5),
+                ..Candle::default()
+            })
+            .collect::<Vec<_>>();
+        let trim = |range: (Bound<OffsetDateTime>, Bound<OffsetDateTime>)| {
+            Candle::trim_to_range(&candles, range)
+                .iter()
+                .map(|candle| candle.timestamp.minute())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            trim((
+                Bound::Included(datetime!(2024-01-01 00:05 UTC)),
+                Bound::Excluded(datetime!(2024-01-01 00:15 UTC))
+            )),
+            [5, 10]
+        );
+        assert_eq!(
+            trim((
+                Bound::Included(datetime!(2024-01-01 00:05 UTC)),
+                Bound::Included(datetime!(2024-01-01 00:15 UTC))
+            )),
+            [5, 10, 15]
+        );
+        // Candles containing a time of the range are kept.
+        assert_eq!(
+            trim((
+                Bound::Excluded(datetime!(2024-01-01 00:07 UTC)),
+                Bound::Excluded(datetime!(2024-01-01 00:11 UTC))
+            )),
+            [5, 10]
+        );
+        assert_eq!(
+            trim((
+                Bound::Unbounded,
+                Bound::Excluded(datetime!(2024-01-01 00:10 UTC))
+            )),
+            [0, 5]
+        );
+        assert_eq!(
+            trim((
+                Bound::Included(datetime!(2024-01-01 00:20 UTC)),
+                Bound::Unbounded
+            )),
+            [20, 25]
+        );
+        assert!(trim((
+            Bound::Included(datetime!(2024-01-02 00:00 UTC)),
+            Bound::Unbounded
+        ))
+        .is_empty());
+        assert!(trim((
+            Bound::Included(datetime!(2024-01-01 00:15 UTC)),
+            Bound::Excluded(datetime!(2024-01-01 00:05 UTC))
+        ))
+        .is_empty());
+        assert!(Candle::trim_to_range(&[], ..).is_empty());
+    }
+
+    #[test]
+    fn heikin_ashi() {
+        let candle = |minutes, open, high, low, close| Candle {
+            timestamp: OffsetDateTime::UNIX_EPOCH + time::Duration::minutes(minutes),
+            open,
+            high,
+            low,
+            close,
+            volume: dec!(5),
+            ..Candle::default()
+        };
+        let candles = [
+            candle(0, dec!(10), dec!(12), dec!(8), dec!(11)),
+            candle(5, dec!(11), dec!(15), dec!(10), dec!(14)),
+            candle(10, dec!(14), dec!(14), dec!(12), dec!(12)),
+        ];
+
+        let transformed = Candle::heikin_ashi(&candles);
+        assert_eq!(
+            transformed
+                .iter()
+                .map(|candle| (candle.open, candle.high, candle.low, candle.close))
+                .collect::<Vec<_>>(),
+            [
+                (dec!(10.5), dec!(12), dec!(8), dec!(10.25)),
+                (dec!(10.375), dec!(15), dec!(10), dec!(12.5)),
+                (dec!(11.4375), dec!(14), dec!(11.4375), dec!(13)),
+            ]
+        );
+        assert_eq!(transformed[2].timestamp, candles[2].timestamp);
+        assert_eq!(transformed[2].volume, dec!(5));
+        assert!(transformed.iter().all(Candle::is_valid));
+        assert!(Candle::heikin_ashi(&[]).is_empty());
+    }
+
+    #[test]
+    fn color() {
+        let candle = Candle {
+            open: dec!(10),
+            close: dec!(11),
+            ..Candle::default()
+        };
+        assert_eq!(candle.color(), Color::Green);
+
+        let candle = Candle {
+            open: dec!(11),
+            close: dec!(10),
+            ..Candle::default()
+        };
+        assert_eq!(candle.color(), Color::Red);
+
+        let candle = Candle {
+            open: dec!(10),
+            close: dec!(10.0),
+            ..Candle::default()
+        };
+        assert_eq!(candle.color(), Color::Doji);
+        assert_eq!(Color::Doji.to_string(), "doji");
+    }
+
+    #[test]
+    fn new() {
+        let ts = OffsetDateTime::UNIX_EPOCH;
+        let tf = Timeframe::FiveMinutes;
+        let candle = Candle::new(ts, tf, dec!(10), dec!(12), dec!(9), dec!(11), dec!(5)).unwrap();
+        assert_eq!(candle.sources(), 1);
+        assert!(!candle.was_merged());
+        assert_eq!(candle.high, dec!(12));
+        assert_eq!(candle.low, dec!(9));
+        assert!(candle.is_valid());
+
+        let cases = [
+            (
+                (dec!(10), dec!(8), dec!(9), dec!(8), dec!(5)),
+                "high 8 is less than low 9",
+            ),
+            (
+                (dec!(10), dec!(9), dec!(8), dec!(9), dec!(5)),
+                "high 9 is less than open 10",
+            ),
+            (
+                (dec!(9), dec!(9), dec!(8), dec!(10), dec!(5)),
+                "high 9 is less than close 10",
+            ),
+            (
+                (dec!(8), dec!(12), dec!(9), dec!(10), dec!(5)),
+                "low 9 is greater than open 8",
+            ),
+            (
+                (dec!(10), dec!(12), dec!(9), dec!(8), dec!(5)),
+                "low 9 is greater than close 8",
+            ),
+            (
+                (dec!(10), dec!(12), dec!(9), dec!(11), dec!(-1)),
+                "volume -1 is negative",
+            ),
+        ];
+
+        for ((open, high, low, close, volume), reason) in cases {
+            assert_eq!(
+                Candle::new(ts, tf, open, high, low, close, volume),
+                Err(Error::InvalidCandle(ts, reason.to_string()))
+            );
+
+            let candle = Candle {
+                open,
+                high,
+                low,
+                close,
+                volume,
+                ..candle
+            };
+            assert!(!candle.is_valid(), "{reason}");
+        }
+    }
+
+    #[test]
+    fn merge_empty() {
+        assert_eq!(Candle::merge([]), Err(Error::MergeEmpty));
+    }
+
+    #[test]
+    fn ohlcv_line() {
+        let candle = Candle::new(
+            OffsetDateTime::UNIX_EPOCH,
+            Timeframe::OneHour,
+            dec!(100.5),
+            dec!(110),
+            dec!(0.000000012345678901),
+            dec!(105),
+            dec!(5.25),
+        )
+        .unwrap();
+        let line = candle.to_ohlcv_line();
+        let parsed = Candle::from_ohlcv_line(&line, Timeframe::OneHour).unwrap();
+
+        assert_eq!(
+            line,
+            "1970-01-01T00:00:00Z,100.5,110,0.000000012345678901,105,5.25"
+        );
+        assert_eq!(parsed, candle);
+        assert_eq!(
+            [
+                parsed.open,
+                parsed.high,
+                parsed.low,
+                parsed.close,
+                parsed.volume
+            ],
+            [
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+                candle.volume
+            ]
+        );
+        assert_eq!(
+            Candle::from_ohlcv_line("1970-01-01T01:00:00+01:00,1,1,1,1,1\n", Timeframe::OneHour)
+                .unwrap()
+                .timestamp,
+            OffsetDateTime::UNIX_EPOCH
+        );
+        assert!(matches!(
+            Candle::from_ohlcv_line("1970-01-01T00:00:00Z,1,1,1,1", Timeframe::OneHour),
+            Err(Error::InvalidLine(_))
+        ));
+        assert!(matches!(
+            Candle::from_ohlcv_line("1970-01-01,1,1,1,1,1", Timeframe::OneHour),
+            Err(Error::InvalidLine(_))
+        ));
+        assert!(matches!(
+            Candle::from_ohlcv_line("1970-01-01T00:00:00Z,1,0,1,1,1", Timeframe::OneHour),
+            Err(Error::InvalidCandle(..))
+        ));
+    }
+
+    #[test]
+    fn ohlcv_line_timestamps() {
+        let line = |timestamp| {
+            Candle {
+                timestamp,
+                ..Candle::default()
+            }
+            .to_ohlcv_line()
+        };
+
+        assert_eq!(
+            line(datetime!(2024-01-01 01:00:30.25 +01:00:30)),
+            "2024-01-01T00:00:00.25Z,0,0,0,0,0"
+        );
+        assert_eq!(
+            line(datetime!(-0001-06-01 01:00 +01:00)),
+            "-000001-06-01T00:00:00.000000000Z,0,0,0,0,0"
+        );
+        assert_eq!(
+            line(datetime!(9999-12-31 23:59:59 -01:00)),
+            "9999-12-31T23:59:59-01:00,0,0,0,0,0"
+        );
+        assert_eq!(
+            line(datetime!(9999-12-31 23:59:59 -00:00:30)),
+            "9999-12-31 23:59:59.0 -00:00:30,0,0,0,0,0"
+        );
+        assert!(matches!(
+            Candle::from_ohlcv_line("9999-12-31T23:59:59-01:00,0,0,0,0,0", Timeframe::OneHour),
+            Err(Error::InvalidLine(_))
+        ));
+    }
+
+    #[test]
+    fn ohlcv_line_round_trip() {
+        for timestamp in [
+            datetime!(0000-01-01 00:00 UTC),
+            datetime!(1970-01-01 00:00 UTC),
+            datetime!(2024-02-29 12:34:56.789 UTC),
+            datetime!(2024-01-01 01:00:30.25 +01:00:30),
+            datetime!(9999-12-31 23:59:59.999999999 UTC),
+        ] {
+            let candle = Candle::new(
+                timestamp,
+                Timeframe::FiveMinutes,
+                dec!(100.5),
+                dec!(110),
+                dec!(90),
+                dec!(105),
+                dec!(5.25),
+            )
+            .unwrap();
+            let parsed =
+                Candle::from_ohlcv_line(&candle.to_ohlcv_line(), Timeframe::FiveMinutes).unwrap();
+
+            assert!(parsed.content_eq(&candle), "{timestamp}");
+        }
+    }
+
+    #[test]
+    fn serde() {
+        #[derive(Debug, PartialEq, Deserialize, Serialize)]
+        struct Record {
+            candle: Candle,
+        }
+
+        let candle = Candle::new(
+            time::macros::datetime!(2024-01-01 01:00 UTC),
+            Timeframe::OneHour,
+            dec!(100.5),
+            dec!(110),
+            dec!(0.000000012345678901),
+            dec!(105),
+            dec!(5.25),
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&candle).unwrap();
+        assert!(json.contains(r#""timeframe":"OneHour""#), "{json}");
+        assert_eq!(serde_json::from_str::<Candle>(&json).unwrap(), candle);
+
+        let record = Record { candle };
+        let toml = toml::to_string(&record).unwrap();
+        assert!(toml.contains(r#"timeframe = "OneHour""#), "{toml}");
+        assert_eq!(toml::from_str::<Record>(&toml).unwrap(), record);
+
+        let json = json.replace(r#""timeframe":"OneHour""#, r#""timeframe":"1h""#);
+        assert_eq!(serde_json::from_str::<Candle>(&json).unwrap(), candle);
+    }
+
+    #[test]
+    fn hash() {
+        use std::{collections::HashSet, hash::BuildHasher};
+
+        let candle = Candle {
+            open: dec!(10),
+            high: dec!(12),
+            low: dec!(9),
+            close: dec!(11),
+            volume: dec!(5),
+            ..Candle::default()
+        };
+        let other = Candle {
+            open: dec!(20),
+            high: dec!(20),
+            low: dec!(20),
+            close: dec!(20),
+            volume: dec!(0),
+            sources: NonZero::new(2).unwrap(),
+            ..candle
+        };
+        let shifted = Candle {
+            timestamp: candle.timestamp.to_offset(time::macros::offset!(+2)),
+            ..candle
+        };
+        let later = Candle {
+            timestamp: candle.timestamp + Timeframe::FiveMinutes.duration(),
+            ..candle
+        };
+        let state = std::hash::RandomState::new();
+
+        assert_eq!(candle, other);
+        assert_eq!(state.hash_one(candle), state.hash_one(other));
+        assert_eq!(candle, shifted);
+        assert_eq!(state.hash_one(candle), state.hash_one(shifted));
+        assert_eq!(HashSet::from([candle, other, shifted, later]).len(), 2);
+    }
+
+    #[test]
+    fn local_timestamp() {
+        use time::macros::{date, datetime, offset};
+
+        let candle = |timestamp| Candle {
+            timestamp,
+            ..Candle::default()
+        };
+        let late = candle(datetime!(2024-01-01 20:00 UTC));
+        let early = candle(datetime!(2024-01-01 03:00 UTC));
+
+        assert_eq!(
+            late.local_timestamp(offset!(+9)),
+            datetime!(2024-01-02 05:00 +9)
+        );
+        assert_eq!(late.local_timestamp(offset!(+9)), late.timestamp);
+        assert_eq!(late.local_date(offset!(+9)), date!(2024 - 01 - 02));
+        assert_eq!(late.local_date(offset!(-5)), date!(2024 - 01 - 01));
+        assert_eq!(early.local_date(offset!(+9)), date!(2024 - 01 - 01));
+        assert_eq!(early.local_date(offset!(-5)), date!(2023 - 12 - 31));
+        assert_eq!(
+            early.local_timestamp(offset!(+5:30)).to_string(),
+            "2024-01-01 8:30:00.0 +05:30:00"
+        );
+        assert_eq!(early.local_date(UtcOffset::UTC), early.timestamp.date());
+        assert_eq!(early.timestamp.offset(), UtcOffset::UTC);
+    }
+
+    #[test]
+    fn percent_change() {
+        let candle = |open, close| Candle {
+            open,
+            high: dec!(20),
+            low: dec!(0),
+            close,
+            ..Candle::default()
+        };
+
+        assert_eq!(candle(dec!(10), dec!(11)).percent_change(), dec!(10));
+        assert_eq!(candle(dec!(10), dec!(7.5)).percent_change(), dec!(-25));
+        assert_eq!(candle(dec!(10), dec!(10)).percent_change(), dec!(0));
+        assert_eq!(candle(dec!(0), dec!(10)).percent_change(), dec!(0));
+    }
+}
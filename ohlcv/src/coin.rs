@@ -1,26 +1,77 @@
-use std::fmt;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::Currency;
+use crate::{Currency, Error};
 
 /// Represents a cryptocurrency and its quote currency.
+///
+/// The symbol is part of the table name of the coin. As table names cannot be
+/// bound as parameters of SQL statements, the symbol is restricted to ASCII
+/// letters and digits.
+///
+/// The table name starts with the table prefix of the coin, which defaults
+/// to [`Coin::DEFAULT_TABLE_PREFIX`]. Coins with different prefixes are
+/// stored in different tables, so several datasets can share a database.
+///
+/// **Coins are identified by their symbol, currency and table prefix.**
+/// Equality and [`Hash`] ignore the name, so two coins are equal if and only
+/// if they are stored in the same table. `BTC` quoted in USD and in EUR are
+/// distinct.
 #[derive(Clone, Debug, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawCoin")]
 pub struct Coin {
     symbol: Box<str>,
     name: Box<str>,
     currency: Currency,
+    #[serde(skip)]
+    table_prefix: Option<Box<str>>,
 }
 
 impl Coin {
-    /// Create a new [`Coin`].
-    #[must_use]
-    pub fn new(symbol: impl Into<String>, name: impl Into<String>, currency: Currency) -> Self {
-        Self {
-            symbol: symbol.into().to_uppercase().into_boxed_str(),
+    /// The default prefix of the table names.
+    pub const DEFAULT_TABLE_PREFIX: &'static str = "candles";
+
+    /// Create a new [`Coin`] with the default table prefix.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the symbol is empty or contains characters other
+    /// than ASCII letters and digits.
+    pub fn new(
+        symbol: impl Into<String>,
+        name: impl Into<String>,
+        currency: Currency,
+    ) -> Result<Self, Error> {
+        let symbol = symbol.into();
+
+        if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(Error::InvalidSymbol(symbol));
+        }
+
+        Ok(Self {
+            symbol: symbol.to_uppercase().into_boxed_str(),
             name: name.into().into_boxed_str(),
             currency,
-        }
+            table_prefix: None,
+        })
+    }
+
+    /// Set the prefix of the table name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prefix is not valid, see
+    /// [`check_table_prefix()`](Self::check_table_prefix).
+    pub fn with_table_prefix(mut self, prefix: impl Into<String>) -> Result<Self, Error> {
+        let prefix = prefix.into();
+
+        Self::check_table_prefix(&prefix)?;
+        self.table_prefix = (prefix != Self::DEFAULT_TABLE_PREFIX).then(|| prefix.into());
+        Ok(self)
     }
 
     /// The symbol of the coin.
@@ -50,8 +101,33 @@ impl Coin {
     /// The prefix of the table name.
     #[must_use]
     #[inline]
-    pub const fn table_prefix() -> &'static str {
-        "candles"
+    pub fn table_prefix(&self) -> &str {
+        self.table_prefix
+            .as_deref()
+            .unwrap_or(Self::DEFAULT_TABLE_PREFIX)
+    }
+
+    /// Check that the prefix can be used for table names.
+    ///
+    /// The prefix is part of SQL statements without quoting, so it must start
+    /// with a lowercase ASCII letter followed by lowercase ASCII letters and
+    /// digits. Underscores are not allowed, as they separate the prefix from
+    /// the symbol and the currency. This way no table of one prefix starts
+    /// with another prefix followed by an underscore.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTablePrefix`] if the prefix is not valid.
+    pub fn check_table_prefix(prefix: &str) -> Result<(), Error> {
+        let mut chars = prefix.chars();
+
+        if chars.next().is_some_and(|c| c.is_ascii_lowercase())
+            && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidTablePrefix(prefix.to_owned()))
+        }
     }
 
     /// The table name of the coin.
@@ -65,18 +141,61 @@ impl Coin {
     /// use ohlcv::Coin;
     /// use ohlcv::Currency;
     ///
-    /// let coin = Coin::new("BTC", "Bitcoin", Currency::USD);
+    /// let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
     /// assert_eq!(coin.table_name(), "candles_btc_usd");
+    ///
+    /// let coin = coin.with_table_prefix("ohlcv").unwrap();
+    /// assert_eq!(coin.table_name(), "ohlcv_btc_usd");
     /// ```
     #[must_use]
     pub fn table_name(&self) -> String {
         format!(
             "{}_{}_{}",
-            Self::table_prefix(),
+            self.table_prefix(),
             self.symbol.to_lowercase(),
             self.currency.to_string().to_lowercase()
         )
     }
+
+    /// Parse a coin from its table name with the default prefix.
+    ///
+    /// This is the inverse of [`table_name()`](Self::table_name), see
+    /// [`from_table_name_with_prefix()`](Self::from_table_name_with_prefix).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ohlcv::Coin;
+    /// use ohlcv::Currency;
+    ///
+    /// let coin = Coin::from_table_name("candles_btc_usd").unwrap();
+    /// assert_eq!(coin.symbol(), "BTC");
+    /// assert_eq!(coin.currency(), Currency::USD);
+    /// ```
+    #[must_use]
+    pub fn from_table_name(table: &str) -> Option<Self> {
+        Self::from_table_name_with_prefix(table, Self::DEFAULT_TABLE_PREFIX)
+    }
+
+    /// Parse a coin from its table name with the given prefix.
+    ///
+    /// This is the inverse of [`table_name()`](Self::table_name) for coins
+    /// with the prefix. As the name of the coin is not part of the table name,
+    /// the symbol is used as name. Returns `None` if the table name does not
+    /// start with the prefix or if the prefix, the symbol or the currency is
+    /// invalid.
+    #[must_use]
+    pub fn from_table_name_with_prefix(table: &str, prefix: &str) -> Option<Self> {
+        let (symbol, currency) = table
+            .strip_prefix(prefix)?
+            .strip_prefix('_')?
+            .rsplit_once('_')?;
+        let currency = currency.to_uppercase().parse::<Currency>().ok()?;
+
+        Self::new(symbol, symbol.to_uppercase(), currency)
+            .and_then(|coin| coin.with_table_prefix(prefix))
+            .ok()
+    }
 }
 
 impl fmt::Display for Coin {
@@ -89,8 +208,152 @@ impl fmt::Display for Coin {
     }
 }
 
+/// Compares the symbol, the currency and the table prefix, see [`Coin`].
 impl PartialEq for Coin {
     fn eq(&self, other: &Self) -> bool {
         self.symbol == other.symbol
+            && self.currency == other.currency
+            && self.table_prefix == other.table_prefix
+    }
+}
+
+/// Hashes the symbol, the currency and the table prefix, consistent with the
+/// equality.
+impl Hash for Coin {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.symbol.hash(state);
+        self.currency.hash(state);
+        self.table_prefix.hash(state);
+    }
+}
+
+/// Unvalidated coin as deserialized.
+#[derive(Deserialize)]
+struct RawCoin {
+    symbol: String,
+    name: String,
+    currency: Currency,
+}
+
+impl TryFrom<RawCoin> for Coin {
+    type Error = Error;
+
+    fn try_from(coin: RawCoin) -> Result<Self, Self::Error> {
+        Self::new(coin.symbol, coin.name, coin.currency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() {
+        let coin = Coin::new("btc", "Bitcoin", Currency::USD).unwrap();
+        assert_eq!(coin.symbol(), "BTC");
+        assert_eq!(coin.table_name(), "candles_btc_usd");
+        assert!(Coin::new("1INCH", "1inch", Currency::USD).is_ok());
+    }
+
+    #[test]
+    fn from_table_name() {
+        let coin = Coin::new("1INCH", "1inch", Currency::EUR).unwrap();
+        let parsed = Coin::from_table_name(&coin.table_name()).unwrap();
+
+        assert_eq!(parsed, coin);
+        assert_eq!(parsed.name(), "1INCH");
+        assert_eq!(parsed.currency(), Currency::EUR);
+        assert_eq!(Coin::from_table_name("candles_btc_xyz"), None);
+        assert_eq!(Coin::from_table_name("candles_btc"), None);
+        assert_eq!(Coin::from_table_name("candlesbtc_usd"), None);
+        assert_eq!(Coin::from_table_name("users_btc_usd"), None);
+        let prefixed = coin.with_table_prefix("ohlcv").unwrap();
+        assert_eq!(
+            Coin::from_table_name_with_prefix(&prefixed.table_name(), "ohlcv"),
+            Some(prefixed)
+        );
+        assert_eq!(
+            Coin::from_table_name_with_prefix("candles_btc_usd", "ohlcv"),
+            None
+        );
+    }
+
+    #[test]
+    fn table_prefix() {
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let prefixed = coin.clone().with_table_prefix("ohlcv2").unwrap();
+
+        assert_eq!(coin.table_prefix(), Coin::DEFAULT_TABLE_PREFIX);
+        assert_eq!(prefixed.table_prefix(), "ohlcv2");
+        assert_eq!(prefixed.table_name(), "ohlcv2_btc_usd");
+        assert_ne!(prefixed, coin);
+        assert_eq!(
+            prefixed.with_table_prefix(Coin::DEFAULT_TABLE_PREFIX),
+            Ok(coin.clone())
+        );
+        assert_eq!(
+            coin.with_table_prefix("ohlcv_eu"),
+            Err(Error::InvalidTablePrefix("ohlcv_eu".to_owned()))
+        );
+        for prefix in ["", "2ohlcv", "Ohlcv", "ohlcv_eu", "ohlcv;", "ohlcv eu"] {
+            assert_eq!(
+                Coin::check_table_prefix(prefix),
+                Err(Error::InvalidTablePrefix(prefix.to_owned()))
+            );
+        }
+    }
+
+    #[test]
+    fn hash() {
+        use std::collections::HashMap;
+
+        let usd = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let eur = Coin::new("BTC", "Bitcoin", Currency::EUR).unwrap();
+        let renamed = Coin::new("btc", "BTC", Currency::USD).unwrap();
+        let mut candles = HashMap::new();
+
+        assert_ne!(usd, eur);
+        assert_eq!(usd, renamed);
+        candles.insert(usd.clone(), 1);
+        candles.insert(eur.clone(), 2);
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[&usd], 1);
+        assert_eq!(candles[&eur], 2);
+        assert_eq!(candles.insert(renamed, 3), Some(1));
+        assert_eq!(candles.get_key_value(&usd).unwrap().0.name(), "Bitcoin");
+    }
+
+    #[test]
+    fn malicious_symbol() {
+        let symbols = [
+            "",
+            "btc; DROP TABLE users;--",
+            "btc_usd",
+            "btc usd",
+            "btc`",
+            "btc'",
+            "btc\"",
+            "btc/*",
+            "bitcöin",
+        ];
+
+        for symbol in symbols {
+            assert_eq!(
+                Coin::new(symbol, "Bitcoin", Currency::USD),
+                Err(Error::InvalidSymbol(symbol.to_owned()))
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize() {
+        let coin: Coin =
+            serde_json::from_str(r#"{"symbol":"eth","name":"Ethereum","currency":"EUR"}"#).unwrap();
+        assert_eq!(coin, Coin::new("ETH", "Ethereum", Currency::EUR).unwrap());
+
+        let coin = serde_json::from_str::<Coin>(
+            r#"{"symbol":"eth;--","name":"Ethereum","currency":"EUR"}"#,
+        );
+        assert!(coin.is_err());
     }
 }
@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use slugify::slugify;
 
 /// Credentials for the database.
@@ -27,6 +29,30 @@ impl Credentials {
         Self { username, password }
     }
 
+    /// Create new credentials with the password read from a file.
+    ///
+    /// The file contains only the password, as provided by the secrets of
+    /// systemd or Docker. A trailing newline is removed. The environment
+    /// variable `OHLCV_<username>_PASSWORD` is not consulted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read.
+    pub fn from_file(
+        username: impl Into<String>,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, crate::Error> {
+        let path = path.as_ref();
+        let password = std::fs::read_to_string(path)
+            .map_err(|err| crate::Error::PasswordFile(path.to_path_buf(), err))?;
+        let password = password.trim_end_matches(['\r', '\n']).to_owned();
+
+        Ok(Self {
+            username: username.into(),
+            password: Some(password),
+        })
+    }
+
     /// Set the password for the credentials.
     #[must_use]
     pub fn with_password(mut self, password: impl Into<String>) -> Self {
@@ -64,17 +90,25 @@ impl TryFrom<&crate::database::mysql::DbConfig> for Credentials {
     /// Convert the database configuration into credentials.
     ///
     /// If the password is set in the configuration, it is used. Otherwise, the
-    /// password is looked up in the environment variable
+    /// password is read from the `password_file` of the configuration. As
+    /// last resort the password is looked up in the environment variable
     /// `OHLCV_<username>_PASSWORD`.
     ///
     /// # Errors
     ///
-    /// Returns an error if the password is missing.
+    /// Returns an error if the password is missing or the password file cannot
+    /// be read.
     fn try_from(config: &crate::database::mysql::DbConfig) -> Result<Self, Self::Error> {
-        let creds = Self::new(&config.username);
+        let username = config
+            .username
+            .as_deref()
+            .ok_or_else(|| Self::Error::DbConfig("missing `username`".to_owned()))?;
+        let creds = Self::new(username);
 
         if let Some(password) = &config.password {
             Ok(creds.with_password(password))
+        } else if let Some(path) = &config.password_file {
+            Self::from_file(username, path)
         } else if creds.has_password() {
             Ok(creds)
         } else {
@@ -91,17 +125,25 @@ impl TryFrom<&crate::database::postgres::DbConfig> for Credentials {
     /// Convert the database configuration into credentials.
     ///
     /// If the password is set in the configuration, it is used. Otherwise, the
-    /// password is looked up in the environment variable
+    /// password is read from the `password_file` of the configuration. As
+    /// last resort the password is looked up in the environment variable
     /// `OHLCV_<username>_PASSWORD`.
     ///
     /// # Errors
     ///
-    /// Returns an error if the password is missing.
+    /// Returns an error if the password is missing or the password file cannot
+    /// be read.
     fn try_from(config: &crate::database::postgres::DbConfig) -> Result<Self, Self::Error> {
-        let creds = Self::new(&config.username);
+        let username = config
+            .username
+            .as_deref()
+            .ok_or_else(|| Self::Error::DbConfig("missing `username`".to_owned()))?;
+        let creds = Self::new(username);
 
         if let Some(password) = &config.password {
             Ok(creds.with_password(password))
+        } else if let Some(path) = &config.password_file {
+            Self::from_file(username, path)
         } else if creds.has_password() {
             Ok(creds)
         } else {
@@ -114,11 +156,9 @@ impl TryFrom<&crate::database::postgres::DbConfig> for Credentials {
 mod tests {
     use std::sync::Mutex;
 
-    use once_cell::sync::Lazy;
-
     use super::*;
 
-    static SERIALIZED: Lazy<Mutex<()>> = Lazy::new(Mutex::default);
+    static SERIALIZED: Mutex<()> = Mutex::new(());
 
     const USERNAMES: &[(&str, &str)] = &[
         ("test", "OHLCV_TEST_PASSWORD"),
@@ -177,13 +217,25 @@ mod tests {
         std::env::set_var(envar, "password2");
 
         let config = crate::database::mysql::DbConfig {
-            host: "localhost".into(),
+            url: None,
+            host: Some("localhost".into()),
             port: Some(3306),
-            database: "test".into(),
-            username: "test".into(),
+            database: Some("test".into()),
+            username: Some("test".into()),
             password: Some("password".into()),
+            password_file: None,
             root_username: None,
+            ssl_mode: None,
+            ssl_ca: None,
+            ssl_cert: None,
+            ssl_key: None,
+            max_connections: None,
+            connect_timeout_secs: None,
+            price_precision: None,
+            price_scale: None,
+            table_prefix: None,
             pool: None,
+            root_pool: None,
         };
 
         let creds = Credentials::try_from(&config);
@@ -193,13 +245,25 @@ mod tests {
         );
 
         let config = crate::database::mysql::DbConfig {
-            host: "localhost".into(),
+            url: None,
+            host: Some("localhost".into()),
             port: Some(3306),
-            database: "test".into(),
-            username: "test".into(),
+            database: Some("test".into()),
+            username: Some("test".into()),
             password: None,
+            password_file: None,
             root_username: None,
+            ssl_mode: None,
+            ssl_ca: None,
+            ssl_cert: None,
+            ssl_key: None,
+            max_connections: None,
+            connect_timeout_secs: None,
+            price_precision: None,
+            price_scale: None,
+            table_prefix: None,
             pool: None,
+            root_pool: None,
         };
 
         let creds = Credentials::try_from(&config);
@@ -209,4 +273,17 @@ mod tests {
         );
         std::env::remove_var(envar);
     }
+
+    #[test]
+    fn from_file() {
+        let path = std::env::temp_dir().join(format!("ohlcv-secret-{}", std::process::id()));
+        std::fs::write(&path, "secret\n").unwrap();
+
+        let creds = Credentials::from_file("test", &path);
+        assert_eq!(creds, Ok(Credentials::new("test").with_password("secret")));
+        std::fs::remove_file(&path).unwrap();
+
+        let creds = Credentials::from_file("test", &path);
+        assert!(matches!(creds, Err(crate::Error::PasswordFile(file, _)) if file == path));
+    }
 }
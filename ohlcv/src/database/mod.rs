@@ -16,11 +16,307 @@
 //! normal user only has access to the data. Exception to this is SQLite, where
 //! no user management is needed.
 
-use std::{fmt, future::Future};
+#[cfg(not(any(feature = "mysql", feature = "postgres", feature = "sqlite")))]
+compile_error!("The feature 'database' requires one of 'mysql', 'postgres', or 'sqlite'.");
 
+use std::{fmt, future::Future, num::NonZero, ops::RangeBounds, time::Duration};
+
+use futures_util::{stream::BoxStream, TryStreamExt};
+use rust_decimal::Decimal;
 use serde::de::DeserializeOwned;
+use sqlx::{error::BoxDynError, pool::PoolOptions};
+
+use time::{Date, OffsetDateTime};
+
+use tracing::warn;
+
+use crate::{Candle, Coin, Error, Timeframe};
+
+/// The maximum number of candles inserted by a single statement.
+const INSERT_CHUNK_SIZE: usize = 100;
+/// The maximum number of candles sent by a single `COPY` message of PostgreSQL.
+#[cfg(feature = "postgres")]
+const BULK_CHUNK_SIZE: usize = 10_000;
+
+/// The default maximum number of connections of a pool.
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+/// The version of the schema created by
+/// [`init_schema()`](Database::init_schema).
+///
+/// The version is stored in the single row of the table
+/// `candles_schema_version`, named after the table prefix, and raised with
+/// every change of the schema. A database with a newer version is not
+/// initialized, so an older release cannot corrupt a schema written by a
+/// newer one.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The default total number of digits of the price columns.
+pub const DEFAULT_PRICE_PRECISION: u16 = 20;
+/// The default number of fractional digits of the price columns.
+pub const DEFAULT_PRICE_SCALE: u16 = 10;
+
+/// Options of a connection pool.
+///
+/// Without a timeout the default of SQLx is used.
+fn pool_options<DB: sqlx::Database>(
+    max_connections: Option<u32>,
+    connect_timeout_secs: Option<u64>,
+) -> PoolOptions<DB> {
+    let options =
+        PoolOptions::new().max_connections(max_connections.unwrap_or(DEFAULT_MAX_CONNECTIONS));
+
+    match connect_timeout_secs {
+        Some(secs) => options.acquire_timeout(Duration::from_secs(secs)),
+        None => options,
+    }
+}
+
+/// SQL type of the price and volume columns, e.g. `DECIMAL(20, 10)`.
+///
+/// Without a precision or scale the defaults [`DEFAULT_PRICE_PRECISION`] and
+/// [`DEFAULT_PRICE_SCALE`] are used. The precision must be positive and the
+/// scale must not exceed the precision.
+#[cfg(any(feature = "mysql", feature = "postgres"))]
+fn price_type(precision: Option<u16>, scale: Option<u16>) -> Result<String, Error> {
+    let precision = precision.unwrap_or(DEFAULT_PRICE_PRECISION);
+    let scale = scale.unwrap_or(DEFAULT_PRICE_SCALE);
+
+    if precision == 0 {
+        return Err(Error::DbConfig("`price_precision` must be positive".into()));
+    }
+    if scale > precision {
+        return Err(Error::DbConfig(format!(
+            "`price_scale` {scale} exceeds `price_precision` {precision}"
+        )));
+    }
+    Ok(format!("DECIMAL({precision}, {scale})"))
+}
+
+/// Candle with the prices and the volume rounded to the scale of the columns.
+///
+/// Without a scale the default [`DEFAULT_PRICE_SCALE`] is used. Midpoints are
+/// rounded away from zero like by the database, so the candle equals the
+/// stored candle. Rounding keeps the order of the prices, so the high price
+/// stays the highest and the low price the lowest.
+#[cfg(any(feature = "mysql", feature = "postgres"))]
+fn round_prices(candle: &Candle, scale: Option<u16>) -> Candle {
+    let scale = u32::from(scale.unwrap_or(DEFAULT_PRICE_SCALE));
+    let round = |value: Decimal| {
+        value.round_dp_with_strategy(scale, rust_decimal::RoundingStrategy::MidpointAwayFromZero)
+    };
+
+    Candle {
+        open: round(candle.open),
+        high: round(candle.high),
+        low: round(candle.low),
+        close: round(candle.close),
+        volume: round(candle.volume),
+        ..*candle
+    }
+}
+
+/// Check that the connection is given either by the URL or by the fields.
+///
+/// The `fields` are the names of the discrete connection fields and whether
+/// they are set. If the URL is set, none of the fields may be set. Otherwise
+/// the `required` fields must be set.
+fn check_url(url: bool, fields: &[(&str, bool)], required: &[&str]) -> Result<(), Error> {
+    let names = |conflicting: bool| {
+        fields
+            .iter()
+            .filter(|(name, set)| {
+                if conflicting {
+                    *set
+                } else {
+                    !set && required.contains(name)
+                }
+            })
+            .map(|(name, _)| format!("`{name}`"))
+            .collect::<Vec<_>>()
+    };
+
+    if url {
+        let conflicting = names(true);
+
+        if !conflicting.is_empty() {
+            return Err(Error::DbConfig(format!(
+                "`url` conflicts with {}",
+                conflicting.join(", ")
+            )));
+        }
+    } else {
+        let missing = names(false);
+
+        if !missing.is_empty() {
+            return Err(Error::DbConfig(format!(
+                "either `url` or {} must be set",
+                missing.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The name of the table storing the schema version for the table prefix,
+/// e.g. `candles_schema_version`.
+fn version_table(prefix: &str) -> String {
+    format!("{prefix}_schema_version")
+}
+
+/// Statement creating the table of the schema version.
+///
+/// The constant primary key restricts the table to a single row.
+fn create_version_statement(table: &str) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {table} (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            version INTEGER NOT NULL
+        )"
+    )
+}
+
+/// Check the schema version stored in the database.
+///
+/// A database without a version is either new or was initialized before the
+/// version was recorded, both are compatible.
+fn check_schema_version(version: Option<i64>) -> Result<(), Error> {
+    match version {
+        Some(version) if version > i64::from(SCHEMA_VERSION) => {
+            Err(Error::SchemaVersion(version, SCHEMA_VERSION))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Coins of the candle tables among the table names.
+///
+/// The table names are parsed by [`Coin::from_table_name_with_prefix()`].
+/// Other tables, including the table of the schema version, are skipped.
+fn coins_from_tables(tables: impl IntoIterator<Item = String>, prefix: &str) -> Vec<Coin> {
+    let version = version_table(prefix);
+    let mut coins = tables
+        .into_iter()
+        .filter(|table| *table != version)
+        .filter_map(|table| {
+            let coin = Coin::from_table_name_with_prefix(&table, prefix);
+
+            if coin.is_none() && is_prefixed(&table, prefix) {
+                warn!("Skipping table `{table}` not matching a coin");
+            }
+            coin
+        })
+        .collect::<Vec<_>>();
+
+    coins.sort_by_cached_key(Coin::table_name);
+    coins
+}
+
+/// Check if the table belongs to the tables of the prefix.
+///
+/// The table name must continue with an underscore after the prefix, so the
+/// tables of a prefix are never mistaken for those of a longer prefix.
+fn is_prefixed(table: &str, prefix: &str) -> bool {
+    table
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.starts_with('_'))
+}
 
-use crate::{Coin, Error};
+/// Timestamp as SQL literal in UTC without the offset, e.g.
+/// `2024-01-01 00:05:00`.
+#[cfg(any(feature = "mysql", feature = "postgres"))]
+fn sql_timestamp(timestamp: OffsetDateTime) -> String {
+    let timestamp = timestamp.to_offset(time::UtcOffset::UTC);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        timestamp.year(),
+        u8::from(timestamp.month()),
+        timestamp.day(),
+        timestamp.hour(),
+        timestamp.minute(),
+        timestamp.second()
+    )
+}
+
+/// Start, end and expected number of candles of a day for the timeframe.
+fn day_bounds(day: Date, timeframe: Timeframe) -> (OffsetDateTime, OffsetDateTime, i64) {
+    let start = day.midnight().assume_utc();
+    let end = start + Timeframe::OneDay.duration();
+
+    (start, end, i64::from(timeframe.candles_per_day()))
+}
+
+/// Timeframes and their bounds of the candles to delete.
+///
+/// Without a timeframe the range is resolved for every timeframe, as the
+/// boundaries differ between the timeframes.
+fn delete_bounds<R>(
+    timeframe: Option<Timeframe>,
+    range: &R,
+) -> Vec<(Timeframe, OffsetDateTime, OffsetDateTime)>
+where
+    R: RangeBounds<OffsetDateTime>,
+{
+    timeframe
+        .as_ref()
+        .map_or(Timeframe::ALL.as_slice(), std::slice::from_ref)
+        .iter()
+        .map(|timeframe| {
+            let (start, end) = timeframe.range((range.start_bound(), range.end_bound()));
+            (*timeframe, start, end)
+        })
+        .collect()
+}
+
+/// Columns of a row of a candle table in the order of [`SELECT_CANDLES`].
+///
+/// The types of the sources and prices depend on the database.
+type CandleRow<S, P> = (OffsetDateTime, String, S, P, P, P, P, P);
+
+/// Columns selected when querying candles.
+const SELECT_CANDLES: &str = "time_stamp, time_frame, sources, open, high, low, close, volume";
+
+/// Create a candle from a row of a candle table.
+///
+/// The prices are converted by `price` receiving the name of the column and
+/// its value. A warning is logged if the candle violates its invariants.
+fn candle_from_row<S, P>(
+    (timestamp, timeframe, sources, open, high, low, close, volume): CandleRow<S, P>,
+    price: impl Fn(&str, P) -> Result<Decimal, Error>,
+) -> Result<Candle, Error>
+where
+    S: TryInto<usize>,
+{
+    let candle = Candle {
+        timestamp,
+        timeframe: timeframe
+            .parse()
+            .map_err(|err| column_error("time_frame", format!("unknown timeframe `{err}`")))?,
+        sources: sources
+            .try_into()
+            .ok()
+            .and_then(NonZero::new)
+            .ok_or_else(|| column_error("sources", "number of sources must be positive"))?,
+        open: price("open", open)?,
+        high: price("high", high)?,
+        low: price("low", low)?,
+        close: price("close", close)?,
+        volume: price("volume", volume)?,
+    };
+
+    if let Err(err) = candle.validate() {
+        warn!("Queried {err}");
+    }
+    Ok(candle)
+}
+
+/// Error of a column that could not be decoded.
+fn column_error(column: &str, err: impl Into<BoxDynError>) -> Error {
+    Error::SqlSelect(Box::new(sqlx::Error::ColumnDecode {
+        index: column.to_owned(),
+        source: err.into(),
+    }))
+}
 
 /// Trait for interacting with a database.
 pub trait Database: DeserializeOwned + fmt::Debug {
@@ -53,15 +349,51 @@ pub trait Database: DeserializeOwned + fmt::Debug {
     #[must_use]
     fn requires_credentials(&self) -> bool;
 
+    /// Get the prefix of the table names.
+    ///
+    /// The prefix namespaces the table of the schema version and the tables
+    /// found by [`list_coins()`](Database::list_coins) and dropped by
+    /// [`drop_schema()`](Database::drop_schema) without coins, so several
+    /// datasets can share a database. The tables of the coins are named by
+    /// [`Coin::table_prefix()`], so the coins passed to the database are
+    /// expected to carry the same prefix. Without a configured prefix the
+    /// default [`Coin::DEFAULT_TABLE_PREFIX`] is used.
+    #[must_use]
+    fn table_prefix(&self) -> &str;
+
+    /// Get the statements creating the tables of the coins.
+    ///
+    /// These are the statements executed by
+    /// [`init_schema()`](Database::init_schema) in the same order, without a
+    /// terminating semicolon. They allow to review the schema or to apply it
+    /// by other tools. The first statement creates the table of the schema
+    /// version and the last one records [`SCHEMA_VERSION`]. The check of the
+    /// stored version is not included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration of the columns is invalid.
+    fn schema_statements(&self, coins: &[Coin]) -> Result<Vec<String>, Error>;
+
     /// Initialize the database schema.
     ///
     /// The credentials are optional and may be used to connect to the database
     /// as a alternative user. The coins are used to create the tables for the
     /// specified coins.
     ///
+    /// The tables are created in a single transaction, which is rolled back on
+    /// any error. MySQL commits every table definition implicitly, so there the
+    /// operation is not atomic.
+    ///
+    /// The version of the schema is recorded in the table
+    /// `candles_schema_version`. If the database holds a schema newer than
+    /// [`SCHEMA_VERSION`], nothing is changed.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the schema could not be initialized.
+    /// Returns [`Error::SchemaVersion`] if the schema of the database is newer
+    /// than [`SCHEMA_VERSION`], or another error if the schema could not be
+    /// initialized.
     fn init_schema(
         &mut self,
         creds: Option<Credentials>,
@@ -74,7 +406,11 @@ pub trait Database: DeserializeOwned + fmt::Debug {
     /// as a alternative user. The coins are used to drop the tables for the
     /// specified coins.
     ///
-    /// If the coins are not specified, all tables are dropped.
+    /// If the coins are not specified, all tables of the
+    /// [`table_prefix()`](Database::table_prefix) are dropped, including the
+    /// table of the schema version. Like
+    /// [`init_schema()`](Database::init_schema) the tables are dropped in a
+    /// single transaction.
     ///
     /// # Errors
     ///
@@ -84,6 +420,238 @@ pub trait Database: DeserializeOwned + fmt::Debug {
         creds: Option<Credentials>,
         coins: Option<&[Coin]>,
     ) -> impl Future<Output = Result<(), Error>>;
+
+    /// Insert the candles of the coin into the database.
+    ///
+    /// Existing candles with the same timestamp and timeframe are replaced.
+    /// The candles are inserted in chunks of multiple rows per statement, all
+    /// within a single transaction. If a chunk fails, none of the candles are
+    /// stored. Returns the number of affected rows as reported by the
+    /// database.
+    ///
+    /// MySQL and PostgreSQL round the prices and the volume to the scale of
+    /// their columns before inserting them, so the stored candles equal the
+    /// rounded candles. SQLite stores the prices unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candles could not be inserted.
+    fn insert_candles(
+        &mut self,
+        coin: &Coin,
+        candles: &[Candle],
+    ) -> impl Future<Output = Result<u64, Error>>;
+
+    /// Insert a large number of candles of the coin into the database.
+    ///
+    /// Like [`insert_candles()`](Database::insert_candles), but uses the
+    /// fastest way of the backend to load the candles, e.g. for imports of
+    /// millions of candles. PostgreSQL streams the candles with `COPY` into a
+    /// temporary table and merges them into the table of the coin. MySQL sends
+    /// statements of as many rows as fit into its maximum packet size. Other
+    /// backends fall back to [`insert_candles()`](Database::insert_candles).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candles could not be inserted.
+    fn bulk_insert_candles(
+        &mut self,
+        coin: &Coin,
+        candles: &[Candle],
+    ) -> impl Future<Output = Result<u64, Error>> {
+        self.insert_candles(coin, candles)
+    }
+
+    /// Delete the candles of the coin within the range.
+    ///
+    /// The range is resolved by [`Timeframe::range`], so every candle
+    /// containing any time of the range is deleted and an unbounded end keeps
+    /// the incomplete current candle. If the timeframe is given, only the
+    /// candles of the timeframe are deleted, otherwise the candles of all
+    /// timeframes. All candles are deleted within a single transaction.
+    /// Returns the number of deleted candles.
+    ///
+    /// A corrupted range can be deleted this way before it is fetched again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candles could not be deleted.
+    fn delete_candles<R>(
+        &mut self,
+        coin: &Coin,
+        timeframe: Option<Timeframe>,
+        range: R,
+    ) -> impl Future<Output = Result<u64, Error>>
+    where
+        R: RangeBounds<OffsetDateTime>;
+
+    /// Replace the candles of the coin within the range by the given candles.
+    ///
+    /// The candles within the range are deleted like by
+    /// [`delete_candles()`](Database::delete_candles) and the given candles
+    /// are inserted like by [`insert_candles()`](Database::insert_candles),
+    /// both within a single transaction. If the insertion fails, the deleted
+    /// candles are restored, so the range is never left empty. Returns the
+    /// number of deleted candles and the number of affected rows of the
+    /// insertion.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candles could not be deleted or inserted.
+    fn replace_candles<R>(
+        &mut self,
+        coin: &Coin,
+        timeframe: Option<Timeframe>,
+        range: R,
+        candles: &[Candle],
+    ) -> impl Future<Output = Result<(u64, u64), Error>>
+    where
+        R: RangeBounds<OffsetDateTime>;
+
+    /// Copy all candles of the coin `from` to the coin `to`.
+    ///
+    /// The table of `to` is created if it does not exist, so the candles of a
+    /// renamed or re-denominated coin can be moved without an export and
+    /// import. The rows are copied by a single `INSERT INTO ... SELECT`, and
+    /// existing candles of `to` with the same timestamp and timeframe are
+    /// replaced. Everything runs within a single transaction. MySQL commits
+    /// the creation of the table implicitly, so there a failed copy may leave
+    /// an empty table behind. The table of `from` is kept. Returns the number
+    /// of affected rows as reported by the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TableMissing`] if the table of `from` does not exist,
+    /// or another error if the table could not be created or the candles could
+    /// not be copied.
+    fn copy_coin(&mut self, from: &Coin, to: &Coin) -> impl Future<Output = Result<u64, Error>>;
+
+    /// Check if all candles of the timeframe are stored for the day.
+    ///
+    /// The day is a date in UTC. For the timeframe of five minutes a full day
+    /// consists of 288 candles.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candles could not be counted.
+    fn has_candles(
+        &mut self,
+        coin: &Coin,
+        timeframe: Timeframe,
+        day: Date,
+    ) -> impl Future<Output = Result<bool, Error>>;
+
+    /// Count the candles of the coin.
+    ///
+    /// If the timeframe is given, only the candles of the timeframe are
+    /// counted, otherwise the candles of all timeframes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candles could not be counted.
+    fn count_candles(
+        &mut self,
+        coin: &Coin,
+        timeframe: Option<Timeframe>,
+    ) -> impl Future<Output = Result<u64, Error>>;
+
+    /// Get the timestamp of the latest stored candle of the timeframe.
+    ///
+    /// Returns `None` if no candles of the timeframe are stored. A fetch can
+    /// resume after this candle instead of downloading whole days again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the timestamp could not be queried.
+    fn latest_timestamp(
+        &mut self,
+        coin: &Coin,
+        timeframe: Timeframe,
+    ) -> impl Future<Output = Result<Option<OffsetDateTime>, Error>>;
+
+    /// Get the candle of the timeframe starting at the timestamp.
+    ///
+    /// The candle is looked up by the primary key of the table, e.g. to verify
+    /// a candle after a fetch or to get the candles bounding a gap. The
+    /// timestamp must be the start of the candle. Returns `None` if no such
+    /// candle is stored. Like [`query_candles()`](Database::query_candles) a
+    /// stored candle violating the invariants of [`Candle::new`] is returned
+    /// and a warning is logged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candle could not be queried or decoded.
+    fn get_candle(
+        &mut self,
+        coin: &Coin,
+        timeframe: Timeframe,
+        timestamp: OffsetDateTime,
+    ) -> impl Future<Output = Result<Option<Candle>, Error>>;
+
+    /// Query the candles of the coin for the timeframe.
+    ///
+    /// The candles from `start` (inclusive) to `end` (exclusive) are returned
+    /// sorted by timestamp. Stored candles violating the invariants of
+    /// [`Candle::new`] are returned as well and a warning is logged, so that
+    /// existing data stays readable. Use [`Candle::is_valid`] to reject them.
+    ///
+    /// All candles are loaded into memory. For bulk work like exporting a
+    /// whole table use [`stream_candles()`](Database::stream_candles) instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candles could not be queried or decoded.
+    fn query_candles<'a>(
+        &'a mut self,
+        coin: &'a Coin,
+        timeframe: Timeframe,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> impl Future<Output = Result<Vec<Candle>, Error>> + 'a {
+        self.stream_candles(coin, timeframe, start, end)
+            .try_collect()
+    }
+
+    /// Stream the candles of the coin for the timeframe.
+    ///
+    /// Like [`query_candles()`](Database::query_candles), but the candles are
+    /// fetched one by one from a database cursor, so the memory usage does not
+    /// grow with the number of candles.
+    ///
+    /// # Errors
+    ///
+    /// The stream yields an error if the connection fails or a candle could
+    /// not be queried or decoded.
+    fn stream_candles<'a>(
+        &'a mut self,
+        coin: &'a Coin,
+        timeframe: Timeframe,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> BoxStream<'a, Result<Candle, Error>>;
+
+    /// List the coins stored in the database.
+    ///
+    /// The coins are parsed from the names of the candle tables with the
+    /// [`table_prefix()`](Database::table_prefix) and carry the prefix. As the
+    /// name of a coin is not stored, the symbol is used as name. Tables with
+    /// an unknown currency are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tables could not be listed.
+    fn list_coins(&mut self) -> impl Future<Output = Result<Vec<Coin>, Error>>;
+
+    /// Check the connection to the database.
+    ///
+    /// Runs `SELECT 1` to verify the connectivity and the credentials without
+    /// touching the schema. The query is cheap, so the method can be called
+    /// frequently, e.g. for liveness checks.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SqlConnect`] if the database cannot be reached.
+    fn ping(&mut self) -> impl Future<Output = Result<(), Error>>;
 }
 
 mod credentials;
@@ -103,3 +671,119 @@ pub mod postgres;
 #[cfg(feature = "sqlite")]
 #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
 pub mod sqlite;
+
+#[cfg(test)]
+mod tests {
+    use crate::Currency;
+
+    use super::*;
+
+    #[cfg(any(feature = "mysql", feature = "postgres"))]
+    #[test]
+    fn price_type() {
+        assert_eq!(super::price_type(None, None).unwrap(), "DECIMAL(20, 10)");
+        assert_eq!(
+            super::price_type(Some(38), Some(18)).unwrap(),
+            "DECIMAL(38, 18)"
+        );
+        assert_eq!(
+            super::price_type(Some(4), None).unwrap_err().to_string(),
+            "invalid database configuration: `price_scale` 10 exceeds `price_precision` 4"
+        );
+        assert!(super::price_type(Some(0), Some(0)).is_err());
+    }
+
+    #[cfg(any(feature = "mysql", feature = "postgres"))]
+    #[test]
+    fn round_prices() {
+        use rust_decimal_macros::dec;
+
+        let candle = Candle {
+            open: dec!(0.000000000001),
+            high: dec!(0.0000000000015),
+            low: dec!(0.000000000001),
+            close: dec!(0.0000000000014),
+            volume: dec!(100.66666666666666666666),
+            ..Candle::default()
+        };
+        let exact = super::round_prices(&candle, Some(18));
+        let rounded = super::round_prices(&candle, None);
+
+        assert_eq!(exact.open, dec!(0.000000000001));
+        assert_eq!(exact.high, dec!(0.0000000000015));
+        assert_eq!(exact.close, dec!(0.0000000000014));
+        assert_eq!(exact.volume, dec!(100.666666666666666667));
+        assert_eq!(rounded.open, Decimal::ZERO);
+        assert_eq!(rounded.high, Decimal::ZERO);
+        assert_eq!(rounded.low, Decimal::ZERO);
+        assert_eq!(rounded.close, Decimal::ZERO);
+        assert_eq!(rounded.volume, dec!(100.6666666667));
+        assert_eq!(
+            super::round_prices(
+                &Candle {
+                    high: dec!(0.00000000005),
+                    ..candle
+                },
+                None
+            )
+            .high,
+            dec!(0.0000000001)
+        );
+    }
+
+    #[test]
+    fn coins_from_table_names() {
+        let tables = [
+            "candles_eth_eur",
+            "candles_btc_usd",
+            "candles_btc_usd_range_idx",
+            "candles_btc_xyz",
+            "candles_schema_version",
+            "users",
+        ];
+        let coins = coins_from_tables(tables.map(str::to_owned), Coin::DEFAULT_TABLE_PREFIX);
+
+        assert_eq!(
+            coins,
+            [
+                Coin::new("BTC", "BTC", Currency::USD).unwrap(),
+                Coin::new("ETH", "ETH", Currency::EUR).unwrap(),
+            ]
+        );
+        assert_eq!(coins[1].currency(), Currency::EUR);
+
+        let tables = ["ohlcv_btc_usd", "ohlcv_schema_version", "ohlcveu_eth_eur"];
+        assert_eq!(
+            coins_from_tables(tables.map(str::to_owned), "ohlcv"),
+            [Coin::new("BTC", "BTC", Currency::USD)
+                .and_then(|coin| coin.with_table_prefix("ohlcv"))
+                .unwrap()]
+        );
+        assert!(is_prefixed("ohlcv_btc_usd", "ohlcv"));
+        assert!(!is_prefixed("ohlcveu_btc_usd", "ohlcv"));
+    }
+
+    #[test]
+    fn url_or_fields() {
+        let required = ["host", "database"];
+
+        assert_eq!(
+            check_url(true, &[("host", false), ("database", false)], &required),
+            Ok(())
+        );
+        assert_eq!(
+            check_url(false, &[("host", true), ("database", true)], &required),
+            Ok(())
+        );
+        assert_eq!(
+            check_url(true, &[("host", true), ("database", false)], &required),
+            Err(Error::DbConfig("`url` conflicts with `host`".to_owned()))
+        );
+        assert_eq!(
+            check_url(false, &[("host", false), ("database", false)], &required),
+            Err(Error::DbConfig(
+                "either `url` or `host`, `database` must be set".to_owned()
+            ))
+        );
+    }
+}
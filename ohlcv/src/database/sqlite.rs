@@ -1,12 +1,26 @@
 //! SQLite database implementation.
 
+use std::{iter, ops::RangeBounds, str::FromStr};
+
+use async_stream::try_stream;
+use futures_util::{stream::BoxStream, TryStreamExt};
+use rust_decimal::Decimal;
 use serde::Deserialize;
-use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePoolOptions, Sqlite};
+use sqlx::{
+    migrate::MigrateDatabase,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+    QueryBuilder, Sqlite, SqliteConnection,
+};
+use time::{Date, OffsetDateTime};
 use tracing::{info, instrument};
 
-use crate::{Coin, Error};
+use crate::{Candle, Coin, Error, Timeframe};
 
-use super::{Credentials, Database};
+use super::{
+    candle_from_row, check_schema_version, check_url, coins_from_tables, column_error,
+    create_version_statement, day_bounds, delete_bounds, is_prefixed, pool_options, version_table,
+    CandleRow, Credentials, Database, INSERT_CHUNK_SIZE, SCHEMA_VERSION, SELECT_CANDLES,
+};
 
 /// The type of database.
 pub type Db = Sqlite;
@@ -24,35 +38,110 @@ pub type DbOptions = SqlitePoolOptions;
 ///
 /// The configuration includes the following fields:
 ///
+/// - `url`: The URL of the database, e.g. `sqlite://ohlcv.db?mode=rwc`. If set,
+///   it is used instead of `database`, which must not be set then.
 /// - `database`: The name of the database.
+/// - `max_connections`: The maximum number of connections of the pool. If not
+///   set, `5` connections are used.
+/// - `connect_timeout_secs`: The maximum time in seconds to wait for a
+///   connection. If not set, the default of SQLx is used.
+/// - `read_only`: Open the database read-only, e.g. for exporting. If not set,
+///   the database is opened for reading and writing.
+/// - `journal_mode`: The journal mode set on every connection, one of
+///   `delete`, `truncate`, `persist`, `memory`, `wal` or `off`. The mode `wal`
+///   lets readers continue while a fetch writes. If not set, the journal mode
+///   of the database file is kept.
 ///
-/// On initialization, the database is created if it does not exist. This
-/// differs from the other database types, where the database must be created
-/// and managed beforehand.
+/// On initialization, the database is created if it does not exist and is not
+/// opened read-only. This differs from the other database types, where the
+/// database must be created and managed beforehand.
 #[derive(Debug, Default, Deserialize)]
 pub struct DbConfig {
-    database: String,
+    url: Option<String>,
+    database: Option<String>,
+    max_connections: Option<u32>,
+    connect_timeout_secs: Option<u64>,
+    read_only: Option<bool>,
+    journal_mode: Option<String>,
+    #[serde(skip)]
+    table_prefix: Option<String>,
     #[serde(skip)]
     pool: Option<DbPool>,
 }
 
 impl DbConfig {
+    /// Create the configuration from a database URL.
+    pub(super) fn from_url(url: &str) -> Result<Self, Error> {
+        SqliteConnectOptions::from_str(url).map_err(|err| Error::DbConfig(err.to_string()))?;
+
+        Ok(Self {
+            url: Some(url.to_owned()),
+            ..Self::default()
+        })
+    }
+
+    /// Set the prefix of the table names, see [`Database::table_prefix`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prefix is not valid, see
+    /// [`Coin::check_table_prefix`].
+    pub fn with_table_prefix(mut self, prefix: impl Into<String>) -> Result<Self, Error> {
+        let prefix = prefix.into();
+
+        Coin::check_table_prefix(&prefix)?;
+        self.table_prefix = Some(prefix);
+        Ok(self)
+    }
+
+    /// URL of the database given by `url` or `database`.
+    fn url(&self) -> Result<String, Error> {
+        check_url(
+            self.url.is_some(),
+            &[("database", self.database.is_some())],
+            &["database"],
+        )?;
+
+        match (&self.url, &self.database) {
+            (Some(url), _) => Ok(url.clone()),
+            (None, Some(database)) => Ok(format!("sqlite://{database}")),
+            (None, None) => unreachable!("checked above"),
+        }
+    }
+
+    /// Options to connect to the database with the configured access and
+    /// journal mode.
+    fn connect_options(&self, url: &str) -> Result<SqliteConnectOptions, Error> {
+        let mut options = SqliteConnectOptions::from_str(url)
+            .map_err(|err| Error::DbConfig(err.to_string()))?
+            .read_only(self.read_only.unwrap_or_default());
+
+        if let Some(mode) = &self.journal_mode {
+            let mode = SqliteJournalMode::from_str(mode)
+                .map_err(|err| Error::DbConfig(err.to_string()))?;
+
+            options = options.journal_mode(mode);
+        }
+        Ok(options)
+    }
+
     #[instrument(skip(self))]
     async fn db(&mut self) -> Result<&DbPool, Error> {
-        let exists = Db::database_exists(&self.database)
-            .await
-            .map_err(|err| Error::SqlConnect("default user".to_owned(), Box::new(err)))?;
-
-        if !exists {
-            Db::create_database(&self.database)
+        if self.pool.is_none() {
+            let url = self.url()?;
+            let options = self.connect_options(&url)?;
+            let exists = Db::database_exists(&url)
                 .await
                 .map_err(|err| Error::SqlConnect("default user".to_owned(), Box::new(err)))?;
-        }
-        if self.pool.is_none() {
-            let url = format!("sqlite://{}", self.database);
-            let pool = DbOptions::new()
-                .max_connections(5)
-                .connect(&url)
+
+            if !exists && !self.read_only.unwrap_or_default() {
+                Db::create_database(&url)
+                    .await
+                    .map_err(|err| Error::SqlConnect("default user".to_owned(), Box::new(err)))?;
+            }
+
+            let pool = pool_options::<Db>(self.max_connections, self.connect_timeout_secs)
+                .connect_with(options)
                 .await
                 .map_err(|err| Error::SqlConnect("default user".to_owned(), Box::new(err)))?;
             self.pool = Some(pool);
@@ -63,6 +152,105 @@ impl DbConfig {
     }
 }
 
+/// Statements creating the table of a coin and its index.
+fn create_statements(table: &str) -> [String; 2] {
+    [
+        format!(
+            "CREATE TABLE IF NOT EXISTS {table} (
+                time_stamp TIMESTAMP NOT NULL,
+                time_frame TEXT NOT NULL,
+                sources INTEGER NOT NULL CHECK (sources > 0),
+                open TEXT NOT NULL,
+                high TEXT NOT NULL,
+                low TEXT NOT NULL,
+                close TEXT NOT NULL,
+                volume TEXT NOT NULL,
+                PRIMARY KEY (time_stamp, time_frame)
+            )"
+        ),
+        format!("CREATE INDEX IF NOT EXISTS {table}_range_idx ON {table} (time_frame, time_stamp)"),
+    ]
+}
+
+/// Statement recording the current schema version in the version table.
+fn update_version_statement(table: &str) -> String {
+    format!(
+        "INSERT INTO {table} (id, version) VALUES (1, {SCHEMA_VERSION}) \
+        ON CONFLICT (id) DO UPDATE SET version = excluded.version"
+    )
+}
+
+/// Insert the candles into the table within the transaction of `conn`.
+///
+/// The candles are inserted in chunks of [`INSERT_CHUNK_SIZE`] rows. Returns
+/// the number of affected rows.
+async fn insert_rows(
+    conn: &mut SqliteConnection,
+    table: &str,
+    candles: &[Candle],
+) -> Result<u64, Error> {
+    let mut rows = 0;
+
+    for chunk in candles.chunks(INSERT_CHUNK_SIZE) {
+        let mut query = QueryBuilder::<Db>::new(format!(
+            "INSERT INTO {table} \
+            (time_stamp, time_frame, sources, open, high, low, close, volume) "
+        ));
+        query.push_values(chunk, |mut row, candle| {
+            row.push_bind(candle.timestamp)
+                .push_bind(candle.timeframe.to_string())
+                .push_bind(i64::try_from(candle.sources.get()).unwrap_or(i64::MAX))
+                .push_bind(candle.open.to_string())
+                .push_bind(candle.high.to_string())
+                .push_bind(candle.low.to_string())
+                .push_bind(candle.close.to_string())
+                .push_bind(candle.volume.to_string());
+        });
+        query.push(
+            " ON CONFLICT (time_stamp, time_frame) DO UPDATE SET \
+            sources = excluded.sources, open = excluded.open, high = excluded.high, \
+            low = excluded.low, close = excluded.close, volume = excluded.volume",
+        );
+
+        rows += query
+            .build()
+            .execute(&mut *conn)
+            .await
+            .map_err(|err| Error::SqlInsert(table.to_owned(), Box::new(err)))?
+            .rows_affected();
+    }
+    Ok(rows)
+}
+
+/// Delete the candles within the bounds from the table within the transaction
+/// of `conn`.
+///
+/// The bounds are resolved by [`delete_bounds`]. Returns the number of deleted
+/// candles.
+async fn delete_rows(
+    conn: &mut SqliteConnection,
+    table: &str,
+    bounds: Vec<(Timeframe, OffsetDateTime, OffsetDateTime)>,
+) -> Result<u64, Error> {
+    let query = format!(
+        "DELETE FROM {table} \
+        WHERE time_frame = $1 AND time_stamp >= $2 AND time_stamp < $3"
+    );
+    let mut rows = 0;
+
+    for (timeframe, start, end) in bounds {
+        rows += sqlx::query::<Db>(&query)
+            .bind(timeframe.to_string())
+            .bind(start)
+            .bind(end)
+            .execute(&mut *conn)
+            .await
+            .map_err(|err| Error::SqlDelete(table.to_owned(), Box::new(err)))?
+            .rows_affected();
+    }
+    Ok(rows)
+}
+
 impl Database for DbConfig {
     #[inline]
     fn root_username(&self) -> Option<&'static str> {
@@ -74,38 +262,71 @@ impl Database for DbConfig {
         false
     }
 
+    #[inline]
+    fn table_prefix(&self) -> &str {
+        self.table_prefix
+            .as_deref()
+            .unwrap_or(Coin::DEFAULT_TABLE_PREFIX)
+    }
+
+    fn schema_statements(&self, coins: &[Coin]) -> Result<Vec<String>, Error> {
+        let tables = coins
+            .iter()
+            .flat_map(|coin| create_statements(&coin.table_name()));
+
+        let version_table = version_table(self.table_prefix());
+
+        Ok(iter::once(create_version_statement(&version_table))
+            .chain(tables)
+            .chain(iter::once(update_version_statement(&version_table)))
+            .collect())
+    }
+
     #[instrument(skip(self, _creds, coins))]
     async fn init_schema(
         &mut self,
         _creds: Option<Credentials>,
         coins: &[Coin],
     ) -> Result<(), Error> {
+        let version_table = version_table(self.table_prefix());
         let db = self.db().await?;
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))?;
 
         info!("Initializing schema for SQLite database");
+        sqlx::query(&create_version_statement(&version_table))
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| Error::SqlCreateTable(version_table.clone(), Box::new(err)))?;
+        let version = sqlx::query_as::<Db, (i64,)>(&format!("SELECT version FROM {version_table}"))
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|err| Error::SqlSelect(Box::new(err)))?;
+        check_schema_version(version.map(|(version,)| version))?;
+
         for coin in coins {
             info!("Creating table for {coin:#}");
             let table = coin.table_name();
-            let query = format!(
-                "CREATE TABLE IF NOT EXISTS {table} (
-                    time_stamp TIMESTAMP NOT NULL,
-                    time_frame TEXT NOT NULL,
-                    sources INTEGER NOT NULL,
-                    open REAL NOT NULL,
-                    high REAL NOT NULL,
-                    low REAL NOT NULL,
-                    close REAL NOT NULL,
-                    volume REAL NOT NULL,
-                    PRIMARY KEY (time_stamp, time_frame)
-                );"
-            );
+            let [create_table, create_index] = create_statements(&table);
 
-            sqlx::query(&query)
-                .execute(db)
+            sqlx::query(&create_table)
+                .execute(&mut *tx)
+                .await
+                .map_err(|err| Error::SqlCreateTable(table.clone(), Box::new(err)))?;
+            sqlx::query(&create_index)
+                .execute(&mut *tx)
                 .await
-                .map_err(|err| Error::SqlCreateTable(table, Box::new(err)))?;
+                .map_err(|err| Error::SqlCreateIndex(table, Box::new(err)))?;
         }
-        Ok(())
+        sqlx::query(&update_version_statement(&version_table))
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| Error::SqlInsert(version_table, Box::new(err)))?;
+        tx.commit()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))
     }
 
     #[instrument(skip(self, _creds, coins))]
@@ -114,7 +335,12 @@ impl Database for DbConfig {
         _creds: Option<Credentials>,
         coins: Option<&[Coin]>,
     ) -> Result<(), Error> {
+        let prefix = self.table_prefix().to_owned();
         let db = self.db().await?;
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))?;
 
         info!("Dropping schema for SQLite database");
         if let Some(coins) = coins {
@@ -124,14 +350,14 @@ impl Database for DbConfig {
                 let query = format!("DROP TABLE IF EXISTS {table};");
 
                 sqlx::query(&query)
-                    .execute(db)
+                    .execute(&mut *tx)
                     .await
                     .map_err(|err| Error::SqlDropTable(table, Box::new(err)))?;
             }
         } else {
             let query = "SELECT name FROM sqlite_master WHERE type = 'table';";
             let tables = sqlx::query_as::<Db, (String,)>(query)
-                .fetch_all(db)
+                .fetch_all(&mut *tx)
                 .await
                 .map_err(|err| Error::SqlSelect(Box::new(err)))?;
 
@@ -139,22 +365,1087 @@ impl Database for DbConfig {
                 let table = table.0;
                 info!("Dropping table `{table}`");
 
-                if table.starts_with(Coin::table_prefix()) {
+                if is_prefixed(&table, &prefix) {
                     let query = format!("DROP TABLE IF EXISTS {table};");
 
                     sqlx::query(&query)
-                        .execute(db)
+                        .execute(&mut *tx)
                         .await
                         .map_err(|err| Error::SqlDropTable(table, Box::new(err)))?;
                 }
             }
         }
+        tx.commit()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))
+    }
+
+    #[instrument(skip(self, candles))]
+    async fn insert_candles(&mut self, coin: &Coin, candles: &[Candle]) -> Result<u64, Error> {
+        let table = coin.table_name();
+        let db = self.db().await?;
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))?;
+        let rows = insert_rows(&mut tx, &table, candles).await?;
+
+        tx.commit()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))?;
+        Ok(rows)
+    }
+
+    #[instrument(skip(self, range))]
+    async fn delete_candles<R>(
+        &mut self,
+        coin: &Coin,
+        timeframe: Option<Timeframe>,
+        range: R,
+    ) -> Result<u64, Error>
+    where
+        R: RangeBounds<OffsetDateTime>,
+    {
+        let table = coin.table_name();
+        let db = self.db().await?;
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))?;
+        let rows = delete_rows(&mut tx, &table, delete_bounds(timeframe, &range)).await?;
+
+        tx.commit()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))?;
+        Ok(rows)
+    }
+
+    #[instrument(skip(self, range, candles))]
+    async fn replace_candles<R>(
+        &mut self,
+        coin: &Coin,
+        timeframe: Option<Timeframe>,
+        range: R,
+        candles: &[Candle],
+    ) -> Result<(u64, u64), Error>
+    where
+        R: RangeBounds<OffsetDateTime>,
+    {
+        let table = coin.table_name();
+        let db = self.db().await?;
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))?;
+        let deleted = delete_rows(&mut tx, &table, delete_bounds(timeframe, &range)).await?;
+        let inserted = insert_rows(&mut tx, &table, candles).await?;
+
+        tx.commit()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))?;
+        Ok((deleted, inserted))
+    }
+
+    #[instrument(skip(self))]
+    async fn copy_coin(&mut self, from: &Coin, to: &Coin) -> Result<u64, Error> {
+        let source = from.table_name();
+        let target = to.table_name();
+        let db = self.db().await?;
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))?;
+
+        sqlx::query("SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(&source)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|err| Error::SqlSelect(Box::new(err)))?
+            .ok_or_else(|| Error::TableMissing(source.clone()))?;
+
+        info!("Copying the candles of {from:#} to {to:#}");
+        let [create_table, create_index] = create_statements(&target);
+
+        sqlx::query(&create_table)
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| Error::SqlCreateTable(target.clone(), Box::new(err)))?;
+        sqlx::query(&create_index)
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| Error::SqlCreateIndex(target.clone(), Box::new(err)))?;
+        // The `WHERE` clause resolves the ambiguity of `ON` after a `SELECT`.
+        let rows = sqlx::query(&format!(
+            "INSERT INTO {target} ({SELECT_CANDLES}) \
+            SELECT {SELECT_CANDLES} FROM {source} WHERE true \
+            ON CONFLICT (time_stamp, time_frame) DO UPDATE SET \
+            sources = excluded.sources, open = excluded.open, high = excluded.high, \
+            low = excluded.low, close = excluded.close, volume = excluded.volume"
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| Error::SqlInsert(target, Box::new(err)))?
+        .rows_affected();
+
+        tx.commit()
+            .await
+            .map_err(|err| Error::SqlTransaction(Box::new(err)))?;
+        Ok(rows)
+    }
+
+    #[instrument(skip(self))]
+    async fn has_candles(
+        &mut self,
+        coin: &Coin,
+        timeframe: Timeframe,
+        day: Date,
+    ) -> Result<bool, Error> {
+        let table = coin.table_name();
+        let (start, end, expected) = day_bounds(day, timeframe);
+        let db = self.db().await?;
+        let query = format!(
+            "SELECT COUNT(*) FROM {table} \
+            WHERE time_frame = $1 AND time_stamp >= $2 AND time_stamp < $3"
+        );
+
+        let (count,) = sqlx::query_as::<Db, (i64,)>(&query)
+            .bind(timeframe.to_string())
+            .bind(start)
+            .bind(end)
+            .fetch_one(db)
+            .await
+            .map_err(|err| Error::SqlSelect(Box::new(err)))?;
+        Ok(count >= expected)
+    }
+
+    #[instrument(skip(self))]
+    async fn count_candles(
+        &mut self,
+        coin: &Coin,
+        timeframe: Option<Timeframe>,
+    ) -> Result<u64, Error> {
+        let table = coin.table_name();
+        let db = self.db().await?;
+        let mut query = QueryBuilder::<Db>::new(format!("SELECT COUNT(*) FROM {table}"));
+
+        if let Some(timeframe) = timeframe {
+            query
+                .push(" WHERE time_frame = ")
+                .push_bind(timeframe.to_string());
+        }
+
+        let (count,) = query
+            .build_query_as::<(i64,)>()
+            .fetch_one(db)
+            .await
+            .map_err(|err| Error::SqlSelect(Box::new(err)))?;
+        Ok(u64::try_from(count).unwrap_or_default())
+    }
+
+    #[instrument(skip(self))]
+    async fn latest_timestamp(
+        &mut self,
+        coin: &Coin,
+        timeframe: Timeframe,
+    ) -> Result<Option<OffsetDateTime>, Error> {
+        let table = coin.table_name();
+        let db = self.db().await?;
+        let query = format!(
+            "SELECT time_stamp FROM {table} WHERE time_frame = $1 \
+            ORDER BY time_stamp DESC LIMIT 1"
+        );
+
+        let row = sqlx::query_as::<Db, (OffsetDateTime,)>(&query)
+            .bind(timeframe.to_string())
+            .fetch_optional(db)
+            .await
+            .map_err(|err| Error::SqlSelect(Box::new(err)))?;
+        Ok(row.map(|(timestamp,)| timestamp))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_candle(
+        &mut self,
+        coin: &Coin,
+        timeframe: Timeframe,
+        timestamp: OffsetDateTime,
+    ) -> Result<Option<Candle>, Error> {
+        let table = coin.table_name();
+        let db = self.db().await?;
+        let query = format!(
+            "SELECT {SELECT_CANDLES} FROM {table} WHERE time_stamp = $1 AND time_frame = $2"
+        );
+
+        let row = sqlx::query_as::<Db, CandleRow<i64, String>>(&query)
+            .bind(timestamp)
+            .bind(timeframe.to_string())
+            .fetch_optional(db)
+            .await
+            .map_err(|err| Error::SqlSelect(Box::new(err)))?;
+        row.map(|row| {
+            candle_from_row(row, |column, price| {
+                Decimal::from_str(&price).map_err(|err| column_error(column, err))
+            })
+        })
+        .transpose()
+    }
+
+    fn stream_candles<'a>(
+        &'a mut self,
+        coin: &'a Coin,
+        timeframe: Timeframe,
+        start: OffsetDateTime,
+        end: OffsetDateTime,
+    ) -> BoxStream<'a, Result<Candle, Error>> {
+        let table = coin.table_name();
+
+        Box::pin(try_stream! {
+            let db = self.db().await?;
+            let query = format!(
+                "SELECT {SELECT_CANDLES} FROM {table} \
+                WHERE time_frame = $1 AND time_stamp >= $2 AND time_stamp < $3 \
+                ORDER BY time_stamp"
+            );
+            let mut rows = sqlx::query_as::<Db, CandleRow<i64, String>>(&query)
+                .bind(timeframe.to_string())
+                .bind(start)
+                .bind(end)
+                .fetch(db);
+
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|err| Error::SqlSelect(Box::new(err)))?
+            {
+                yield candle_from_row(row, |column, price| {
+                    Decimal::from_str(&price).map_err(|err| column_error(column, err))
+                })?;
+            }
+        })
+    }
+
+    #[instrument(skip(self))]
+    async fn list_coins(&mut self) -> Result<Vec<Coin>, Error> {
+        let db = self.db().await?;
+        let tables =
+            sqlx::query_as::<Db, (String,)>("SELECT name FROM sqlite_master WHERE type = 'table';")
+                .fetch_all(db)
+                .await
+                .map_err(|err| Error::SqlSelect(Box::new(err)))?;
+
+        Ok(coins_from_tables(
+            tables.into_iter().map(|(table,)| table),
+            self.table_prefix(),
+        ))
+    }
+
+    #[instrument(skip(self))]
+    async fn ping(&mut self) -> Result<(), Error> {
+        let db = self.db().await?;
+
+        sqlx::query("SELECT 1;")
+            .execute(db)
+            .await
+            .map_err(|err| Error::SqlConnect("default user".to_owned(), Box::new(err)))?;
         Ok(())
     }
 }
 
 impl PartialEq for DbConfig {
     fn eq(&self, other: &Self) -> bool {
-        self.database == other.database
+        self.url == other.url && self.database == other.database
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::{date, datetime};
+
+    use crate::{Currency, Timeframe};
+
+    use super::*;
+
+    /// Temporary database file, deleted with its journal files on drop.
+    struct TempDb {
+        path: std::path::PathBuf,
+    }
+
+    impl TempDb {
+        /// A database file in the temporary directory named after the test.
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("ohlcv-{name}-{}.db", std::process::id()));
+
+            Self { path }
+        }
+
+        /// The path of the file as configured by `database`.
+        fn database(&self) -> String {
+            self.path.to_string_lossy().into_owned()
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let mut file = self.path.clone().into_os_string();
+                file.push(suffix);
+                let _ = std::fs::remove_file(file);
+            }
+        }
+    }
+
+    /// Configuration of a temporary database and the guard deleting it.
+    fn temp_db(name: &str) -> (DbConfig, TempDb) {
+        let db = TempDb::new(name);
+        let config = DbConfig {
+            database: Some(db.database()),
+            ..DbConfig::default()
+        };
+
+        (config, db)
+    }
+
+    #[tokio::test]
+    async fn insert_candles() {
+        let (mut config, _db) = temp_db("insert");
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let candle = |close| {
+            Candle::new(
+                datetime!(2024-01-01 00:00 UTC),
+                Timeframe::FiveMinutes,
+                dec!(100),
+                dec!(110),
+                dec!(90),
+                close,
+                dec!(5),
+            )
+            .unwrap()
+        };
+
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+        assert_eq!(
+            config
+                .insert_candles(&coin, &[candle(dec!(95))])
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            config
+                .insert_candles(&coin, &[candle(dec!(105))])
+                .await
+                .unwrap(),
+            1
+        );
+
+        let candles = config
+            .query_candles(
+                &coin,
+                Timeframe::FiveMinutes,
+                datetime!(2024-01-01 00:00 UTC),
+                datetime!(2024-01-02 00:00 UTC),
+            )
+            .await
+            .unwrap();
+        assert_eq!(candles, [candle(dec!(105))]);
+        assert_eq!(candles[0].close, dec!(105));
+        assert_eq!(
+            config.list_coins().await.unwrap(),
+            [Coin::new("BTC", "BTC", Currency::USD).unwrap()]
+        );
+    }
+
+    #[tokio::test]
+    async fn query_candles_lossless() {
+        let (mut config, _db) = temp_db("query");
+        let coin = Coin::new("SHIB", "Shiba Inu", Currency::USD).unwrap();
+        let price = dec!(0.000000012345678901);
+        let candles = [0, 5].map(|minutes| {
+            Candle::new(
+                datetime!(2024-01-01 00:00 UTC) + time::Duration::minutes(minutes),
+                Timeframe::FiveMinutes,
+                price,
+                price,
+                price,
+                price,
+                dec!(123456789.123456789),
+            )
+            .unwrap()
+        });
+
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+        config.insert_candles(&coin, &candles).await.unwrap();
+
+        let queried = config
+            .query_candles(
+                &coin,
+                Timeframe::FiveMinutes,
+                datetime!(2024-01-01 00:00 UTC),
+                datetime!(2024-01-01 00:10 UTC),
+            )
+            .await
+            .unwrap();
+        assert_eq!(queried, candles);
+        for candle in &queried {
+            assert_eq!(candle.sources, candles[0].sources);
+            assert_eq!(
+                [candle.open, candle.high, candle.low, candle.close],
+                [price; 4]
+            );
+            assert_eq!(candle.volume, dec!(123456789.123456789));
+        }
+        assert_eq!(queried[0].open.to_string(), "0.000000012345678901");
+        assert_eq!(
+            config
+                .query_candles(
+                    &coin,
+                    Timeframe::FiveMinutes,
+                    datetime!(2024-01-01 00:05 UTC),
+                    datetime!(2024-01-01 00:10 UTC),
+                )
+                .await
+                .unwrap(),
+            candles[1..]
+        );
+    }
+
+    #[tokio::test]
+    async fn insert_candles_rollback() {
+        let (mut config, _db) = temp_db("insert-tx");
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let start = datetime!(2024-01-01 00:00 UTC);
+        let candles = (0..2 * INSERT_CHUNK_SIZE)
+            .map(|index| {
+                Candle::new(
+                    start + Timeframe::FiveMinutes.duration() * u32::try_from(index).unwrap(),
+                    Timeframe::FiveMinutes,
+                    dec!(1),
+                    dec!(1),
+                    dec!(1),
+                    dec!(1),
+                    dec!(1),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+
+        // Fail the last candle, which is part of the second chunk.
+        let table = coin.table_name();
+        sqlx::query(&format!(
+            "CREATE TRIGGER fail_insert BEFORE INSERT ON {table} \
+            WHEN NEW.time_stamp = '{}' BEGIN SELECT RAISE(ABORT, 'failed'); END",
+            candles[candles.len() - 1]
+                .timestamp
+                .format(&time::format_description::well_known::Rfc3339)
+                .unwrap()
+        ))
+        .execute(config.db().await.unwrap())
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            config.insert_candles(&coin, &candles).await,
+            Err(Error::SqlInsert(name, _)) if name == table
+        ));
+
+        let (count,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(config.db().await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn ping() {
+        let (mut config, _db) = temp_db("ping");
+
+        config.ping().await.unwrap();
+        assert!(config.list_coins().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn connect_modes() {
+        let db = TempDb::new("modes");
+        let database = Some(db.database());
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let mut read_only = DbConfig {
+            database: database.clone(),
+            read_only: Some(true),
+            ..DbConfig::default()
+        };
+
+        // A missing database is not created if opened read-only.
+        assert!(matches!(
+            read_only.ping().await,
+            Err(Error::SqlConnect(_, _))
+        ));
+        assert!(!db.path.exists());
+
+        let mut config = DbConfig {
+            database: database.clone(),
+            journal_mode: Some("WAL".to_owned()),
+            ..DbConfig::default()
+        };
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+        let (mode,): (String,) = sqlx::query_as("PRAGMA journal_mode")
+            .fetch_one(config.db().await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        assert_eq!(
+            read_only.list_coins().await.unwrap(),
+            std::slice::from_ref(&coin)
+        );
+        assert!(read_only
+            .insert_candles(&coin, &[Candle::default()])
+            .await
+            .is_err());
+
+        let mut invalid = DbConfig {
+            database,
+            journal_mode: Some("fast".to_owned()),
+            ..DbConfig::default()
+        };
+        assert!(matches!(invalid.ping().await, Err(Error::DbConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn count_candles() {
+        let (mut config, _db) = temp_db("count");
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let candles = [
+            (0, Timeframe::FiveMinutes),
+            (5, Timeframe::FiveMinutes),
+            (10, Timeframe::FiveMinutes),
+            (0, Timeframe::Quarters),
+        ]
+        .map(|(minutes, timeframe)| {
+            Candle::new(
+                datetime!(2024-01-01 00:00 UTC) + time::Duration::minutes(minutes),
+                timeframe,
+                dec!(1),
+                dec!(1),
+                dec!(1),
+                dec!(1),
+                dec!(1),
+            )
+            .unwrap()
+        });
+
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+        assert_eq!(config.count_candles(&coin, None).await.unwrap(), 0);
+
+        config.insert_candles(&coin, &candles).await.unwrap();
+        assert_eq!(config.count_candles(&coin, None).await.unwrap(), 4);
+        assert_eq!(
+            config
+                .count_candles(&coin, Some(Timeframe::FiveMinutes))
+                .await
+                .unwrap(),
+            3
+        );
+        assert_eq!(
+            config
+                .count_candles(&coin, Some(Timeframe::OneHour))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            config
+                .latest_timestamp(&coin, Timeframe::FiveMinutes)
+                .await
+                .unwrap(),
+            Some(datetime!(2024-01-01 00:10 UTC))
+        );
+        assert_eq!(
+            config
+                .latest_timestamp(&coin, Timeframe::Quarters)
+                .await
+                .unwrap(),
+            Some(datetime!(2024-01-01 00:00 UTC))
+        );
+        assert_eq!(
+            config
+                .latest_timestamp(&coin, Timeframe::OneHour)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_candles() {
+        let (mut config, _db) = temp_db("delete");
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let start = datetime!(2024-01-01 00:00 UTC);
+        let candles = [
+            (0, Timeframe::FiveMinutes),
+            (5, Timeframe::FiveMinutes),
+            (10, Timeframe::FiveMinutes),
+            (15, Timeframe::FiveMinutes),
+            (0, Timeframe::Quarters),
+            (15, Timeframe::Quarters),
+        ]
+        .map(|(minutes, timeframe)| {
+            Candle::new(
+                start + time::Duration::minutes(minutes),
+                timeframe,
+                dec!(1),
+                dec!(1),
+                dec!(1),
+                dec!(1),
+                dec!(1),
+            )
+            .unwrap()
+        });
+
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+        config.insert_candles(&coin, &candles).await.unwrap();
+
+        // The candles containing the bounds are deleted.
+        let range = datetime!(2024-01-01 00:03 UTC)..=datetime!(2024-01-01 00:07 UTC);
+        assert_eq!(
+            config
+                .delete_candles(&coin, Some(Timeframe::FiveMinutes), range)
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            config
+                .count_candles(&coin, Some(Timeframe::FiveMinutes))
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            config
+                .count_candles(&coin, Some(Timeframe::Quarters))
+                .await
+                .unwrap(),
+            2
+        );
+
+        // Without a timeframe the range is resolved for every timeframe.
+        let range = datetime!(2024-01-01 00:15 UTC)..datetime!(2024-01-01 00:20 UTC);
+        assert_eq!(config.delete_candles(&coin, None, range).await.unwrap(), 2);
+
+        for (timeframe, minutes) in [(Timeframe::FiveMinutes, 10), (Timeframe::Quarters, 0)] {
+            let remaining = config
+                .query_candles(&coin, timeframe, start, datetime!(2024-01-02 00:00 UTC))
+                .await
+                .unwrap();
+            assert_eq!(
+                remaining
+                    .iter()
+                    .map(|candle| candle.timestamp)
+                    .collect::<Vec<_>>(),
+                [start + time::Duration::minutes(minutes)]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn replace_candles() {
+        let (mut config, _db) = temp_db("replace");
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let start = datetime!(2024-01-01 00:00 UTC);
+        let range = start..datetime!(2024-01-02 00:00 UTC);
+        let candle = |minutes, price| {
+            Candle::new(
+                start + time::Duration::minutes(minutes),
+                Timeframe::FiveMinutes,
+                price,
+                price,
+                price,
+                price,
+                dec!(1),
+            )
+            .unwrap()
+        };
+        let stored = [candle(0, dec!(1)), candle(5, dec!(1)), candle(10, dec!(1))];
+        let replaced = [candle(0, dec!(2)), candle(10, dec!(2))];
+
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+        config.insert_candles(&coin, &stored).await.unwrap();
+
+        // A failing insertion keeps the stored candles.
+        let table = coin.table_name();
+        sqlx::query(&format!(
+            "CREATE TRIGGER fail_insert BEFORE INSERT ON {table} \
+            WHEN NEW.open = '3' BEGIN SELECT RAISE(ABORT, 'failed'); END"
+        ))
+        .execute(config.db().await.unwrap())
+        .await
+        .unwrap();
+        assert!(matches!(
+            config
+                .replace_candles(
+                    &coin,
+                    Some(Timeframe::FiveMinutes),
+                    range.clone(),
+                    &[candle(0, dec!(3))]
+                )
+                .await,
+            Err(Error::SqlInsert(name, _)) if name == table
+        ));
+        assert_eq!(
+            config
+                .query_candles(&coin, Timeframe::FiveMinutes, range.start, range.end)
+                .await
+                .unwrap(),
+            stored
+        );
+
+        // The candles of the range are replaced entirely.
+        assert_eq!(
+            config
+                .replace_candles(
+                    &coin,
+                    Some(Timeframe::FiveMinutes),
+                    range.clone(),
+                    &replaced
+                )
+                .await
+                .unwrap(),
+            (3, 2)
+        );
+        assert_eq!(
+            config
+                .query_candles(&coin, Timeframe::FiveMinutes, range.start, range.end)
+                .await
+                .unwrap(),
+            replaced
+        );
+    }
+
+    #[tokio::test]
+    async fn sources_positive() {
+        let (mut config, _db) = temp_db("sources");
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let table = coin.table_name();
+
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+
+        let insert = |sources: i64| {
+            format!(
+                "INSERT INTO {table} \
+                (time_stamp, time_frame, sources, open, high, low, close, volume) \
+                VALUES ('2024-01-01T00:0{sources}:00Z', '5m', {sources}, '1', '1', '1', '1', '1')"
+            )
+        };
+        let db = config.db().await.unwrap();
+
+        assert!(sqlx::query(&insert(0)).execute(db).await.is_err());
+        assert!(sqlx::query(&insert(1)).execute(db).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn has_candles() {
+        let (mut config, _db) = temp_db("has");
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let day = date!(2024 - 01 - 01);
+        let candles = (0..288)
+            .map(|index| {
+                Candle::new(
+                    day.midnight().assume_utc() + time::Duration::minutes(5 * index),
+                    Timeframe::FiveMinutes,
+                    dec!(1),
+                    dec!(1),
+                    dec!(1),
+                    dec!(1),
+                    dec!(1),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+        config.insert_candles(&coin, &candles[1..]).await.unwrap();
+        assert!(!config
+            .has_candles(&coin, Timeframe::FiveMinutes, day)
+            .await
+            .unwrap());
+        config.insert_candles(&coin, &candles[..1]).await.unwrap();
+        assert!(config
+            .has_candles(&coin, Timeframe::FiveMinutes, day)
+            .await
+            .unwrap());
+        assert!(!config
+            .has_candles(&coin, Timeframe::FiveMinutes, day.next_day().unwrap())
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn get_candle() {
+        let (mut config, _db) = temp_db("get");
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let candle = Candle::new(
+            datetime!(2024-01-01 00:05 UTC),
+            Timeframe::FiveMinutes,
+            dec!(100.5),
+            dec!(110),
+            dec!(90.25),
+            dec!(105),
+            dec!(5.125),
+        )
+        .unwrap();
+
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+        config
+            .insert_candles(&coin, std::slice::from_ref(&candle))
+            .await
+            .unwrap();
+
+        let stored = config
+            .get_candle(&coin, Timeframe::FiveMinutes, candle.timestamp)
+            .await
+            .unwrap();
+        assert!(stored.is_some_and(|stored| stored.content_eq(&candle)));
+        assert_eq!(
+            config
+                .get_candle(&coin, Timeframe::OneHour, candle.timestamp)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            config
+                .get_candle(
+                    &coin,
+                    Timeframe::FiveMinutes,
+                    datetime!(2024-01-01 00:10 UTC)
+                )
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn copy_coin() {
+        let (mut config, _db) = temp_db("copy");
+        let from = Coin::new("MATIC", "Polygon", Currency::USD).unwrap();
+        let to = Coin::new("POL", "Polygon", Currency::USD).unwrap();
+        let candle = Candle::new(
+            datetime!(2024-01-01 00:05 UTC),
+            Timeframe::FiveMinutes,
+            dec!(0.5),
+            dec!(0.75),
+            dec!(0.25),
+            dec!(0.625),
+            dec!(1000),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.copy_coin(&from, &to).await,
+            Err(Error::TableMissing(from.table_name()))
+        );
+        config
+            .init_schema(None, std::slice::from_ref(&from))
+            .await
+            .unwrap();
+        config
+            .insert_candles(&from, std::slice::from_ref(&candle))
+            .await
+            .unwrap();
+
+        assert_eq!(config.copy_coin(&from, &to).await, Ok(1));
+        // Copying again replaces the candles of the destination.
+        assert_eq!(config.copy_coin(&from, &to).await, Ok(1));
+        let copied = config
+            .get_candle(&to, Timeframe::FiveMinutes, candle.timestamp)
+            .await
+            .unwrap();
+        assert!(copied.is_some_and(|copied| copied.content_eq(&candle)));
+        assert_eq!(config.count_candles(&from, None).await, Ok(1));
+        assert_eq!(config.list_coins().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn init_schema() {
+        let (mut config, _db) = temp_db("init");
+        let coin = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+        config
+            .init_schema(None, std::slice::from_ref(&coin))
+            .await
+            .unwrap();
+
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name = $1")
+                .bind(format!("{}_range_idx", coin.table_name()))
+                .fetch_one(config.db().await.unwrap())
+                .await
+                .unwrap();
+        assert_eq!(count, 1);
+
+        // SQLite stores the definitions without the `IF NOT EXISTS` clause.
+        let statements = config
+            .schema_statements(std::slice::from_ref(&coin))
+            .unwrap()
+            .into_iter()
+            .filter(|statement| statement.starts_with("CREATE "))
+            .map(|statement| statement.replace("IF NOT EXISTS ", ""))
+            .collect::<Vec<_>>();
+        let stored: Vec<(String,)> =
+            sqlx::query_as("SELECT sql FROM sqlite_master WHERE sql IS NOT NULL ORDER BY rowid")
+                .fetch_all(config.db().await.unwrap())
+                .await
+                .unwrap();
+        assert_eq!(
+            stored
+                .into_iter()
+                .map(|(statement,)| statement)
+                .collect::<Vec<_>>(),
+            statements
+        );
+
+        let (version,): (i64,) = sqlx::query_as("SELECT version FROM candles_schema_version")
+            .fetch_one(config.db().await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(version, i64::from(SCHEMA_VERSION));
+
+        // A newer schema is left untouched.
+        sqlx::query("UPDATE candles_schema_version SET version = version + 1")
+            .execute(config.db().await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            config.init_schema(None, std::slice::from_ref(&coin)).await,
+            Err(Error::SchemaVersion(
+                i64::from(SCHEMA_VERSION) + 1,
+                SCHEMA_VERSION
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn init_schema_rollback() {
+        let (mut config, _db) = temp_db("rollback");
+        let coins = [
+            Coin::new("BTC", "Bitcoin", Currency::USD).unwrap(),
+            Coin::new("ETH", "Ethereum", Currency::USD).unwrap(),
+        ];
+        let db = config.db().await.unwrap();
+
+        // The index of the second coin clashes with an existing table.
+        sqlx::query(&format!(
+            "CREATE TABLE {}_range_idx (id INTEGER)",
+            coins[1].table_name()
+        ))
+        .execute(db)
+        .await
+        .unwrap();
+
+        let result = config.init_schema(None, &coins).await;
+        assert!(
+            matches!(result, Err(Error::SqlCreateIndex(table, _)) if table == coins[1].table_name())
+        );
+
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM sqlite_master WHERE name LIKE 'candles%'")
+                .fetch_one(config.db().await.unwrap())
+                .await
+                .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn table_prefix() {
+        let db = TempDb::new("prefix");
+        let database = Some(db.database());
+        let mut default = DbConfig {
+            database: database.clone(),
+            ..DbConfig::default()
+        };
+        let mut prefixed = DbConfig {
+            database,
+            ..DbConfig::default()
+        }
+        .with_table_prefix("ohlcv")
+        .unwrap();
+        let btc = Coin::new("BTC", "Bitcoin", Currency::USD).unwrap();
+        let eth = Coin::new("ETH", "Ethereum", Currency::USD)
+            .and_then(|coin| coin.with_table_prefix("ohlcv"))
+            .unwrap();
+
+        assert!(matches!(
+            DbConfig::default().with_table_prefix("ohlcv_"),
+            Err(Error::InvalidTablePrefix(_))
+        ));
+        assert_eq!(default.table_prefix(), "candles");
+        assert_eq!(prefixed.table_prefix(), eth.table_prefix());
+
+        default
+            .init_schema(None, std::slice::from_ref(&btc))
+            .await
+            .unwrap();
+        prefixed
+            .init_schema(None, std::slice::from_ref(&eth))
+            .await
+            .unwrap();
+        assert_eq!(
+            default.list_coins().await.unwrap(),
+            std::slice::from_ref(&btc)
+        );
+        assert_eq!(
+            prefixed.list_coins().await.unwrap(),
+            std::slice::from_ref(&eth)
+        );
+
+        // Dropping all tables of a prefix keeps the tables of the others.
+        prefixed.drop_schema(None, None).await.unwrap();
+        assert_eq!(prefixed.list_coins().await.unwrap(), []);
+        assert_eq!(default.list_coins().await.unwrap(), [btc]);
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = 'candles_schema_version'",
+        )
+        .fetch_one(default.db().await.unwrap())
+        .await
+        .unwrap();
+        assert_eq!(count, 1);
     }
 }
@@ -1,7 +1,11 @@
+#[cfg(feature = "database")]
+use std::path::PathBuf;
 use std::{error::Error as StdError, fmt};
 
 use time::OffsetDateTime;
 
+#[cfg(feature = "fetch")]
+use crate::Exchange;
 use crate::Timeframe;
 
 /// Error type.
@@ -9,36 +13,147 @@ use crate::Timeframe;
 #[allow(clippy::module_name_repetitions)]
 pub enum Error {
     /// SQLx common error.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
     SqlCommon(Box<sqlx::Error>),
     /// Failed to connect to the database.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
     SqlConnect(String, Box<sqlx::Error>),
     /// Failed to create table.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
     SqlCreateTable(String, Box<sqlx::Error>),
+    /// Failed to create index on table.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
+    SqlCreateIndex(String, Box<sqlx::Error>),
     /// Failed to drop table.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
     SqlDropTable(String, Box<sqlx::Error>),
     /// Failed to drop type.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
     SqlDropType(String, Box<sqlx::Error>),
     // Failed to select rows.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
     SqlSelect(Box<sqlx::Error>),
+    /// Failed to begin or commit a transaction.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
+    SqlTransaction(Box<sqlx::Error>),
+    /// Failed to insert rows into table.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
+    SqlInsert(String, Box<sqlx::Error>),
+    /// Failed to delete rows from table.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
+    SqlDelete(String, Box<sqlx::Error>),
+    /// Table of a coin does not exist.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
+    TableMissing(String),
+    /// Schema of the database is newer than the supported version.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
+    SchemaVersion(i64, u32),
+    /// Symbol of a coin contains invalid characters.
+    InvalidSymbol(String),
+    /// Prefix of the table names contains invalid characters.
+    InvalidTablePrefix(String),
+    /// Candle violates an invariant.
+    InvalidCandle(OffsetDateTime, String),
+    /// Line of a candle could not be parsed.
+    InvalidLine(String),
     /// Iterator of candles to merge is empty.
     MergeEmpty,
     /// Timeframes of candles to merge are not equal.
     MergeTimeframe(usize, Timeframe, Timeframe),
     /// Timestamps of candles to merge are not equal.
     MergeTimestamp(usize, OffsetDateTime, OffsetDateTime),
+    /// Database configuration is invalid.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
+    DbConfig(String),
     /// Password is missing for the user.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
     MissingPassword(String),
+    /// Failed to read the password file.
+    #[cfg(feature = "database")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "database")))]
+    PasswordFile(PathBuf, std::io::Error),
+    /// Series of candles is empty.
+    SeriesEmpty,
+    /// Timestamp in a series of candles is not after the previous one.
+    SeriesOrder(OffsetDateTime),
+    /// Gap starting at the timestamp exceeds the maximum number of candles.
+    SeriesGap(OffsetDateTime, usize),
+    /// Gap starting at the timestamp is too close to the previous gap.
+    SeriesGapDistance(OffsetDateTime, usize),
+    /// Number of missing candles exceeds the limit for the expected candles.
+    SeriesMissing(usize, usize),
+    /// Timeframe cannot be resampled into the other timeframe.
+    ResampleTimeframe(Timeframe, Timeframe),
+    /// Candle at the timestamp has another timeframe than the resampled one.
+    ResampleCandle(OffsetDateTime, Timeframe, Timeframe),
+    /// HTTP request to an exchange failed.
+    #[cfg(feature = "fetch")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+    Http(Box<reqwest::Error>),
+    /// Exchange returned an invalid response or an error message.
+    #[cfg(feature = "fetch")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+    ExchangeResponse(Exchange, String),
+    /// All attempts to download from an exchange failed.
+    #[cfg(feature = "fetch")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+    FetchExhausted(Exchange, String),
+}
+
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+impl Error {
+    /// Checks if the error is a transient failure of a download.
+    ///
+    /// Transient failures are connection errors, timeouts, server errors (HTTP
+    /// 5xx) and rate limit errors (HTTP 429).
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Http(err) => {
+                err.is_connect()
+                    || err.is_timeout()
+                    || err.status().is_some_and(|status| {
+                        status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    })
+            }
+            _ => false,
+        }
+    }
 }
 
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
+            #[cfg(feature = "database")]
             Self::SqlCommon(err)
             | Self::SqlConnect(_, err)
             | Self::SqlCreateTable(_, err)
+            | Self::SqlCreateIndex(_, err)
             | Self::SqlDropTable(_, err)
             | Self::SqlDropType(_, err)
-            | Self::SqlSelect(err) => Some(err.as_ref()),
+            | Self::SqlSelect(err)
+            | Self::SqlTransaction(err)
+            | Self::SqlInsert(_, err)
+            | Self::SqlDelete(_, err) => Some(err.as_ref()),
+            #[cfg(feature = "database")]
+            Self::PasswordFile(_, err) => Some(err),
+            #[cfg(feature = "fetch")]
+            Self::Http(err) => Some(err.as_ref()),
             _ => None,
         }
     }
@@ -47,16 +162,25 @@ impl StdError for Error {
 impl PartialEq for Error {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
+            #[cfg(feature = "database")]
             (Self::SqlConnect(a, err_a), Self::SqlConnect(b, err_b))
             | (Self::SqlCreateTable(a, err_a), Self::SqlCreateTable(b, err_b))
+            | (Self::SqlCreateIndex(a, err_a), Self::SqlCreateIndex(b, err_b))
             | (Self::SqlDropTable(a, err_a), Self::SqlDropTable(b, err_b))
-            | (Self::SqlDropType(a, err_a), Self::SqlDropType(b, err_b)) => {
+            | (Self::SqlDropType(a, err_a), Self::SqlDropType(b, err_b))
+            | (Self::SqlInsert(a, err_a), Self::SqlInsert(b, err_b))
+            | (Self::SqlDelete(a, err_a), Self::SqlDelete(b, err_b)) => {
                 a == b && err_a.to_string() == err_b.to_string()
             }
+            #[cfg(feature = "database")]
             (Self::SqlCommon(err_a), Self::SqlCommon(err_b))
-            | (Self::SqlSelect(err_a), Self::SqlSelect(err_b)) => {
+            | (Self::SqlSelect(err_a), Self::SqlSelect(err_b))
+            | (Self::SqlTransaction(err_a), Self::SqlTransaction(err_b)) => {
                 err_a.to_string() == err_b.to_string()
             }
+            #[cfg(feature = "database")]
+            (Self::SchemaVersion(a, s_a), Self::SchemaVersion(b, s_b)) => a == b && s_a == s_b,
+            (Self::InvalidCandle(t_a, a), Self::InvalidCandle(t_b, b)) => t_a == t_b && a == b,
             (Self::MergeEmpty, Self::MergeEmpty) => true,
             (Self::MergeTimeframe(a, t1_a, t2_a), Self::MergeTimeframe(b, t1_b, t2_b)) => {
                 a == b && t1_a == t1_b && t2_a == t2_b
@@ -64,31 +188,112 @@ impl PartialEq for Error {
             (Self::MergeTimestamp(a, t1_a, t2_a), Self::MergeTimestamp(b, t1_b, t2_b)) => {
                 a == b && t1_a == t1_b && t2_a == t2_b
             }
-            (Self::MissingPassword(a), Self::MissingPassword(b)) => a == b,
+            (Self::InvalidSymbol(a), Self::InvalidSymbol(b))
+            | (Self::InvalidTablePrefix(a), Self::InvalidTablePrefix(b))
+            | (Self::InvalidLine(a), Self::InvalidLine(b)) => a == b,
+            #[cfg(feature = "database")]
+            (Self::DbConfig(a), Self::DbConfig(b))
+            | (Self::MissingPassword(a), Self::MissingPassword(b))
+            | (Self::TableMissing(a), Self::TableMissing(b)) => a == b,
+            #[cfg(feature = "database")]
+            (Self::PasswordFile(a, err_a), Self::PasswordFile(b, err_b)) => {
+                a == b && err_a.kind() == err_b.kind()
+            }
+            (Self::SeriesEmpty, Self::SeriesEmpty) => true,
+            (Self::SeriesOrder(a), Self::SeriesOrder(b)) => a == b,
+            (Self::SeriesGap(t_a, a), Self::SeriesGap(t_b, b))
+            | (Self::SeriesGapDistance(t_a, a), Self::SeriesGapDistance(t_b, b)) => {
+                t_a == t_b && a == b
+            }
+            (Self::SeriesMissing(a, e_a), Self::SeriesMissing(b, e_b)) => a == b && e_a == e_b,
+            (Self::ResampleTimeframe(f_a, t_a), Self::ResampleTimeframe(f_b, t_b)) => {
+                f_a == f_b && t_a == t_b
+            }
+            (Self::ResampleCandle(t_a, a, e_a), Self::ResampleCandle(t_b, b, e_b)) => {
+                t_a == t_b && a == b && e_a == e_b
+            }
+            #[cfg(feature = "fetch")]
+            (Self::Http(err_a), Self::Http(err_b)) => err_a.to_string() == err_b.to_string(),
+            #[cfg(feature = "fetch")]
+            (Self::ExchangeResponse(a, msg_a), Self::ExchangeResponse(b, msg_b))
+            | (Self::FetchExhausted(a, msg_a), Self::FetchExhausted(b, msg_b)) => {
+                a == b && msg_a == msg_b
+            }
             _ => false,
         }
     }
 }
 
 impl fmt::Display for Error {
+    #[allow(clippy::too_many_lines)]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            #[cfg(feature = "database")]
             Self::SqlCommon(err) => err.fmt(f),
+            #[cfg(feature = "database")]
             Self::SqlConnect(user, err) => {
                 write!(f, "failed to connect user `{user}` to the database: {err}")
             }
+            #[cfg(feature = "database")]
             Self::SqlCreateTable(table, err) => {
                 write!(f, "failed to create table `{table}`: {err}")
             }
+            #[cfg(feature = "database")]
+            Self::SqlCreateIndex(table, err) => {
+                write!(f, "failed to create index on table `{table}`: {err}")
+            }
+            #[cfg(feature = "database")]
             Self::SqlDropTable(table, err) => {
                 write!(f, "failed to drop table `{table}`: {err}")
             }
+            #[cfg(feature = "database")]
             Self::SqlDropType(typename, err) => {
                 write!(f, "failed to drop type `{typename}`: {err}")
             }
+            #[cfg(feature = "database")]
             Self::SqlSelect(err) => {
                 write!(f, "failed to select rows: {err}")
             }
+            #[cfg(feature = "database")]
+            Self::SqlTransaction(err) => {
+                write!(f, "failed to begin or commit transaction: {err}")
+            }
+            #[cfg(feature = "database")]
+            Self::SqlInsert(table, err) => {
+                write!(f, "failed to insert rows into table `{table}`: {err}")
+            }
+            #[cfg(feature = "database")]
+            Self::SqlDelete(table, err) => {
+                write!(f, "failed to delete rows from table `{table}`: {err}")
+            }
+            #[cfg(feature = "database")]
+            Self::TableMissing(table) => write!(f, "table `{table}` does not exist"),
+            #[cfg(feature = "database")]
+            Self::SchemaVersion(version, supported) => {
+                write!(
+                    f,
+                    "database schema version {version} is newer than the supported version {supported}"
+                )
+            }
+            Self::InvalidSymbol(symbol) => {
+                write!(
+                    f,
+                    "invalid coin symbol `{symbol}`: only ASCII letters and digits are allowed"
+                )
+            }
+            Self::InvalidTablePrefix(prefix) => {
+                write!(
+                    f,
+                    "invalid table prefix `{prefix}`: only a lowercase ASCII letter followed by \
+                    lowercase ASCII letters and digits is allowed"
+                )
+            }
+            Self::InvalidCandle(timestamp, reason) => {
+                write!(f, "invalid candle at {timestamp}: {reason}")
+            }
+            Self::InvalidLine(reason) => {
+                write!(f, "invalid candle line: {reason}")
+            }
             Self::MergeEmpty => {
                 write!(f, "failed to merge candles: iterator is empty")
             }
@@ -104,9 +309,81 @@ impl fmt::Display for Error {
                     "timestamps of candles at index {index} do not match: {a} and {b}"
                 )
             }
+            #[cfg(feature = "database")]
+            Self::DbConfig(msg) => {
+                write!(f, "invalid database configuration: {msg}")
+            }
+            #[cfg(feature = "database")]
             Self::MissingPassword(username) => {
                 write!(f, "missing password for user: {username}")
             }
+            #[cfg(feature = "database")]
+            Self::PasswordFile(path, err) => {
+                write!(f, "failed to read password file {}: {err}", path.display())
+            }
+            Self::SeriesEmpty => {
+                write!(f, "series of candles is empty")
+            }
+            Self::SeriesOrder(timestamp) => {
+                write!(f, "candle at {timestamp} is not after the previous candle")
+            }
+            Self::SeriesGap(timestamp, count) => {
+                write!(f, "gap of {count} candles starting at {timestamp}")
+            }
+            Self::SeriesGapDistance(timestamp, distance) => {
+                write!(
+                    f,
+                    "gap starting at {timestamp} is only {distance} candles after the previous gap"
+                )
+            }
+            Self::SeriesMissing(missing, expected) => {
+                write!(f, "{missing} of {expected} candles are missing")
+            }
+            Self::ResampleTimeframe(from, to) => {
+                write!(f, "cannot resample candles of timeframe {from} into {to}")
+            }
+            Self::ResampleCandle(timestamp, timeframe, expected) => {
+                write!(
+                    f,
+                    "candle at {timestamp} has timeframe {timeframe} instead of {expected}"
+                )
+            }
+            #[cfg(feature = "fetch")]
+            Self::Http(err) => {
+                write!(f, "HTTP request failed: {err}")
+            }
+            #[cfg(feature = "fetch")]
+            Self::ExchangeResponse(exchange, msg) => {
+                write!(f, "invalid response from {exchange}: {msg}")
+            }
+            #[cfg(feature = "fetch")]
+            Self::FetchExhausted(exchange, msg) => {
+                write!(f, "all attempts to fetch from {exchange} failed: {msg}")
+            }
         }
     }
 }
+
+#[cfg(all(test, feature = "fetch"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fetch_errors() {
+        let response = http::Response::builder().status(502).body("").unwrap();
+        let err = reqwest::Response::from(response)
+            .error_for_status()
+            .unwrap_err();
+        let http = Error::Http(Box::new(err));
+        let response = Error::ExchangeResponse(Exchange::Kraken, "EGeneral:Invalid".into());
+
+        assert!(http.source().is_some());
+        assert!(http.to_string().starts_with("HTTP request failed: "));
+        assert!(response.source().is_none());
+        assert_eq!(
+            response.to_string(),
+            "invalid response from Kraken: EGeneral:Invalid"
+        );
+        assert_ne!(http, response);
+    }
+}
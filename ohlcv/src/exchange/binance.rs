@@ -0,0 +1,280 @@
+//! Binance exchange implementation.
+//!
+//! The candles are downloaded from the `/api/v3/klines` endpoint of the Binance
+//! REST API. The symbol is the concatenation of the base and quote asset, e.g.
+//! `BTCUSDC`.
+
+use rust_decimal::Decimal;
+use serde::{de::IgnoredAny, Deserialize};
+use time::{Date, OffsetDateTime};
+use tracing::{debug, instrument};
+
+use crate::{Candle, Error, Timeframe};
+
+use super::{client::get_json, Exchange, HttpClient};
+
+/// The base URL of the Binance REST API.
+pub const BASE_URL: &str = "https://api.binance.com";
+/// The maximum number of candles returned by a single request.
+pub const MAX_CANDLES: usize = 1000;
+
+/// A candle as returned by the API.
+///
+/// The fields are `[open time, open, high, low, close, volume, close time,
+/// quote volume, trades, taker base volume, taker quote volume, ignore]`,
+/// where the times are given in milliseconds since the Unix epoch.
+#[derive(Debug, Deserialize)]
+struct Kline(
+    i64,
+    Decimal,
+    Decimal,
+    Decimal,
+    Decimal,
+    Decimal,
+    i64,
+    Decimal,
+    IgnoredAny,
+    IgnoredAny,
+    IgnoredAny,
+    IgnoredAny,
+);
+
+impl Kline {
+    fn into_candle(self) -> Result<Candle, Error> {
+        let Self(time, open, high, low, close, _, _, volume, ..) = self;
+        let timestamp = OffsetDateTime::from_unix_timestamp_nanos(i128::from(time) * 1_000_000)
+            .map_err(|err| Error::ExchangeResponse(Exchange::Binance, err.to_string()))?;
+
+        Candle::new(
+            timestamp,
+            Timeframe::FiveMinutes,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        )
+    }
+}
+
+/// Fetch the 5-minute candles of the given day for the symbol `symbol`.
+///
+/// The candles are returned in ascending order of their timestamps. The
+/// volume of the candles is the quote asset volume.
+///
+/// # Errors
+///
+/// Returns an error if the request fails or the response cannot be parsed.
+#[instrument(skip(client))]
+pub async fn fetch_candles(
+    client: &impl HttpClient,
+    symbol: &str,
+    date: Date,
+) -> Result<Vec<Candle>, Error> {
+    let start = date.midnight().assume_utc();
+    let end = start + Timeframe::OneDay.duration();
+
+    fetch_range(client, symbol, start, end).await
+}
+
+/// Fetch the 5-minute candles from `start` up to `end` for the symbol
+/// `symbol`.
+///
+/// A single request returns at most [`MAX_CANDLES`] candles, so ranges of more
+/// than about three and a half days are downloaded page by page. Every page
+/// starts one millisecond after the open time of the last candle of the
+/// previous page. The rate limit of the exchange is not respected between the
+/// pages.
+///
+/// The candles are returned in ascending order of their timestamps without
+/// duplicates. The volume of the candles is the quote asset volume.
+///
+/// # Errors
+///
+/// Returns an error if a request fails or a response cannot be parsed.
+#[instrument(skip(client))]
+pub async fn fetch_range(
+    client: &impl HttpClient,
+    symbol: &str,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+) -> Result<Vec<Candle>, Error> {
+    fetch_pages(client, BASE_URL, symbol, start, end).await
+}
+
+/// Fetch the candles of a range page by page from the API at `base_url`.
+async fn fetch_pages(
+    client: &impl HttpClient,
+    base_url: &str,
+    symbol: &str,
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+) -> Result<Vec<Candle>, Error> {
+    let url = format!("{base_url}/api/v3/klines");
+    let mut candles = Vec::new();
+    let mut next = millis(start);
+
+    while next < millis(end) {
+        let klines = get_json::<Vec<Kline>>(
+            client,
+            Exchange::Binance,
+            &url,
+            &[
+                ("symbol", symbol.to_string()),
+                ("interval", Timeframe::FiveMinutes.to_string()),
+                ("startTime", next.to_string()),
+                // The `endTime` parameter is inclusive.
+                ("endTime", (millis(end) - 1).to_string()),
+                ("limit", MAX_CANDLES.to_string()),
+            ],
+        )
+        .await?;
+
+        debug!("Received {} candles", klines.len());
+        let received = klines.len();
+        let last = klines.iter().map(|kline| kline.0).max();
+        candles.extend(
+            klines
+                .into_iter()
+                .map(Kline::into_candle)
+                .collect::<Result<Vec<_>, _>>()?,
+        );
+        // Stop if the page is not full or the cursor does not advance.
+        match last {
+            Some(last) if received == MAX_CANDLES && last >= next => next = last + 1,
+            _ => break,
+        }
+    }
+    candles.retain(|candle| candle.timestamp >= start && candle.timestamp < end);
+    candles.sort_unstable_by_key(|candle| candle.timestamp);
+    candles.dedup_by_key(|candle| candle.timestamp);
+
+    Ok(candles)
+}
+
+/// Milliseconds since the Unix epoch.
+#[allow(clippy::cast_possible_truncation)]
+const fn millis(time: OffsetDateTime) -> i64 {
+    (time.unix_timestamp_nanos() / 1_000_000) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+    use time::{
+        macros::{date, datetime},
+        Duration,
+    };
+    use wiremock::{
+        matchers::{method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::exchange::client::mock::MockClient;
+
+    use super::*;
+
+    #[test]
+    fn kline_into_candle() {
+        let klines: Vec<Kline> = serde_json::from_str(
+            r#"[[
+                1700000100000, "10.0", "12.0", "9.5", "11.5", "2.0",
+                1700000399999, "22.0", 5, "1.0", "11.0", "0"
+            ]]"#,
+        )
+        .unwrap();
+        let candle = klines.into_iter().next().unwrap().into_candle().unwrap();
+
+        assert_eq!(candle.timestamp.unix_timestamp(), 1_700_000_100);
+        assert_eq!(candle.open, dec!(10));
+        assert_eq!(candle.high, dec!(12));
+        assert_eq!(candle.low, dec!(9.5));
+        assert_eq!(candle.close, dec!(11.5));
+        assert_eq!(candle.volume, dec!(22));
+    }
+
+    #[tokio::test]
+    async fn fetch_pages() {
+        let start = datetime!(2024-01-01 00:00 UTC);
+        let end = start + Duration::days(5);
+        let step = 5 * 60 * 1000;
+        let kline = |time: i64| {
+            json!([
+                time,
+                "10.0",
+                "12.0",
+                "9.5",
+                "11.5",
+                "2.0",
+                time + step - 1,
+                "22.0",
+                5,
+                "1.0",
+                "11.0",
+                "0"
+            ])
+        };
+        let open = |index: i64| millis(start) + index * step;
+        let server = MockServer::start().await;
+        // The second page starts after the last candle of the first page, but
+        // returns the boundary candle again.
+        let pages = [(open(0), 0..1000), (open(999) + 1, 999..1440)];
+
+        for (start_time, indices) in pages {
+            let klines = indices.map(|index| kline(open(index))).collect::<Vec<_>>();
+
+            Mock::given(method("GET"))
+                .and(path("/api/v3/klines"))
+                .and(query_param("startTime", start_time.to_string()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(klines))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let candles = super::fetch_pages(
+            &reqwest::Client::new(),
+            &server.uri(),
+            "BTCUSDC",
+            start,
+            end,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(candles.len(), 5 * 288);
+        assert_eq!(candles[0].timestamp, start);
+        assert_eq!(candles[1439].timestamp, end - Duration::minutes(5));
+        assert!(candles
+            .windows(2)
+            .all(|pair| pair[1].timestamp - pair[0].timestamp == Duration::minutes(5)));
+    }
+
+    #[tokio::test]
+    async fn fetch_candles() {
+        let client = MockClient::new([r#"[
+            [1704067200000, "10", "12", "9", "11", "1", 1704067499999, "10", 5, "1", "10", "0"],
+            [1704067500000, "10", "12", "9", "11", "1", 1704067799999, "10", 5, "1", "10", "0"]
+        ]"#]);
+        let candles = super::fetch_candles(&client, "BTCUSDC", date!(2024 - 01 - 01))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            candles
+                .iter()
+                .map(|candle| candle.timestamp)
+                .collect::<Vec<_>>(),
+            [
+                datetime!(2024-01-01 00:00 UTC),
+                datetime!(2024-01-01 00:05 UTC)
+            ]
+        );
+        assert_eq!(
+            client.requests(),
+            ["https://api.binance.com/api/v3/klines?\
+            symbol=BTCUSDC&interval=5m&startTime=1704067200000&endTime=1704153599999&limit=1000"]
+        );
+    }
+}
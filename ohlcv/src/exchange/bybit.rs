@@ -0,0 +1,218 @@
+//! Bybit exchange implementation.
+//!
+//! The candles are downloaded from the `/v5/market/kline` endpoint of the
+//! Bybit REST API. The symbol is the concatenation of the base and quote coin,
+//! e.g. `BTCUSDT`.
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use time::{Date, OffsetDateTime};
+use tracing::{debug, instrument};
+
+use crate::{Candle, Error, Timeframe};
+
+use super::{client::get_json, Exchange, HttpClient};
+
+/// The base URL of the Bybit REST API.
+pub const BASE_URL: &str = "https://api.bybit.com";
+/// The maximum number of candles returned by a single request.
+pub const MAX_CANDLES: usize = 1000;
+
+/// The return code of a successful response.
+const CODE_SUCCESS: i64 = 0;
+
+/// The response of the API.
+///
+/// If the return code is not [`CODE_SUCCESS`], the request failed and the
+/// message describes the error.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    ret_code: i64,
+    ret_msg: String,
+    result: Option<KlineResult>,
+}
+
+/// The result of a response.
+///
+/// Failed requests return an empty result object without a list.
+#[derive(Debug, Deserialize)]
+struct KlineResult {
+    #[serde(default)]
+    list: Vec<Kline>,
+}
+
+/// A candle as returned by the API.
+///
+/// The fields are `[start time, open, high, low, close, volume, turnover]`,
+/// where the start time is given in milliseconds since the Unix epoch and
+/// `turnover` is the volume in the quote coin. All fields are strings.
+#[derive(Debug, Deserialize)]
+struct Kline(String, Decimal, Decimal, Decimal, Decimal, Decimal, Decimal);
+
+impl Kline {
+    fn into_candle(self) -> Result<Candle, Error> {
+        let Self(time, open, high, low, close, _, turnover) = self;
+        let timestamp = time
+            .parse::<i64>()
+            .map_err(|err| err.to_string())
+            .and_then(|time| {
+                OffsetDateTime::from_unix_timestamp_nanos(i128::from(time) * 1_000_000)
+                    .map_err(|err| err.to_string())
+            })
+            .map_err(|err| Error::ExchangeResponse(Exchange::Bybit, err))?;
+
+        Candle::new(
+            timestamp,
+            Timeframe::FiveMinutes,
+            open,
+            high,
+            low,
+            close,
+            turnover,
+        )
+    }
+}
+
+/// Fetch the 5-minute candles of the given day for the symbol `symbol`.
+///
+/// The candles of the spot market are requested. They are returned in
+/// ascending order of their timestamps. The volume of the candles is the
+/// turnover in the quote coin.
+///
+/// # Errors
+///
+/// Returns an error if the request fails, the response cannot be parsed or
+/// the API reports an error.
+#[instrument(skip(client))]
+pub async fn fetch_candles(
+    client: &impl HttpClient,
+    symbol: &str,
+    date: Date,
+) -> Result<Vec<Candle>, Error> {
+    let start = date.midnight().assume_utc();
+    let end = start + Timeframe::OneDay.duration();
+
+    let url = format!("{BASE_URL}/v5/market/kline");
+    let response = get_json::<Response>(
+        client,
+        Exchange::Bybit,
+        &url,
+        &[
+            ("category", "spot".to_string()),
+            ("symbol", symbol.to_string()),
+            ("interval", "5".to_string()),
+            ("start", (start.unix_timestamp() * 1000).to_string()),
+            ("end", (end.unix_timestamp() * 1000 - 1).to_string()),
+            ("limit", MAX_CANDLES.to_string()),
+        ],
+    )
+    .await?;
+
+    if response.ret_code != CODE_SUCCESS {
+        return Err(Error::ExchangeResponse(Exchange::Bybit, response.ret_msg));
+    }
+
+    let klines = response
+        .result
+        .map(|result| result.list)
+        .unwrap_or_default();
+    debug!("Received {} candles", klines.len());
+    let mut candles = klines
+        .into_iter()
+        .map(Kline::into_candle)
+        .collect::<Result<Vec<_>, _>>()?;
+    candles.retain(|candle| candle.timestamp >= start && candle.timestamp < end);
+    candles.sort_unstable_by_key(|candle| candle.timestamp);
+
+    Ok(candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::{date, datetime};
+
+    use crate::exchange::client::mock::MockClient;
+
+    use super::*;
+
+    #[test]
+    fn parse_response() {
+        let response: Response = serde_json::from_str(
+            r#"{
+                "retCode": 0,
+                "retMsg": "OK",
+                "result": {
+                    "category": "spot",
+                    "symbol": "BTCUSDT",
+                    "list": [["1700000100000", "10.0", "12.0", "9.5", "11.5", "2.0", "22.0"]]
+                },
+                "time": 1700000200000
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(response.ret_code, CODE_SUCCESS);
+
+        let candle = response.result.unwrap().list.into_iter().next().unwrap();
+        let candle = candle.into_candle().unwrap();
+
+        assert_eq!(candle.timestamp.unix_timestamp(), 1_700_000_100);
+        assert_eq!(candle.open, dec!(10));
+        assert_eq!(candle.high, dec!(12));
+        assert_eq!(candle.low, dec!(9.5));
+        assert_eq!(candle.close, dec!(11.5));
+        assert_eq!(candle.volume, dec!(22));
+    }
+
+    #[tokio::test]
+    async fn fetch_candles() {
+        let client = MockClient::new([r#"{
+            "retCode": 0,
+            "retMsg": "OK",
+            "result": {
+                "list": [
+                    ["1704067500000", "10", "12", "9", "11", "1", "10"],
+                    ["1704067200000", "10", "12", "9", "11", "1", "10"]
+                ]
+            }
+        }"#]);
+        let candles = super::fetch_candles(&client, "BTCUSDT", date!(2024 - 01 - 01))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            candles
+                .iter()
+                .map(|candle| candle.timestamp)
+                .collect::<Vec<_>>(),
+            [
+                datetime!(2024-01-01 00:00 UTC),
+                datetime!(2024-01-01 00:05 UTC)
+            ]
+        );
+        assert_eq!(
+            client.requests(),
+            ["https://api.bybit.com/v5/market/kline?\
+            category=spot&symbol=BTCUSDT&interval=5&start=1704067200000&end=1704153599999&limit=1000"]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_error() {
+        let client = MockClient::new([r#"{
+            "retCode": 10001,
+            "retMsg": "Not supported symbols",
+            "result": {},
+            "time": 1704067200000
+        }"#]);
+
+        assert_eq!(
+            super::fetch_candles(&client, "BTCXYZ", date!(2024 - 01 - 01)).await,
+            Err(Error::ExchangeResponse(
+                Exchange::Bybit,
+                "Not supported symbols".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::future::Future;
+
+use reqwest::{header::USER_AGENT, Client};
+use serde::de::DeserializeOwned;
+
+use crate::Error;
+
+use super::{Exchange, RateLimiter};
+
+/// HTTP client used by the exchange implementations to send requests.
+///
+/// The implementations of the exchanges only depend on this trait, so they can
+/// be tested against captured responses without network access. The trait is
+/// implemented for [`reqwest::Client`], which is used by the [`Fetcher`].
+///
+/// [`Fetcher`]: super::Fetcher
+pub trait HttpClient: Sync {
+    /// Send a GET request to `url` with the query parameters and headers.
+    ///
+    /// Returns the body of the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the status of the response is
+    /// not a success.
+    fn get(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+        headers: &[(&str, &str)],
+    ) -> impl Future<Output = Result<Vec<u8>, Error>> + Send;
+}
+
+impl HttpClient for Client {
+    async fn get(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+        headers: &[(&str, &str)],
+    ) -> Result<Vec<u8>, Error> {
+        let request = headers.iter().fold(
+            Self::get(self, url).query(query),
+            |request, (name, value)| request.header(*name, *value),
+        );
+        let body = request
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|err| Error::Http(Box::new(err)))?
+            .bytes()
+            .await
+            .map_err(|err| Error::Http(Box::new(err)))?;
+
+        Ok(body.to_vec())
+    }
+}
+
+/// HTTP client sending its own user agent with every request.
+///
+/// The user agent overrides the default user agent of the wrapped client.
+#[derive(Debug)]
+pub(super) struct WithUserAgent<'a, C> {
+    client: &'a C,
+    user_agent: &'a str,
+}
+
+impl<'a, C: HttpClient> WithUserAgent<'a, C> {
+    pub(super) const fn new(client: &'a C, user_agent: &'a str) -> Self {
+        Self { client, user_agent }
+    }
+}
+
+impl<C: HttpClient> HttpClient for WithUserAgent<'_, C> {
+    async fn get(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+        headers: &[(&str, &str)],
+    ) -> Result<Vec<u8>, Error> {
+        let headers = headers
+            .iter()
+            .copied()
+            .chain([(USER_AGENT.as_str(), self.user_agent)])
+            .collect::<Vec<_>>();
+
+        self.client.get(url, query, &headers).await
+    }
+}
+
+/// HTTP client waiting for the rate limit of an exchange before every request.
+///
+/// Exchanges paging through a day send several requests, so the limit is
+/// charged per request rather than per download.
+#[derive(Debug)]
+pub(super) struct RateLimited<'a, C> {
+    client: &'a C,
+    limiter: &'a RateLimiter,
+    exchange: Exchange,
+}
+
+impl<'a, C: HttpClient> RateLimited<'a, C> {
+    pub(super) const fn new(client: &'a C, limiter: &'a RateLimiter, exchange: Exchange) -> Self {
+        Self {
+            client,
+            limiter,
+            exchange,
+        }
+    }
+}
+
+impl<C: HttpClient> HttpClient for RateLimited<'_, C> {
+    async fn get(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+        headers: &[(&str, &str)],
+    ) -> Result<Vec<u8>, Error> {
+        self.limiter.acquire(self.exchange).await;
+        self.client.get(url, query, headers).await
+    }
+}
+
+/// Send a GET request and parse the JSON body of the response.
+///
+/// A body that cannot be parsed is reported as invalid response of the
+/// exchange.
+pub(super) async fn get_json<T: DeserializeOwned>(
+    client: &impl HttpClient,
+    exchange: Exchange,
+    url: &str,
+    query: &[(&str, String)],
+) -> Result<T, Error> {
+    let body = client.get(url, query, &[]).await?;
+
+    serde_json::from_slice(&body).map_err(|err| Error::ExchangeResponse(exchange, err.to_string()))
+}
+
+#[cfg(test)]
+pub(super) mod mock {
+    use std::{collections::VecDeque, sync::Mutex};
+
+    use super::*;
+
+    /// HTTP client answering the requests with captured responses in order.
+    ///
+    /// The requests are recorded as URL with the query parameters appended,
+    /// followed by the headers on separate lines.
+    #[derive(Debug, Default)]
+    pub struct MockClient {
+        responses: Mutex<VecDeque<String>>,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl MockClient {
+        pub fn new<S: Into<String>>(responses: impl IntoIterator<Item = S>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into_iter().map(Into::into).collect()),
+                requests: Mutex::default(),
+            }
+        }
+
+        pub fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl HttpClient for MockClient {
+        async fn get(
+            &self,
+            url: &str,
+            query: &[(&str, String)],
+            headers: &[(&str, &str)],
+        ) -> Result<Vec<u8>, Error> {
+            let query = query
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join("&");
+            let request = headers
+                .iter()
+                .fold(format!("{url}?{query}"), |request, (name, value)| {
+                    format!("{request}\n{name}: {value}")
+                });
+
+            self.requests.lock().unwrap().push(request);
+            let response = self.responses.lock().unwrap().pop_front();
+            Ok(response.expect("unexpected request").into_bytes())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZero, time::Duration};
+
+    use super::{super::RateLimit, mock::MockClient, *};
+
+    #[tokio::test]
+    async fn with_user_agent() {
+        let client = MockClient::new(["", ""]);
+
+        client.get("https://example.com", &[], &[]).await.unwrap();
+        WithUserAgent::new(&client, "test/1.0")
+            .get("https://example.com", &[("a", "1".to_string())], &[])
+            .await
+            .unwrap();
+        assert_eq!(
+            client.requests(),
+            [
+                "https://example.com?",
+                "https://example.com?a=1\nuser-agent: test/1.0"
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limited() {
+        let limit = RateLimit::new(NonZero::new(1).unwrap(), NonZero::new(1).unwrap());
+        let limiter = RateLimiter::new().with_limit(Exchange::OKX, limit);
+        let client = MockClient::new(["", "", ""]);
+        let rate_limited = RateLimited::new(&client, &limiter, Exchange::OKX);
+        let start = tokio::time::Instant::now();
+
+        // Every request of a paged download is charged.
+        for _ in 0..3 {
+            rate_limited
+                .get("https://example.com", &[], &[])
+                .await
+                .unwrap();
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        assert_eq!(client.requests().len(), 3);
+    }
+}
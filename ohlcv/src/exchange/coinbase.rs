@@ -0,0 +1,201 @@
+//! Coinbase exchange implementation.
+//!
+//! The candles are downloaded from the `/products/{id}/candles` endpoint of the
+//! Coinbase Exchange API. The product identifier uses the dash form of the
+//! trading pair, e.g. `BTC-USD`.
+
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use time::{format_description::well_known::Rfc3339, Date, OffsetDateTime};
+use tracing::{debug, instrument};
+
+use crate::{Candle, Error, Timeframe};
+
+use super::{client::get_json, Exchange, HttpClient};
+
+/// The base URL of the Coinbase Exchange API.
+pub const BASE_URL: &str = "https://api.exchange.coinbase.com";
+/// The maximum number of candles returned by a single request.
+pub const MAX_CANDLES: usize = 300;
+
+/// The granularity of the candles in seconds.
+const GRANULARITY: Duration = Timeframe::FiveMinutes.duration();
+/// The number of candles of a single day.
+const CANDLES_PER_DAY: u64 = Timeframe::OneDay.duration().as_secs() / GRANULARITY.as_secs();
+
+// A single request must be sufficient to download the candles of a day.
+#[allow(clippy::cast_possible_truncation)]
+const _: () = assert!(CANDLES_PER_DAY as usize <= MAX_CANDLES);
+
+/// The response of the API.
+///
+/// A failed request returns an object with a message describing the error
+/// instead of the list of candles.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Response {
+    Klines(Vec<Kline>),
+    Error { message: String },
+}
+
+/// A candle as returned by the API.
+///
+/// The fields are `[time, low, high, open, close, volume]`, where `time` is the
+/// start of the candle in seconds since the Unix epoch and `volume` is the
+/// volume in the base currency.
+#[derive(Debug, Deserialize)]
+struct Kline(i64, Decimal, Decimal, Decimal, Decimal, Decimal);
+
+impl Kline {
+    fn into_candle(self) -> Result<Candle, Error> {
+        let Self(time, low, high, open, close, volume) = self;
+        let timestamp = OffsetDateTime::from_unix_timestamp(time)
+            .map_err(|err| Error::ExchangeResponse(Exchange::Coinbase, err.to_string()))?;
+        // The volume is given in the base currency. It is converted to the
+        // quote currency using the typical price of the candle.
+        let volume = volume * (high + low + close) / Decimal::from(3);
+
+        Candle::new(
+            timestamp,
+            Timeframe::FiveMinutes,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        )
+    }
+}
+
+/// Fetch the 5-minute candles of the given day for the product `symbol`.
+///
+/// The candles are returned in ascending order of their timestamps. The
+/// volume of the candles is converted from the base currency into the quote
+/// currency using the typical price of the candle.
+///
+/// # Errors
+///
+/// Returns an error if the request fails, the response cannot be parsed or
+/// contains more candles than allowed, or the API reports an error.
+#[instrument(skip(client))]
+pub async fn fetch_candles(
+    client: &impl HttpClient,
+    symbol: &str,
+    date: Date,
+) -> Result<Vec<Candle>, Error> {
+    let start = date.midnight().assume_utc();
+    let end = start + Timeframe::OneDay.duration();
+    let url = format!("{BASE_URL}/products/{symbol}/candles");
+    let format = |time: OffsetDateTime| {
+        time.format(&Rfc3339)
+            .map_err(|err| Error::ExchangeResponse(Exchange::Coinbase, err.to_string()))
+    };
+    let response = get_json::<Response>(
+        client,
+        Exchange::Coinbase,
+        &url,
+        &[
+            ("granularity", GRANULARITY.as_secs().to_string()),
+            ("start", format(start)?),
+            ("end", format(end)?),
+        ],
+    )
+    .await?;
+    let klines = match response {
+        Response::Klines(klines) => klines,
+        Response::Error { message } => {
+            return Err(Error::ExchangeResponse(Exchange::Coinbase, message));
+        }
+    };
+
+    debug!("Received {} candles", klines.len());
+    if klines.len() > MAX_CANDLES {
+        return Err(Error::ExchangeResponse(
+            Exchange::Coinbase,
+            format!(
+                "received {} candles, the limit is {MAX_CANDLES}",
+                klines.len()
+            ),
+        ));
+    }
+
+    let mut candles = klines
+        .into_iter()
+        .map(Kline::into_candle)
+        .collect::<Result<Vec<_>, _>>()?;
+    candles.retain(|candle| candle.timestamp >= start && candle.timestamp < end);
+    candles.sort_unstable_by_key(|candle| candle.timestamp);
+
+    Ok(candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::{date, datetime};
+
+    use crate::exchange::client::mock::MockClient;
+
+    use super::*;
+
+    #[test]
+    fn kline_into_candle() {
+        let response: Response =
+            serde_json::from_str("[[1700000100, 9.5, 12, 10, 11.5, 2]]").unwrap();
+        let Response::Klines(klines) = response else {
+            panic!("unexpected response: {response:?}");
+        };
+        let candle = klines.into_iter().next().unwrap().into_candle().unwrap();
+
+        assert_eq!(candle.timestamp.unix_timestamp(), 1_700_000_100);
+        assert_eq!(candle.timeframe, Timeframe::FiveMinutes);
+        assert_eq!(candle.open, dec!(10));
+        assert_eq!(candle.high, dec!(12));
+        assert_eq!(candle.low, dec!(9.5));
+        assert_eq!(candle.close, dec!(11.5));
+        assert_eq!(candle.volume, dec!(22));
+    }
+
+    #[tokio::test]
+    async fn fetch_candles() {
+        let client = MockClient::new([
+            "[[1704153600, 9, 12, 10, 11, 1], [1704067500, 9, 12, 10, 11, 1], [1704067200, 9, 12, 10, 11, 1]]",
+        ]);
+        let candles = super::fetch_candles(&client, "BTC-USD", date!(2024 - 01 - 01))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            candles
+                .iter()
+                .map(|candle| candle.timestamp)
+                .collect::<Vec<_>>(),
+            [
+                datetime!(2024-01-01 00:00 UTC),
+                datetime!(2024-01-01 00:05 UTC)
+            ]
+        );
+        assert_eq!(
+            client.requests(),
+            [
+                "https://api.exchange.coinbase.com/products/BTC-USD/candles?\
+            granularity=300&start=2024-01-01T00:00:00Z&end=2024-01-02T00:00:00Z"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_error() {
+        let client = MockClient::new([r#"{"message": "NotFound"}"#]);
+
+        assert_eq!(
+            super::fetch_candles(&client, "BTC-XYZ", date!(2024 - 01 - 01)).await,
+            Err(Error::ExchangeResponse(
+                Exchange::Coinbase,
+                "NotFound".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::{collections::HashMap, time::Duration};
+
+use reqwest::Client;
+use time::Date;
+use tracing::instrument;
+
+use crate::{Candle, Error};
+
+use super::{
+    client::{RateLimited, WithUserAgent},
+    Exchange, RateLimit, RateLimiter, Retry,
+};
+
+/// Entry point to download candles from the exchanges.
+///
+/// The fetcher shares a single HTTP client and a [`RateLimiter`] between all
+/// downloads. It can be shared between concurrent tasks, so many coins can be
+/// downloaded in parallel without exceeding the rate limits of the exchanges.
+/// Transient failures are retried according to the [`Retry`] policy. A request
+/// taking longer than the request timeout fails as transient, so a hanging
+/// exchange is retried as well.
+#[derive(Debug)]
+pub struct Fetcher {
+    client: Client,
+    user_agent: Box<str>,
+    user_agents: HashMap<Exchange, Box<str>>,
+    limiter: RateLimiter,
+    retry: Retry,
+}
+
+impl Fetcher {
+    /// The default timeout of a request.
+    pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Create a new fetcher sending the given user agent with every request.
+    ///
+    /// The default rate limits of the exchanges, the default retry policy and
+    /// the [default request timeout](Self::DEFAULT_REQUEST_TIMEOUT) are used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created.
+    pub fn new(user_agent: &str) -> Result<Self, Error> {
+        Ok(Self {
+            client: client(user_agent, Self::DEFAULT_REQUEST_TIMEOUT)?,
+            user_agent: user_agent.into(),
+            user_agents: HashMap::new(),
+            limiter: RateLimiter::new(),
+            retry: Retry::default(),
+        })
+    }
+
+    /// Set the user agent sent with the requests to the exchange.
+    ///
+    /// The user agent overrides the user agent of the fetcher for this
+    /// exchange only.
+    #[must_use]
+    pub fn with_user_agent(mut self, exchange: Exchange, user_agent: &str) -> Self {
+        self.user_agents.insert(exchange, user_agent.into());
+        self
+    }
+
+    /// Set the timeout of every request to the exchanges.
+    ///
+    /// The timeout covers the whole request from connecting until the body of
+    /// the response is read. A timed out request fails with [`Error::Http`],
+    /// which is retried according to the [`Retry`] policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Result<Self, Error> {
+        self.client = client(&self.user_agent, timeout)?;
+        Ok(self)
+    }
+
+    /// Set the rate limit for the exchange.
+    #[must_use]
+    pub fn with_rate_limit(mut self, exchange: Exchange, limit: RateLimit) -> Self {
+        self.limiter = self.limiter.with_limit(exchange, limit);
+        self
+    }
+
+    /// Set the retry policy for failed downloads.
+    #[must_use]
+    pub const fn with_retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Get the rate limiter of the fetcher.
+    #[must_use]
+    #[inline]
+    pub const fn rate_limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    /// Fetch the 5-minute candles of the given day from the exchange.
+    ///
+    /// Waits for the rate limit of the exchange before every request, also
+    /// for the pages of a day, and retries transient failures. The user agent set for the exchange is
+    /// used, if any. See [`Exchange::fetch_candles()`] for
+    /// details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candles cannot be downloaded or the response of
+    /// the exchange is invalid. If all attempts fail with a transient error,
+    /// [`Error::FetchExhausted`] is returned.
+    #[instrument(skip(self))]
+    pub async fn fetch_candles(
+        &self,
+        exchange: Exchange,
+        symbol: &str,
+        date: Date,
+    ) -> Result<Vec<Candle>, Error> {
+        self.retry
+            .run(exchange, || async {
+                let client = RateLimited::new(&self.client, &self.limiter, exchange);
+
+                match self.user_agents.get(&exchange) {
+                    Some(user_agent) => {
+                        let client = WithUserAgent::new(&client, user_agent);
+                        exchange.fetch_candles(&client, symbol, date).await
+                    }
+                    None => exchange.fetch_candles(&client, symbol, date).await,
+                }
+            })
+            .await
+    }
+}
+
+/// Create the HTTP client sending the user agent and timing out requests.
+fn client(user_agent: &str, timeout: Duration) -> Result<Client, Error> {
+    Client::builder()
+        .user_agent(user_agent)
+        .timeout(timeout)
+        .build()
+        .map_err(|err| Error::Http(Box::new(err)))
+}
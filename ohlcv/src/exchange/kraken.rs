@@ -0,0 +1,231 @@
+//! Kraken exchange implementation.
+//!
+//! The candles are downloaded from the `/0/public/OHLC` endpoint of the Kraken
+//! REST API. Kraken uses its own asset codes, e.g. `XBT` for Bitcoin, so the
+//! pair must be given in the Kraken-native form, e.g. `XBTUSD`.
+
+use std::{collections::HashMap, time::Duration};
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use time::{Date, OffsetDateTime};
+use tracing::{debug, instrument};
+
+use crate::{Candle, Error, Timeframe};
+
+use super::{client::get_json, Exchange, HttpClient};
+
+/// The base URL of the Kraken REST API.
+pub const BASE_URL: &str = "https://api.kraken.com";
+
+/// The interval of the candles in seconds.
+const INTERVAL: Duration = Timeframe::FiveMinutes.duration();
+
+/// The response of the API.
+///
+/// If the list of errors is not empty, the request failed and the result is
+/// missing.
+#[derive(Debug, Deserialize)]
+struct Response {
+    error: Vec<String>,
+    result: Option<HashMap<String, Entry>>,
+}
+
+/// An entry of the result map.
+///
+/// The result map contains the candles keyed by the pair name and the field
+/// `last` with the timestamp to use for polling new data.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Klines(Vec<Kline>),
+    Last(i64),
+}
+
+/// A candle as returned by the API.
+///
+/// The fields are `[time, open, high, low, close, vwap, volume, count]`, where
+/// `time` is the start of the candle in seconds since the Unix epoch and
+/// `volume` is the volume in the base currency.
+#[derive(Debug, Deserialize)]
+struct Kline(
+    i64,
+    Decimal,
+    Decimal,
+    Decimal,
+    Decimal,
+    Decimal,
+    Decimal,
+    u64,
+);
+
+impl Kline {
+    fn into_candle(self) -> Result<Candle, Error> {
+        let Self(time, open, high, low, close, vwap, volume, _) = self;
+        let timestamp = OffsetDateTime::from_unix_timestamp(time)
+            .map_err(|err| Error::ExchangeResponse(Exchange::Kraken, err.to_string()))?;
+
+        Candle::new(
+            timestamp,
+            Timeframe::FiveMinutes,
+            open,
+            high,
+            low,
+            close,
+            // The volume is given in the base currency.
+            volume * vwap,
+        )
+    }
+}
+
+/// Fetch the 5-minute candles of the given day for the pair `symbol`.
+///
+/// The pair is passed verbatim to the API. The candles are returned in
+/// ascending order of their timestamps. The volume of the candles is converted
+/// from the base currency into the quote currency using the volume-weighted
+/// average price of the candle.
+///
+/// Kraken only returns the most recent 720 candles of a pair, so only the
+/// last two days can be downloaded completely.
+///
+/// # Errors
+///
+/// Returns an error if the request fails, the response cannot be parsed or
+/// the API reports an error.
+#[instrument(skip(client))]
+pub async fn fetch_candles(
+    client: &impl HttpClient,
+    symbol: &str,
+    date: Date,
+) -> Result<Vec<Candle>, Error> {
+    let start = date.midnight().assume_utc();
+    let end = start + Timeframe::OneDay.duration();
+
+    let url = format!("{BASE_URL}/0/public/OHLC");
+    let response = get_json::<Response>(
+        client,
+        Exchange::Kraken,
+        &url,
+        &[
+            ("pair", symbol.to_string()),
+            ("interval", (INTERVAL.as_secs() / 60).to_string()),
+            // The `since` parameter is exclusive.
+            ("since", (start.unix_timestamp() - 1).to_string()),
+        ],
+    )
+    .await?;
+
+    if !response.error.is_empty() {
+        return Err(Error::ExchangeResponse(
+            Exchange::Kraken,
+            response.error.join(", "),
+        ));
+    }
+
+    let klines = response
+        .result
+        .into_iter()
+        .flat_map(HashMap::into_values)
+        .find_map(|entry| match entry {
+            Entry::Klines(klines) => Some(klines),
+            Entry::Last(_) => None,
+        })
+        .ok_or_else(|| {
+            Error::ExchangeResponse(Exchange::Kraken, format!("missing candles for {symbol}"))
+        })?;
+
+    debug!("Received {} candles", klines.len());
+    let mut candles = klines
+        .into_iter()
+        .map(Kline::into_candle)
+        .collect::<Result<Vec<_>, _>>()?;
+    candles.retain(|candle| candle.timestamp >= start && candle.timestamp < end);
+    candles.sort_unstable_by_key(|candle| candle.timestamp);
+
+    Ok(candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::{date, datetime};
+
+    use crate::exchange::client::mock::MockClient;
+
+    use super::*;
+
+    #[test]
+    fn parse_response() {
+        let response: Response = serde_json::from_str(
+            r#"{
+                "error": [],
+                "result": {
+                    "XXBTZUSD": [
+                        [1700000100, "10.0", "12.0", "9.5", "11.5", "11.0", "2.0", 5]
+                    ],
+                    "last": 1700000100
+                }
+            }"#,
+        )
+        .unwrap();
+        assert!(response.error.is_empty());
+
+        let mut result = response.result.unwrap();
+        assert!(matches!(
+            result.remove("last"),
+            Some(Entry::Last(1_700_000_100))
+        ));
+        let Some(Entry::Klines(klines)) = result.remove("XXBTZUSD") else {
+            panic!("missing candles");
+        };
+        let candle = klines.into_iter().next().unwrap().into_candle().unwrap();
+
+        assert_eq!(candle.timestamp.unix_timestamp(), 1_700_000_100);
+        assert_eq!(candle.open, dec!(10));
+        assert_eq!(candle.high, dec!(12));
+        assert_eq!(candle.low, dec!(9.5));
+        assert_eq!(candle.close, dec!(11.5));
+        assert_eq!(candle.volume, dec!(22));
+    }
+
+    #[test]
+    fn parse_error() {
+        let response: Response =
+            serde_json::from_str(r#"{"error": ["EQuery:Unknown asset pair"]}"#).unwrap();
+        assert_eq!(response.error, ["EQuery:Unknown asset pair"]);
+        assert!(response.result.is_none());
+    }
+
+    #[tokio::test]
+    async fn fetch_candles() {
+        let client = MockClient::new([r#"{
+            "error": [],
+            "result": {
+                "XXBTZUSD": [
+                    [1704066900, "10", "12", "9", "11", "10", "1", 5],
+                    [1704067200, "10", "12", "9", "11", "10", "1", 5],
+                    [1704067500, "10", "12", "9", "11", "10", "1", 5]
+                ],
+                "last": 1704067500
+            }
+        }"#]);
+        let candles = super::fetch_candles(&client, "XBTUSD", date!(2024 - 01 - 01))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            candles
+                .iter()
+                .map(|candle| candle.timestamp)
+                .collect::<Vec<_>>(),
+            [
+                datetime!(2024-01-01 00:00 UTC),
+                datetime!(2024-01-01 00:05 UTC)
+            ]
+        );
+        assert_eq!(
+            client.requests(),
+            ["https://api.kraken.com/0/public/OHLC?pair=XBTUSD&interval=5&since=1704067199"]
+        );
+    }
+}
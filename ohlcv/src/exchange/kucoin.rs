@@ -0,0 +1,191 @@
+//! KuCoin exchange implementation.
+//!
+//! The candles are downloaded from the `/api/v1/market/candles` endpoint of the
+//! KuCoin REST API. The symbol uses the dash form of the trading pair, e.g.
+//! `BTC-USDT`.
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use time::{Date, OffsetDateTime};
+use tracing::{debug, instrument};
+
+use crate::{Candle, Error, Timeframe};
+
+use super::{client::get_json, Exchange, HttpClient};
+
+/// The base URL of the KuCoin REST API.
+pub const BASE_URL: &str = "https://api.kucoin.com";
+/// The maximum number of candles returned by a single request.
+pub const MAX_CANDLES: usize = 1500;
+
+/// The code of a successful response.
+const CODE_SUCCESS: &str = "200000";
+
+/// The response of the API.
+///
+/// If the code is not [`CODE_SUCCESS`], the request failed and the message
+/// describes the error.
+#[derive(Debug, Deserialize)]
+struct Response {
+    code: String,
+    msg: Option<String>,
+    data: Option<Vec<Kline>>,
+}
+
+/// A candle as returned by the API.
+///
+/// The fields are `[time, open, close, high, low, volume, turnover]`, where
+/// `time` is the start of the candle in seconds since the Unix epoch and
+/// `turnover` is the volume in the quote currency. All fields are strings.
+#[derive(Debug, Deserialize)]
+struct Kline(String, Decimal, Decimal, Decimal, Decimal, Decimal, Decimal);
+
+impl Kline {
+    fn into_candle(self) -> Result<Candle, Error> {
+        let Self(time, open, close, high, low, _, turnover) = self;
+        let timestamp = time
+            .parse::<i64>()
+            .map_err(|err| err.to_string())
+            .and_then(|time| {
+                OffsetDateTime::from_unix_timestamp(time).map_err(|err| err.to_string())
+            })
+            .map_err(|err| Error::ExchangeResponse(Exchange::KuCoin, err))?;
+
+        Candle::new(
+            timestamp,
+            Timeframe::FiveMinutes,
+            open,
+            high,
+            low,
+            close,
+            turnover,
+        )
+    }
+}
+
+/// Fetch the 5-minute candles of the given day for the symbol `symbol`.
+///
+/// The candles are returned in ascending order of their timestamps. The
+/// volume of the candles is the turnover in the quote currency.
+///
+/// # Errors
+///
+/// Returns an error if the request fails, the response cannot be parsed or
+/// the API reports an error.
+#[instrument(skip(client))]
+pub async fn fetch_candles(
+    client: &impl HttpClient,
+    symbol: &str,
+    date: Date,
+) -> Result<Vec<Candle>, Error> {
+    let start = date.midnight().assume_utc();
+    let end = start + Timeframe::OneDay.duration();
+
+    let url = format!("{BASE_URL}/api/v1/market/candles");
+    let response = get_json::<Response>(
+        client,
+        Exchange::KuCoin,
+        &url,
+        &[
+            ("symbol", symbol.to_string()),
+            ("type", "5min".to_string()),
+            ("startAt", start.unix_timestamp().to_string()),
+            ("endAt", end.unix_timestamp().to_string()),
+        ],
+    )
+    .await?;
+
+    if response.code != CODE_SUCCESS {
+        return Err(Error::ExchangeResponse(
+            Exchange::KuCoin,
+            response.msg.unwrap_or(response.code),
+        ));
+    }
+
+    let klines = response.data.unwrap_or_default();
+    debug!("Received {} candles", klines.len());
+    let mut candles = klines
+        .into_iter()
+        .map(Kline::into_candle)
+        .collect::<Result<Vec<_>, _>>()?;
+    candles.retain(|candle| candle.timestamp >= start && candle.timestamp < end);
+    candles.sort_unstable_by_key(|candle| candle.timestamp);
+
+    Ok(candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::{date, datetime};
+
+    use crate::exchange::client::mock::MockClient;
+
+    use super::*;
+
+    #[test]
+    fn parse_response() {
+        let response: Response = serde_json::from_str(
+            r#"{
+                "code": "200000",
+                "data": [["1700000100", "10.0", "11.5", "12.0", "9.5", "2.0", "22.0"]]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(response.code, CODE_SUCCESS);
+
+        let candle = response.data.unwrap().into_iter().next().unwrap();
+        let candle = candle.into_candle().unwrap();
+
+        assert_eq!(candle.timestamp.unix_timestamp(), 1_700_000_100);
+        assert_eq!(candle.open, dec!(10));
+        assert_eq!(candle.high, dec!(12));
+        assert_eq!(candle.low, dec!(9.5));
+        assert_eq!(candle.close, dec!(11.5));
+        assert_eq!(candle.volume, dec!(22));
+    }
+
+    #[tokio::test]
+    async fn fetch_candles() {
+        let client = MockClient::new([r#"{
+            "code": "200000",
+            "data": [
+                ["1704153600", "10", "11", "12", "9", "1", "10"],
+                ["1704067500", "10", "11", "12", "9", "1", "10"],
+                ["1704067200", "10", "11", "12", "9", "1", "10"]
+            ]
+        }"#]);
+        let candles = super::fetch_candles(&client, "BTC-USDT", date!(2024 - 01 - 01))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            candles
+                .iter()
+                .map(|candle| candle.timestamp)
+                .collect::<Vec<_>>(),
+            [
+                datetime!(2024-01-01 00:00 UTC),
+                datetime!(2024-01-01 00:05 UTC)
+            ]
+        );
+        assert_eq!(
+            client.requests(),
+            ["https://api.kucoin.com/api/v1/market/candles?\
+            symbol=BTC-USDT&type=5min&startAt=1704067200&endAt=1704153600"]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_error() {
+        let client = MockClient::new([r#"{"code": "400100", "msg": "This pair is not provided"}"#]);
+
+        assert_eq!(
+            super::fetch_candles(&client, "BTC-XYZ", date!(2024 - 01 - 01)).await,
+            Err(Error::ExchangeResponse(
+                Exchange::KuCoin,
+                "This pair is not provided".to_string()
+            ))
+        );
+    }
+}
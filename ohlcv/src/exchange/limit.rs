@@ -0,0 +1,214 @@
+use std::{
+    collections::HashMap,
+    num::NonZero,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use tracing::{instrument, trace};
+
+use super::Exchange;
+
+/// The rate limit of an exchange.
+///
+/// The limit is given as the number of requests allowed in a period of
+/// seconds. The requests may be sent in bursts up to the number of requests,
+/// afterwards they are spread evenly over the period.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    requests: NonZero<u32>,
+    seconds: NonZero<u64>,
+}
+
+impl RateLimit {
+    /// Create a new rate limit of `requests` per `seconds`.
+    #[must_use]
+    pub const fn new(requests: NonZero<u32>, seconds: NonZero<u64>) -> Self {
+        Self { requests, seconds }
+    }
+
+    /// The number of requests allowed in the period.
+    #[must_use]
+    #[inline]
+    pub const fn requests(&self) -> u32 {
+        self.requests.get()
+    }
+
+    /// The period of the rate limit.
+    #[must_use]
+    #[inline]
+    pub const fn period(&self) -> Duration {
+        Duration::from_secs(self.seconds.get())
+    }
+
+    /// The time it takes to regain a single request.
+    fn interval(&self) -> Duration {
+        self.period() / self.requests()
+    }
+}
+
+impl Exchange {
+    /// The default rate limit of the exchange.
+    ///
+    /// The defaults are given in requests per period, as every request of a
+    /// download is charged, also the pages of a day. They are conservative
+    /// and stay well below the limits published by the exchanges for their
+    /// public market data endpoints.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub const fn rate_limit(&self) -> RateLimit {
+        // The unwraps are safe, as the values are not zero.
+        let (requests, seconds) = match self {
+            // 6000 weight per minute, a kline request has a weight of 2.
+            Self::Binance => (600, 60),
+            // 600 requests per 5 seconds for public endpoints.
+            Self::Bybit => (60, 5),
+            // 10 requests per second for public endpoints.
+            Self::Coinbase => (5, 1),
+            // Public endpoints allow about one request per second.
+            Self::Kraken => (1, 1),
+            // 2000 weight per 30 seconds, a kline request has a weight of 3.
+            Self::KuCoin => (100, 30),
+            // 20 requests per 2 seconds for historic candles.
+            Self::OKX => (10, 2),
+        };
+        RateLimit::new(
+            NonZero::new(requests).unwrap(),
+            NonZero::new(seconds).unwrap(),
+        )
+    }
+}
+
+/// The state of the token bucket of an exchange.
+#[derive(Debug)]
+struct Bucket {
+    tokens: u32,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Take a token from the bucket.
+    ///
+    /// Returns the time to wait until the next token is available if the
+    /// bucket is empty.
+    fn take(&mut self, limit: RateLimit, now: Instant) -> Option<Duration> {
+        let interval = limit.interval();
+        let elapsed = now.saturating_duration_since(self.updated);
+        let refill = elapsed.as_nanos() / interval.as_nanos().max(1);
+
+        if refill > 0 {
+            let refill = u32::try_from(refill).unwrap_or(u32::MAX);
+            self.tokens = self.tokens.saturating_add(refill).min(limit.requests());
+            self.updated += interval * refill.min(limit.requests());
+            if self.tokens == limit.requests() {
+                self.updated = now;
+            }
+        }
+
+        if self.tokens > 0 {
+            self.tokens -= 1;
+            None
+        } else {
+            Some(interval.saturating_sub(now.saturating_duration_since(self.updated)))
+        }
+    }
+}
+
+/// Rate limiter for the requests to the exchanges.
+///
+/// The limiter keeps a token bucket for every exchange. Before sending a
+/// request to an exchange, a token must be acquired using
+/// [`acquire()`](Self::acquire). If the bucket of the exchange is empty, the
+/// call waits until a token becomes available. The limiter can be shared
+/// between concurrent tasks.
+///
+/// If no limit is set for an exchange, the default limit of
+/// [`Exchange::rate_limit()`] is used.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    limits: HashMap<Exchange, RateLimit>,
+    buckets: Mutex<HashMap<Exchange, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter using the default limits of the exchanges.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the rate limit for the exchange.
+    #[must_use]
+    pub fn with_limit(mut self, exchange: Exchange, limit: RateLimit) -> Self {
+        self.limits.insert(exchange, limit);
+        self
+    }
+
+    /// Get the rate limit for the exchange.
+    #[must_use]
+    pub fn limit(&self, exchange: Exchange) -> RateLimit {
+        self.limits
+            .get(&exchange)
+            .copied()
+            .unwrap_or_else(|| exchange.rate_limit())
+    }
+
+    /// Acquire a token to send a request to the exchange.
+    ///
+    /// Waits until a token is available.
+    #[instrument(skip(self))]
+    pub async fn acquire(&self, exchange: Exchange) {
+        let limit = self.limit(exchange);
+
+        loop {
+            let wait = {
+                let now = Instant::now();
+                let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+                buckets
+                    .entry(exchange)
+                    .or_insert_with(|| Bucket {
+                        tokens: limit.requests(),
+                        updated: now,
+                    })
+                    .take(limit, now)
+            };
+
+            match wait {
+                Some(wait) => {
+                    trace!("Rate limit of {exchange} reached, waiting {wait:?}");
+                    tokio::time::sleep(wait).await;
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn acquire() {
+        let limit = RateLimit::new(NonZero::new(2).unwrap(), NonZero::new(1).unwrap());
+        let limiter = RateLimiter::new().with_limit(Exchange::Binance, limit);
+        let start = Instant::now();
+
+        // The burst is allowed immediately.
+        limiter.acquire(Exchange::Binance).await;
+        limiter.acquire(Exchange::Binance).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // Further requests are spread over the period.
+        limiter.acquire(Exchange::Binance).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+        limiter.acquire(Exchange::Binance).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(1000));
+
+        // Other exchanges are not affected.
+        limiter.acquire(Exchange::Kraken).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(1000));
+    }
+}
@@ -1,14 +1,296 @@
+//! Module for downloading candles from cryptocurrency exchanges.
+//!
+//! The module provides the [`Exchange`] type to identify an exchange in a
+//! configuration file. With the feature `fetch`, it also provides an
+//! implementation for each supported exchange. The implementations use the
+//! public REST API of the exchange to download the 5-minute candles of a
+//! single day. Without the feature, the module has no network dependencies, so
+//! configurations naming exchanges can be parsed by storage-only users.
+//!
+//! The method [`Exchange::fetch_candles()`] dispatches to the implementation
+//! of the exchange. Adding an exchange is a matter of adding a variant and its
+//! implementation. The implementations send their requests through an
+//! [`HttpClient`], which is implemented for [`reqwest::Client`].
+//!
+//! The [`Fetcher`] is the entry point to download candles. It respects the
+//! rate limits of the exchanges using a [`RateLimiter`], so many coins can be
+//! downloaded in parallel. Transient failures are retried according to the
+//! [`Retry`] policy.
+
+use std::{fmt, str::FromStr};
+
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "fetch")]
+use time::Date;
+#[cfg(feature = "fetch")]
+use tracing::instrument;
+
+use crate::Currency;
+#[cfg(feature = "fetch")]
+use crate::{Candle, Error};
+
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+pub mod binance;
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+pub mod bybit;
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+pub mod coinbase;
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+pub mod kraken;
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+pub mod kucoin;
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+pub mod okx;
+
+#[cfg(feature = "fetch")]
+mod client;
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+pub use client::HttpClient;
+
+#[cfg(feature = "fetch")]
+mod fetcher;
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+pub use fetcher::Fetcher;
+
+#[cfg(feature = "fetch")]
+mod limit;
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+pub use limit::{RateLimit, RateLimiter};
+
+#[cfg(feature = "fetch")]
+mod retry;
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+pub use retry::Retry;
 
 /// The type of exchange.
 ///
 /// This is a convenience enum to allow the use of different exchange types in a
 /// configuration file. The enum is serialized and deserialized using the
-/// `serde` crate.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// `serde` crate. The exchanges are ordered by their names.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Exchange {
     /// The Binance exchange.
     Binance,
+    /// The Bybit exchange.
+    Bybit,
+    /// The Coinbase exchange.
+    Coinbase,
+    /// The Kraken exchange.
+    Kraken,
     /// The KuCoin exchange.
     KuCoin,
+    /// The OKX exchange.
+    OKX,
+}
+
+/// Codes of Kraken differing from the common symbols of the coins.
+const KRAKEN_CODES: [(&str, &str); 2] = [("BTC", "XBT"), ("DOGE", "XDG")];
+
+impl Exchange {
+    /// All supported exchanges.
+    pub const ALL: [Self; 6] = [
+        Self::Binance,
+        Self::Bybit,
+        Self::Coinbase,
+        Self::Kraken,
+        Self::KuCoin,
+        Self::OKX,
+    ];
+    /// The names of the exchanges as accepted by [`Exchange::from_str`].
+    pub const NAMES: [&'static str; 6] =
+        ["Binance", "Bybit", "Coinbase", "Kraken", "KuCoin", "OKX"];
+
+    /// Format the symbol of the trading pair on the exchange.
+    ///
+    /// The base symbol and the code of the quote currency are written in upper
+    /// case, separated by a hyphen on Coinbase, KuCoin and OKX and without a
+    /// separator on Binance, Bybit and Kraken. Kraken uses its own codes for
+    /// some coins, e.g. `XBT` for Bitcoin.
+    ///
+    /// ```
+    /// # use ohlcv::{Currency, Exchange};
+    /// assert_eq!(Exchange::Binance.format_symbol("btc", Currency::USDC), "BTCUSDC");
+    /// assert_eq!(Exchange::Coinbase.format_symbol("ETH", Currency::EUR), "ETH-EUR");
+    /// assert_eq!(Exchange::Kraken.format_symbol("BTC", Currency::USD), "XBTUSD");
+    /// ```
+    #[must_use]
+    pub fn format_symbol(&self, base: &str, currency: Currency) -> String {
+        let base = base.to_uppercase();
+
+        match self {
+            Self::Binance | Self::Bybit => format!("{base}{currency}"),
+            Self::Coinbase | Self::KuCoin | Self::OKX => format!("{base}-{currency}"),
+            Self::Kraken => {
+                let base = KRAKEN_CODES
+                    .iter()
+                    .find(|(symbol, _)| *symbol == base)
+                    .map_or(base.as_str(), |(_, code)| code);
+
+                format!("{base}{currency}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Exchange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Binance => write!(f, "Binance"),
+            Self::Bybit => write!(f, "Bybit"),
+            Self::Coinbase => write!(f, "Coinbase"),
+            Self::Kraken => write!(f, "Kraken"),
+            Self::KuCoin => write!(f, "KuCoin"),
+            Self::OKX => write!(f, "OKX"),
+        }
+    }
+}
+
+/// Parses the name of the exchange ignoring the case, e.g. `kucoin`.
+impl FromStr for Exchange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|exchange| exchange.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| s.to_string())
+    }
+}
+
+#[cfg(feature = "fetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fetch")))]
+impl Exchange {
+    /// Fetch the 5-minute candles of the given day from the exchange.
+    ///
+    /// The symbol is the exchange-specific name of the trading pair. The
+    /// candles are returned in ascending order of their timestamps.
+    ///
+    /// The requests are sent with `client` instead of a client built for
+    /// the user agent, so one client with its connection pool serves all
+    /// exchanges. Wrapping the client sets the user agent, as done by
+    /// [`Fetcher`] per exchange.
+    ///
+    /// The request is sent immediately without respecting the rate limit of
+    /// the exchange. Use a [`Fetcher`] to respect the rate limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candles cannot be downloaded or the response of
+    /// the exchange is invalid.
+    #[instrument(skip(self, client), fields(exchange = ?self))]
+    pub async fn fetch_candles(
+        &self,
+        client: &impl HttpClient,
+        symbol: &str,
+        date: Date,
+    ) -> Result<Vec<Candle>, Error> {
+        match self {
+            Self::Binance => binance::fetch_candles(client, symbol, date).await,
+            Self::Bybit => bybit::fetch_candles(client, symbol, date).await,
+            Self::Coinbase => coinbase::fetch_candles(client, symbol, date).await,
+            Self::Kraken => kraken::fetch_candles(client, symbol, date).await,
+            Self::KuCoin => kucoin::fetch_candles(client, symbol, date).await,
+            Self::OKX => okx::fetch_candles(client, symbol, date).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "fetch")]
+    #[tokio::test]
+    async fn fetch_candles() {
+        let base_urls = [
+            binance::BASE_URL,
+            bybit::BASE_URL,
+            coinbase::BASE_URL,
+            kraken::BASE_URL,
+            kucoin::BASE_URL,
+            okx::BASE_URL,
+        ];
+
+        for (exchange, base_url) in Exchange::ALL.into_iter().zip(base_urls) {
+            let client = client::mock::MockClient::new(["[]"]);
+            // The empty list is not a valid response of every exchange.
+            let _ = exchange
+                .fetch_candles(&client, "BTC-USDT", time::macros::date!(2024 - 01 - 01))
+                .await;
+
+            let requests = client.requests();
+            assert_eq!(requests.len(), 1, "{exchange}");
+            assert!(requests[0].starts_with(base_url), "{exchange}");
+        }
+    }
+
+    #[test]
+    fn deserialize() {
+        let exchanges: Vec<Exchange> =
+            serde_json::from_str(r#"["Binance", "Coinbase", "KuCoin", "OKX"]"#).unwrap();
+
+        assert_eq!(
+            exchanges,
+            [
+                Exchange::Binance,
+                Exchange::Coinbase,
+                Exchange::KuCoin,
+                Exchange::OKX
+            ]
+        );
+        assert!(serde_json::from_str::<Exchange>(r#""Unknown""#).is_err());
+        // Unlike parsing, deserializing matches the exact names.
+        assert!(serde_json::from_str::<Exchange>(r#""coinbase""#).is_err());
+    }
+
+    #[test]
+    fn from_str() {
+        for (exchange, name) in Exchange::ALL.into_iter().zip(Exchange::NAMES) {
+            assert_eq!(exchange.to_string(), name);
+            assert_eq!(name.parse::<Exchange>(), Ok(exchange));
+            assert_eq!(name.to_lowercase().parse::<Exchange>(), Ok(exchange));
+            assert_eq!(name.to_uppercase().parse::<Exchange>(), Ok(exchange));
+        }
+        assert_eq!("Bitstamp".parse::<Exchange>(), Err("Bitstamp".to_string()));
+        assert_eq!("".parse::<Exchange>(), Err(String::new()));
+    }
+
+    #[test]
+    fn format_symbol() {
+        let symbols = [
+            (Exchange::Binance, "BTCUSDC"),
+            (Exchange::Bybit, "BTCUSDC"),
+            (Exchange::Coinbase, "BTC-USDC"),
+            (Exchange::Kraken, "XBTUSDC"),
+            (Exchange::KuCoin, "BTC-USDC"),
+            (Exchange::OKX, "BTC-USDC"),
+        ];
+
+        for (exchange, symbol) in symbols {
+            assert_eq!(exchange.format_symbol("btc", Currency::USDC), symbol);
+            assert_eq!(
+                Currency::from_pair(symbol),
+                Some((&symbol[..3], Currency::USDC))
+            );
+        }
+        assert_eq!(
+            Exchange::Kraken.format_symbol("DOGE", Currency::EUR),
+            "XDGEUR"
+        );
+        assert_eq!(
+            Exchange::Kraken.format_symbol("1INCH", Currency::USD),
+            "1INCHUSD"
+        );
+    }
 }
@@ -0,0 +1,285 @@
+//! OKX exchange implementation.
+//!
+//! The candles are downloaded from the `/api/v5/market/candles` endpoint of
+//! the OKX REST API. This endpoint only serves the most recent
+//! [`RECENT_CANDLES`] candles, so older days are downloaded from the
+//! `/api/v5/market/history-candles` endpoint. The symbol is the instrument ID
+//! of the trading pair, e.g. `BTC-USDT`.
+//!
+//! A single request returns at most [`MAX_CANDLES`] candles, so the candles of
+//! a day are downloaded page by page, starting with the most recent ones.
+
+use rust_decimal::Decimal;
+use serde::{de::IgnoredAny, Deserialize};
+use time::{Date, OffsetDateTime};
+use tracing::{debug, instrument};
+
+use crate::{Candle, Error, Timeframe};
+
+use super::{client::get_json, Exchange, HttpClient};
+
+/// The base URL of the OKX REST API.
+pub const BASE_URL: &str = "https://www.okx.com";
+/// The maximum number of candles returned by a single request.
+pub const MAX_CANDLES: usize = 100;
+/// The number of recent candles served by the `/api/v5/market/candles`
+/// endpoint.
+pub const RECENT_CANDLES: u32 = 1440;
+
+/// The code of a successful response.
+const CODE_SUCCESS: &str = "0";
+
+/// The response of the API.
+///
+/// If the code is not [`CODE_SUCCESS`], the request failed and the message
+/// describes the error.
+#[derive(Debug, Deserialize)]
+struct Response {
+    code: String,
+    msg: String,
+    data: Option<Vec<Kline>>,
+}
+
+/// A candle as returned by the API.
+///
+/// The fields are `[time, open, high, low, close, volume, currency volume,
+/// quote volume, confirm]`, where `time` is the start of the candle in
+/// milliseconds since the Unix epoch. All fields are strings.
+#[derive(Debug, Deserialize)]
+struct Kline(
+    String,
+    Decimal,
+    Decimal,
+    Decimal,
+    Decimal,
+    IgnoredAny,
+    IgnoredAny,
+    Decimal,
+    IgnoredAny,
+);
+
+impl Kline {
+    fn into_candle(self) -> Result<Candle, Error> {
+        let Self(time, open, high, low, close, _, _, volume, _) = self;
+        let timestamp = time
+            .parse::<i64>()
+            .map_err(|err| err.to_string())
+            .and_then(|time| {
+                OffsetDateTime::from_unix_timestamp_nanos(i128::from(time) * 1_000_000)
+                    .map_err(|err| err.to_string())
+            })
+            .map_err(|err| Error::ExchangeResponse(Exchange::OKX, err))?;
+
+        Candle::new(
+            timestamp,
+            Timeframe::FiveMinutes,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        )
+    }
+}
+
+/// Return the path of the endpoint serving the candles starting at `start`.
+///
+/// The recent endpoint is only used if the whole day lies within the last
+/// [`RECENT_CANDLES`] candles before `now`.
+fn endpoint(start: OffsetDateTime, now: OffsetDateTime) -> &'static str {
+    if start >= now - Timeframe::FiveMinutes.duration() * RECENT_CANDLES {
+        "/api/v5/market/candles"
+    } else {
+        "/api/v5/market/history-candles"
+    }
+}
+
+/// Fetch the 5-minute candles of the given day for the symbol `symbol`.
+///
+/// Days outside the window of the most recent candles are downloaded from the
+/// history endpoint. The candles are requested page by page from the end of the day backwards,
+/// using the oldest candle of a page as the `after` cursor of the next
+/// request. The `before` cursor limits the pages to the day. The candles are
+/// returned in ascending order of their timestamps. The volume of the candles
+/// is the volume in the quote currency.
+///
+/// # Errors
+///
+/// Returns an error if a request fails, a response cannot be parsed or the
+/// API reports an error.
+#[instrument(skip(client))]
+pub async fn fetch_candles(
+    client: &impl HttpClient,
+    symbol: &str,
+    date: Date,
+) -> Result<Vec<Candle>, Error> {
+    let start = date.midnight().assume_utc();
+    let end = start + Timeframe::OneDay.duration();
+
+    let url = format!("{BASE_URL}{}", endpoint(start, OffsetDateTime::now_utc()));
+    let mut candles = Vec::new();
+    // Both cursors are exclusive.
+    let mut after = end;
+    let before = start - Timeframe::FiveMinutes.duration();
+
+    loop {
+        let response = get_json::<Response>(
+            client,
+            Exchange::OKX,
+            &url,
+            &[
+                ("instId", symbol.to_string()),
+                ("bar", Timeframe::FiveMinutes.to_string()),
+                ("after", (after.unix_timestamp() * 1000).to_string()),
+                ("before", (before.unix_timestamp() * 1000).to_string()),
+                ("limit", MAX_CANDLES.to_string()),
+            ],
+        )
+        .await?;
+
+        if response.code != CODE_SUCCESS {
+            return Err(Error::ExchangeResponse(Exchange::OKX, response.msg));
+        }
+
+        let page = response
+            .data
+            .unwrap_or_default()
+            .into_iter()
+            .map(Kline::into_candle)
+            .collect::<Result<Vec<_>, _>>()?;
+        let received = page.len();
+        let oldest = page.iter().map(|candle| candle.timestamp).min();
+
+        debug!("Received {received} candles");
+        candles.extend(page);
+        // Stop if the page is not full or the cursor does not advance.
+        match oldest {
+            Some(oldest) if received == MAX_CANDLES && oldest > start && oldest < after => {
+                after = oldest;
+            }
+            _ => break,
+        }
+    }
+    candles.retain(|candle| candle.timestamp >= start && candle.timestamp < end);
+    candles.sort_unstable_by_key(|candle| candle.timestamp);
+    candles.dedup_by_key(|candle| candle.timestamp);
+
+    Ok(candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::macros::{date, datetime};
+
+    use crate::exchange::client::mock::MockClient;
+
+    use super::*;
+
+    #[test]
+    fn parse_response() {
+        let response: Response = serde_json::from_str(
+            r#"{
+                "code": "0",
+                "msg": "",
+                "data": [[
+                    "1700000100000", "10.0", "12.0", "9.5", "11.5", "2.0", "22.0", "22.0", "1"
+                ]]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(response.code, CODE_SUCCESS);
+
+        let candle = response.data.unwrap().into_iter().next().unwrap();
+        let candle = candle.into_candle().unwrap();
+
+        assert_eq!(candle.timestamp.unix_timestamp(), 1_700_000_100);
+        assert_eq!(candle.open, dec!(10));
+        assert_eq!(candle.high, dec!(12));
+        assert_eq!(candle.low, dec!(9.5));
+        assert_eq!(candle.close, dec!(11.5));
+        assert_eq!(candle.volume, dec!(22));
+    }
+
+    #[tokio::test]
+    async fn fetch_history_pages() {
+        let start = 1_704_067_200_000_i64;
+        let step = 5 * 60 * 1000;
+        // The candles of the day are returned from the newest to the oldest in
+        // pages of 100, 100 and 88 candles.
+        let page = |indices: std::ops::Range<i64>| {
+            let klines = indices
+                .rev()
+                .map(|index| {
+                    let time = start + index * step;
+                    format!(r#"["{time}", "10", "12", "9", "11", "1", "1", "10", "1"]"#)
+                })
+                .collect::<Vec<_>>();
+            format!(
+                r#"{{"code": "0", "msg": "", "data": [{}]}}"#,
+                klines.join(",")
+            )
+        };
+        let client = MockClient::new([page(188..288), page(88..188), page(0..88)]);
+        let candles = super::fetch_candles(&client, "BTC-USDT", date!(2024 - 01 - 01))
+            .await
+            .unwrap();
+
+        assert_eq!(candles.len(), 288);
+        assert_eq!(candles[0].timestamp, datetime!(2024-01-01 00:00 UTC));
+        assert_eq!(candles[287].timestamp, datetime!(2024-01-01 23:55 UTC));
+        assert_eq!(
+            client.requests(),
+            [start + 288 * step, start + 188 * step, start + 88 * step].map(|after| format!(
+                "https://www.okx.com/api/v5/market/history-candles?\
+                instId=BTC-USDT&bar=5m&after={after}&before={}&limit=100",
+                start - step
+            ))
+        );
+    }
+
+    #[test]
+    fn endpoints() {
+        let now = datetime!(2024-01-06 12:00 UTC);
+
+        assert_eq!(
+            endpoint(datetime!(2024-01-06 00:00 UTC), now),
+            "/api/v5/market/candles"
+        );
+        assert_eq!(
+            endpoint(datetime!(2024-01-01 12:00 UTC), now),
+            "/api/v5/market/candles"
+        );
+        assert_eq!(
+            endpoint(datetime!(2024-01-01 00:00 UTC), now),
+            "/api/v5/market/history-candles"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_recent() {
+        let client = MockClient::new([r#"{"code": "0", "msg": "", "data": []}"#]);
+        let today = OffsetDateTime::now_utc().date();
+        let candles = super::fetch_candles(&client, "BTC-USDT", today)
+            .await
+            .unwrap();
+
+        assert!(candles.is_empty());
+        assert!(client.requests()[0].starts_with("https://www.okx.com/api/v5/market/candles?"));
+    }
+
+    #[tokio::test]
+    async fn fetch_error() {
+        let client = MockClient::new([
+            r#"{"code": "51001", "msg": "Instrument ID does not exist", "data": []}"#,
+        ]);
+
+        assert_eq!(
+            super::fetch_candles(&client, "BTC-XYZ", date!(2024 - 01 - 01)).await,
+            Err(Error::ExchangeResponse(
+                Exchange::OKX,
+                "Instrument ID does not exist".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use std::{future::Future, num::NonZero, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::Error;
+
+use super::Exchange;
+
+/// The default number of attempts.
+pub const DEFAULT_ATTEMPTS: u32 = 3;
+/// The default delay before the first retry in milliseconds.
+pub const DEFAULT_DELAY_MS: u64 = 1000;
+
+/// Retry policy for downloads from the exchanges.
+///
+/// Transient failures are retried up to the number of attempts with an
+/// exponential backoff. The first retry waits for the base delay, every
+/// further retry doubles the delay. Transient failures are connection errors,
+/// timeouts, server errors (HTTP 5xx) and rate limit errors (HTTP 429). All
+/// other errors are permanent and returned immediately.
+///
+/// By default there are three attempts with a base delay of one second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Retry {
+    attempts: NonZero<u32>,
+    delay_ms: u64,
+}
+
+impl Retry {
+    /// Create a new retry policy.
+    ///
+    /// The delay is kept in whole milliseconds, so a fraction of a millisecond
+    /// is dropped.
+    #[must_use]
+    pub fn new(attempts: NonZero<u32>, delay: Duration) -> Self {
+        Self {
+            attempts,
+            delay_ms: u64::try_from(delay.as_millis()).unwrap_or(u64::MAX),
+        }
+    }
+
+    /// The maximum number of attempts.
+    #[must_use]
+    #[inline]
+    pub const fn attempts(&self) -> u32 {
+        self.attempts.get()
+    }
+
+    /// The delay before the first retry.
+    #[must_use]
+    #[inline]
+    pub const fn delay(&self) -> Duration {
+        Duration::from_millis(self.delay_ms)
+    }
+
+    /// Run the operation until it succeeds, fails permanently or the attempts
+    /// are exhausted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the operation if it is permanent. If all attempts
+    /// fail, [`Error::FetchExhausted`] carrying the last error is returned.
+    pub async fn run<F, Fut, T>(&self, exchange: Exchange, mut operation: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut delay = self.delay();
+
+        for attempt in 1..=self.attempts() {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(err) if !err.is_transient() => return Err(err),
+                Err(err) if attempt == self.attempts() => {
+                    return Err(Error::FetchExhausted(exchange, err.to_string()));
+                }
+                Err(err) => {
+                    warn!(
+                        "Attempt {attempt} of {} for {exchange} failed, retrying in {delay:?}: {err}",
+                        self.attempts()
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = delay.saturating_mul(2);
+                }
+            }
+        }
+        unreachable!("the number of attempts is not zero")
+    }
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            // This is safe because the default is not zero.
+            attempts: NonZero::new(DEFAULT_ATTEMPTS).unwrap(),
+            delay_ms: DEFAULT_DELAY_MS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn server_error() -> Error {
+        let response = http::Response::builder().status(503).body("").unwrap();
+        let err = reqwest::Response::from(response)
+            .error_for_status()
+            .unwrap_err();
+
+        Error::Http(Box::new(err))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn transient() {
+        let retry = Retry::default();
+        let calls = AtomicU32::new(0);
+        let start = tokio::time::Instant::now();
+
+        let result: Result<(), Error> = retry
+            .run(Exchange::Binance, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(server_error())
+            })
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::FetchExhausted(Exchange::Binance, _))
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(start.elapsed() >= Duration::from_secs(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn permanent() {
+        let retry = Retry::default();
+        let calls = AtomicU32::new(0);
+        let error = Error::ExchangeResponse(Exchange::Kraken, "EQuery:Unknown asset pair".into());
+
+        let result: Result<(), Error> = retry
+            .run(Exchange::Kraken, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(Error::ExchangeResponse(
+                    Exchange::Kraken,
+                    "EQuery:Unknown asset pair".into(),
+                ))
+            })
+            .await;
+
+        assert_eq!(result, Err(error));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn success() {
+        let retry = Retry::new(NonZero::new(5).unwrap(), Duration::from_secs(1));
+        let calls = AtomicU32::new(0);
+
+        let result = retry
+            .run(Exchange::KuCoin, || async {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(server_error())
+                } else {
+                    Ok(42)
+                }
+            })
+            .await;
+
+        assert_eq!(result, Ok(42));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn delay() {
+        let attempts = NonZero::new(2).unwrap();
+        let retry = toml::from_str::<Retry>("delay_ms = 500").unwrap();
+
+        assert_eq!(
+            Retry::new(attempts, Duration::from_millis(500)).delay(),
+            Duration::from_millis(500)
+        );
+        assert_eq!(retry.delay(), Duration::from_millis(500));
+        assert_eq!(retry.attempts(), DEFAULT_ATTEMPTS);
+    }
+}
@@ -0,0 +1,389 @@
+//! Technical indicators over series of candles.
+//!
+//! The indicators are calculated with [`Decimal`] arithmetic and work on any
+//! slice of candles, e.g. queried from the database or freshly fetched. The
+//! candles are expected to be sorted by timestamp. Every indicator returns one
+//! value per candle, aligned to the input. Candles of the warmup period, for
+//! which the indicator is not yet defined, have the value `None`. The
+//! [`volume_profile`] summarizes the whole series instead.
+
+use std::cmp::Ordering;
+
+use rust_decimal::Decimal;
+
+use crate::Candle;
+
+/// Simple moving average of the close prices.
+///
+/// The value of a candle is the arithmetic mean of the close prices of the
+/// candle and the `period - 1` candles before it. The first `period - 1`
+/// values are `None`. If the period is zero, all values are `None`.
+#[must_use]
+pub fn sma(candles: &[Candle], period: usize) -> Vec<Option<Decimal>> {
+    let mut values = vec![None; candles.len()];
+
+    if period == 0 || candles.len() < period {
+        return values;
+    }
+
+    let divisor = Decimal::from(period);
+    let mut sum = candles[..period]
+        .iter()
+        .map(|candle| candle.close)
+        .sum::<Decimal>();
+
+    values[period - 1] = Some(sum / divisor);
+    for index in period..candles.len() {
+        sum += candles[index].close - candles[index - period].close;
+        values[index] = Some(sum / divisor);
+    }
+    values
+}
+
+/// Exponential moving average of the close prices.
+///
+/// The average is seeded with the simple moving average of the first `period`
+/// candles. Every following value is weighted with the smoothing factor
+/// `2 / (period + 1)`:
+///
+/// `ema = (close - previous) * 2 / (period + 1) + previous`
+///
+/// The first `period - 1` values are `None`. If the period is zero, all
+/// values are `None`.
+#[must_use]
+pub fn ema(candles: &[Candle], period: usize) -> Vec<Option<Decimal>> {
+    let mut values = sma(candles, period);
+
+    if period == 0 || candles.len() < period {
+        return values;
+    }
+
+    let factor = Decimal::TWO / Decimal::from(period + 1);
+    let mut previous = values[period - 1].unwrap_or_default();
+
+    for index in period..candles.len() {
+        previous += (candles[index].close - previous) * factor;
+        values[index] = Some(previous);
+    }
+    values
+}
+
+/// Relative strength index of the close prices.
+///
+/// The gains and losses between the close prices of consecutive candles are
+/// averaged with Wilder's smoothing. The first averages are the arithmetic
+/// means of the first `period` changes, every following average is:
+///
+/// `average = (previous * (period - 1) + change) / period`
+///
+/// The index is `100 - 100 / (1 + gain / loss)`. It is 100 if there are no
+/// losses and 50 if the close price did not change at all. The first `period`
+/// values are `None`, because the first candle has no change. If the period is
+/// zero, all values are `None`.
+#[must_use]
+pub fn rsi(candles: &[Candle], period: usize) -> Vec<Option<Decimal>> {
+    let mut values = vec![None; candles.len()];
+
+    if period == 0 || candles.len() <= period {
+        return values;
+    }
+
+    let divisor = Decimal::from(period);
+    let mut gain = Decimal::ZERO;
+    let mut loss = Decimal::ZERO;
+
+    for (index, pair) in candles.windows(2).enumerate() {
+        let change = pair[1].close - pair[0].close;
+        let (up, down) = if change.is_sign_positive() {
+            (change, Decimal::ZERO)
+        } else {
+            (Decimal::ZERO, -change)
+        };
+
+        match (index + 1).cmp(&period) {
+            Ordering::Less => {
+                gain += up;
+                loss += down;
+                continue;
+            }
+            Ordering::Equal => {
+                gain = (gain + up) / divisor;
+                loss = (loss + down) / divisor;
+            }
+            Ordering::Greater => {
+                gain = smooth(gain, up, divisor);
+                loss = smooth(loss, down, divisor);
+            }
+        }
+        values[index + 1] = Some(if loss.is_zero() {
+            if gain.is_zero() {
+                Decimal::ONE_HUNDRED / Decimal::TWO
+            } else {
+                Decimal::ONE_HUNDRED
+            }
+        } else {
+            Decimal::ONE_HUNDRED - Decimal::ONE_HUNDRED / (Decimal::ONE + gain / loss)
+        });
+    }
+    values
+}
+
+/// Average true range of the candles.
+///
+/// The true range of a candle is its [range](Candle::range) extended to the
+/// close price of the previous candle, i.e. gaps between the candles are
+/// included. The first candle has no previous candle, so its true range is its
+/// range. The first average is the arithmetic mean of the first `period` true
+/// ranges, every following average is smoothed like in [`rsi`]:
+///
+/// `atr = (previous * (period - 1) + true range) / period`
+///
+/// The first `period - 1` values are `None`. If the period is zero, all
+/// values are `None`.
+#[must_use]
+pub fn atr(candles: &[Candle], period: usize) -> Vec<Option<Decimal>> {
+    let mut values = vec![None; candles.len()];
+
+    if period == 0 || candles.len() < period {
+        return values;
+    }
+
+    let divisor = Decimal::from(period);
+    let mut average = Decimal::ZERO;
+    let mut previous = Option::<&Candle>::None;
+
+    for (index, candle) in candles.iter().enumerate() {
+        let range = previous.map_or_else(
+            || candle.range(),
+            |previous| {
+                candle
+                    .range()
+                    .max((candle.high - previous.close).abs())
+                    .max((candle.low - previous.close).abs())
+            },
+        );
+
+        previous = Some(candle);
+        match (index + 1).cmp(&period) {
+            Ordering::Less => {
+                average += range;
+                continue;
+            }
+            Ordering::Equal => average = (average + range) / divisor,
+            Ordering::Greater => average = smooth(average, range, divisor),
+        }
+        values[index] = Some(average);
+    }
+    values
+}
+
+/// Volume profile of the candles.
+///
+/// The price range from the lowest low to the highest high of the candles is
+/// split into `buckets` buckets of equal size. The volume of every candle is
+/// distributed across the buckets overlapping its range from low to high,
+/// proportional to the overlap. A candle without a range, i.e. with equal high
+/// and low prices, adds its whole volume to the bucket containing its price.
+///
+/// Returns the lower bound of every bucket with its total volume, in ascending
+/// order of the prices. High volumes mark the price levels traded the most. If
+/// all candles have the same price, there is a single bucket. If there are no
+/// candles or no buckets, the profile is empty.
+#[must_use]
+pub fn volume_profile(candles: &[Candle], buckets: usize) -> Vec<(Decimal, Decimal)> {
+    let (Some(low), Some(high)) = (
+        candles.iter().map(|candle| candle.low).min(),
+        candles.iter().map(|candle| candle.high).max(),
+    ) else {
+        return Vec::new();
+    };
+
+    if buckets == 0 {
+        return Vec::new();
+    }
+    if low == high {
+        let volume = candles.iter().map(|candle| candle.volume).sum();
+        return vec![(low, volume)];
+    }
+
+    let width = (high - low) / Decimal::from(buckets);
+    let bounds = (0..buckets)
+        .map(|index| {
+            let upper = if index + 1 == buckets {
+                high
+            } else {
+                low + width * Decimal::from(index + 1)
+            };
+            (low + width * Decimal::from(index), upper)
+        })
+        .collect::<Vec<_>>();
+    let mut profile = bounds
+        .iter()
+        .map(|(lower, _)| (*lower, Decimal::ZERO))
+        .collect::<Vec<_>>();
+
+    for candle in candles {
+        let range = candle.range();
+
+        if range.is_zero() {
+            let index = bounds
+                .iter()
+                .position(|(_, upper)| candle.low < *upper)
+                .unwrap_or(buckets - 1);
+            profile[index].1 += candle.volume;
+            continue;
+        }
+        for ((lower, upper), (_, volume)) in bounds.iter().zip(&mut profile) {
+            let overlap = candle.high.min(*upper) - candle.low.max(*lower);
+
+            if overlap > Decimal::ZERO {
+                *volume += candle.volume * overlap / range;
+            }
+        }
+    }
+    profile
+}
+
+/// Wilder's smoothing of an average with the next value.
+fn smooth(average: Decimal, value: Decimal, period: Decimal) -> Decimal {
+    (average * (period - Decimal::ONE) + value) / period
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use time::{macros::datetime, Duration};
+
+    use crate::Timeframe;
+
+    use super::*;
+
+    /// Candles with the given close prices.
+    fn series(closes: &[Decimal]) -> Vec<Candle> {
+        let prices = closes
+            .iter()
+            .map(|close| (*close, *close, *close))
+            .collect::<Vec<_>>();
+
+        bars(&prices)
+    }
+
+    /// Candles with the given high, low and close prices.
+    fn bars(prices: &[(Decimal, Decimal, Decimal)]) -> Vec<Candle> {
+        prices
+            .iter()
+            .zip(0..)
+            .map(|((high, low, close), index)| {
+                Candle::new(
+                    datetime!(2024-01-01 00:00 UTC) + Duration::minutes(5 * index),
+                    Timeframe::FiveMinutes,
+                    *close,
+                    *high,
+                    *low,
+                    *close,
+                    dec!(1),
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn simple_moving_average() {
+        let candles = series(&[dec!(2), dec!(4), dec!(6), dec!(8), dec!(13)]);
+
+        assert_eq!(
+            sma(&candles, 3),
+            [None, None, Some(dec!(4)), Some(dec!(6)), Some(dec!(9))]
+        );
+        assert_eq!(sma(&candles, 1)[4], Some(dec!(13)));
+        assert_eq!(sma(&candles, 0), [None; 5]);
+        assert_eq!(sma(&candles, 6), [None; 5]);
+    }
+
+    #[test]
+    fn exponential_moving_average() {
+        let candles = series(&[dec!(2), dec!(4), dec!(6), dec!(8), dec!(12)]);
+
+        assert_eq!(
+            ema(&candles, 3),
+            [None, None, Some(dec!(4)), Some(dec!(6)), Some(dec!(9))]
+        );
+        assert_eq!(ema(&candles, 0), [None; 5]);
+        assert_eq!(ema(&candles, 6), [None; 5]);
+    }
+
+    #[test]
+    fn relative_strength_index() {
+        let candles = series(&[dec!(10), dec!(11), dec!(10), dec!(11.5), dec!(7.75)]);
+
+        assert_eq!(
+            rsi(&candles, 2),
+            [None, None, Some(dec!(50)), Some(dec!(80)), Some(dec!(20))]
+        );
+        assert_eq!(
+            rsi(&series(&[dec!(1), dec!(2), dec!(3)]), 2),
+            [None, None, Some(dec!(100))]
+        );
+        assert_eq!(
+            rsi(&series(&[dec!(1), dec!(1), dec!(1)]), 2),
+            [None, None, Some(dec!(50))]
+        );
+        assert_eq!(rsi(&candles, 0), [None; 5]);
+        assert_eq!(rsi(&candles, 5), [None; 5]);
+    }
+
+    #[test]
+    fn average_true_range() {
+        let candles = bars(&[
+            (dec!(12), dec!(8), dec!(10)),
+            (dec!(11), dec!(9), dec!(10)),
+            (dec!(15), dec!(12), dec!(14)),
+            (dec!(13), dec!(7), dec!(8)),
+        ]);
+
+        assert_eq!(
+            atr(&candles, 2),
+            [None, Some(dec!(3)), Some(dec!(4)), Some(dec!(5.5))]
+        );
+        assert_eq!(atr(&candles, 1)[2], Some(dec!(5)));
+        assert_eq!(atr(&candles, 0), [None; 4]);
+        assert_eq!(atr(&candles, 5), [None; 4]);
+    }
+
+    #[test]
+    fn volume_profiles() {
+        let candle = |low, high, volume| Candle {
+            open: low,
+            high,
+            low,
+            close: high,
+            volume,
+            ..Candle::default()
+        };
+        let candles = [
+            candle(dec!(10), dec!(20), dec!(10)),
+            candle(dec!(12), dec!(14), dec!(4)),
+            candle(dec!(20), dec!(20), dec!(3)),
+            candle(dec!(14), dec!(16), dec!(2)),
+        ];
+
+        assert_eq!(
+            volume_profile(&candles, 2),
+            [(dec!(10), dec!(10)), (dec!(15), dec!(9))]
+        );
+        assert_eq!(
+            volume_profile(&candles, 4)
+                .iter()
+                .map(|(_, volume)| volume)
+                .sum::<Decimal>(),
+            dec!(19)
+        );
+        assert_eq!(
+            volume_profile(&[candle(dec!(5), dec!(5), dec!(1)); 2], 3),
+            [(dec!(5), dec!(2))]
+        );
+        assert!(volume_profile(&candles, 0).is_empty());
+        assert!(volume_profile(&[], 2).is_empty());
+    }
+}
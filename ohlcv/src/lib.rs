@@ -1,4 +1,9 @@
-#![allow(clippy::doc_markdown, clippy::multiple_crate_versions, dead_code)]
+#![allow(
+    clippy::doc_markdown,
+    clippy::duration_suboptimal_units,
+    clippy::multiple_crate_versions,
+    dead_code
+)]
 //! # OHLCV
 //!
 //! **Note:** *The library is in the early stages of development and is not yet
@@ -12,8 +17,8 @@
 //!
 //! - [x] Data model and base types
 //! - [x] Initialize and drop schema
-//! - [ ] Download historical OHLCV data
-//! - [ ] Export/import OHLCV data as CSV or JSON
+//! - [x] Download historical OHLCV data
+//! - [x] Export/import OHLCV data as CSV or JSON
 //!
 //! ## Overview
 //!
@@ -72,11 +77,25 @@
 //! normal user only has access to the data. Exception to this is SQLite, where
 //! no user management is needed.
 //!
+//! The tables are named by a prefix followed by the symbol and currency of the
+//! coin, e.g. `candles_btc_usd`. The prefix defaults to `candles` and can be
+//! set with `with_table_prefix` of the coins and of the database
+//! configuration, so several datasets can share a database. The database only
+//! lists and drops the tables of its prefix. The version of the schema is
+//! recorded in the table `<prefix>_schema_version`.
+//! `init_schema` refuses to touch a database with a schema newer than the one
+//! of the library, so an older release cannot corrupt it.
+//!
 //! See the implementation of the database configuration for more details.
 //!
 //! The [`Database`] trait provides methods to interact with the database. The
 //! trait is implemented for the [`DbType`] type.
 //!
+//! Every database is enabled by the feature of the same name: `sqlite`,
+//! `postgres` or `mysql`. Without any of them, the `database` module is not
+//! available and the library only provides the data model, e.g. candles,
+//! timeframes and coins for in-memory analytics.
+//!
 //! ## Download historical OHLCV data
 //!
 //! The library can download historical OHLCV data from various cryptocurrency
@@ -101,7 +120,16 @@
 //! Supported exchanges include:
 //!
 //! - Binance
+//! - Bybit
+//! - Coinbase
+//! - Kraken
 //! - KuCoin
+//! - OKX
+//!
+//! The exchanges are identified by the [`Exchange`] type of the feature
+//! `exchange`, e.g. in configuration files. Downloading requires the feature
+//! `fetch`, which adds the HTTP client and the implementations of the
+//! exchanges.
 //!
 //! The databases supported include:
 //!
@@ -139,13 +167,11 @@
 //! There will be three attempts to download the data with increasing time
 //! between attempts for a trading pair for an exchange.
 //!
-//! If downloading a trading pair from more than one exchange, the prive values
-//! of the candles will be averaged by a volume-weighted average price (VWAP).
-//! The volume of the candles will be summed. In the candle the number of
-//! sources will be stored.
-
-#[cfg(not(any(feature = "mysql", feature = "postgres", feature = "sqlite")))]
-compile_error!("At least one of the features 'mysql', 'postgres', or 'sqlite' must be enabled.");
+//! If downloading a trading pair from more than one exchange, the open and
+//! close prices of the candles will be averaged by a volume-weighted average
+//! price (VWAP). The high and low prices are the extremes of all exchanges. The
+//! volume of the candles will be summed. In the candle the number of sources
+//! will be stored.
 
 mod basetypes;
 pub use basetypes::{Currency, Timeframe};
@@ -156,14 +182,26 @@ pub use candle::{Candle, Color};
 mod coin;
 pub use coin::Coin;
 
+#[cfg(feature = "database")]
+#[cfg_attr(docsrs, doc(cfg(feature = "database")))]
 pub mod database;
+#[cfg(feature = "database")]
+#[cfg_attr(docsrs, doc(cfg(feature = "database")))]
 pub use database::{Database, DbType};
 
 mod error;
 pub use error::Error;
 
+pub mod series;
+pub use series::{merge_by_timestamp, resample, validate_series};
+
 #[cfg(feature = "exchange")]
-mod exchange;
+#[cfg_attr(docsrs, doc(cfg(feature = "exchange")))]
+pub mod exchange;
 #[cfg(feature = "exchange")]
 #[cfg_attr(docsrs, doc(cfg(feature = "exchange")))]
 pub use exchange::Exchange;
+
+#[cfg(feature = "indicators")]
+#[cfg_attr(docsrs, doc(cfg(feature = "indicators")))]
+pub mod indicators;
@@ -0,0 +1,539 @@
+//! Checks and transformations of series of candles.
+//!
+//! A series is a list of candles of the same timeframe sorted by their
+//! timestamp. The series is expected to cover whole days in UTC, e.g. 288
+//! candles per day for the timeframe of five minutes.
+
+use rust_decimal::Decimal;
+use time::OffsetDateTime;
+
+use crate::{candle::percent_change, Candle, Error, Timeframe};
+
+/// The maximum number of consecutive candles that may be missing.
+pub const MAX_GAP: usize = 5;
+/// The minimum number of candles between two gaps.
+pub const MIN_GAP_DISTANCE: usize = 5;
+/// The maximum percentage of candles that may be missing.
+pub const MAX_MISSING_PERCENT: usize = 5;
+
+/// Validate a series of candles downloaded from an exchange.
+///
+/// The series spans the days from the midnight before the first candle to the
+/// midnight after the last candle. The series is rejected if:
+///
+/// - there is a gap of more than [`MAX_GAP`] candles,
+/// - the next gap is less than [`MIN_GAP_DISTANCE`] candles away,
+/// - more than [`MAX_MISSING_PERCENT`] percent of the candles are missing.
+///
+/// Gaps at the start or the end of the days are taken into account.
+///
+/// # Errors
+///
+/// Returns an error if the series is empty, not sorted by timestamp or
+/// violates one of the rules above.
+pub fn validate_series(candles: &[Candle], timeframe: Timeframe) -> Result<(), Error> {
+    let Some((start, end)) = span(candles) else {
+        return Err(Error::SeriesEmpty);
+    };
+    if let Some(pair) = candles
+        .windows(2)
+        .find(|pair| pair[1].timestamp <= pair[0].timestamp)
+    {
+        return Err(Error::SeriesOrder(pair[1].timestamp));
+    }
+
+    let expected = slots(start, end, timeframe);
+    let mut last_gap = Option::<OffsetDateTime>::None;
+    let mut missing = 0;
+
+    for (gap_start, gap) in gaps(candles, timeframe) {
+        if gap > MAX_GAP {
+            return Err(Error::SeriesGap(gap_start, gap));
+        }
+        if let Some(last_gap) = last_gap {
+            let distance = slots(last_gap, gap_start, timeframe);
+
+            if distance < MIN_GAP_DISTANCE {
+                return Err(Error::SeriesGapDistance(gap_start, distance));
+            }
+        }
+        last_gap = Some(gap_start + timeframe.duration() * u32::try_from(gap).unwrap_or(u32::MAX));
+        missing += gap;
+    }
+
+    if missing * 100 > expected * MAX_MISSING_PERCENT {
+        return Err(Error::SeriesMissing(missing, expected));
+    }
+    Ok(())
+}
+
+/// Find the gaps in a series of candles.
+///
+/// Returns the timestamp of the first missing candle and the number of
+/// consecutive missing candles for every gap. Like [`validate_series`] the
+/// series spans whole days, so missing candles at the start or the end of the
+/// days are reported as gaps. The candles must be sorted by timestamp.
+#[must_use]
+pub fn gaps(candles: &[Candle], timeframe: Timeframe) -> Vec<(OffsetDateTime, usize)> {
+    let Some((start, end)) = span(candles) else {
+        return Vec::new();
+    };
+    let mut expected = start;
+    let mut gaps = Vec::new();
+
+    for timestamp in candles.iter().map(|candle| candle.timestamp).chain([end]) {
+        let gap = slots(expected, timestamp, timeframe);
+
+        if gap > 0 {
+            gaps.push((expected, gap));
+        }
+        expected = expected.max(timestamp + timeframe.duration());
+    }
+    gaps
+}
+
+/// Calculate the close-to-close returns of a series of candles in percent.
+///
+/// A return is calculated for every candle directly following the previous
+/// candle, i.e. starting one timeframe after it. Candles after a gap are
+/// skipped, as their return would span the missing candles, and so is the
+/// first candle. If the previous close price is zero, the return is zero.
+/// The candles must be sorted by timestamp.
+#[must_use]
+pub fn returns(candles: &[Candle]) -> Vec<Decimal> {
+    candles
+        .windows(2)
+        .filter(|pair| pair[1].timestamp == pair[0].span().1)
+        .map(|pair| percent_change(pair[0].close, pair[1].close))
+        .collect()
+}
+
+/// Resample a series of candles into a larger timeframe.
+///
+/// The candles of the timeframe `from` are lazily aggregated into candles of
+/// the timeframe `to`, e.g. 5-minute candles into 1-hour candles. A resampled
+/// candle starts at the timestamp rounded down to `to`. Its open price is the
+/// open price of the first candle and its close price the close price of the
+/// last candle. The high and low prices are the extremes of the candles, the
+/// volume is their sum and the sources are the minimum of their sources.
+///
+/// A resampled candle is emitted as soon as a candle of the next one is read,
+/// the last one at the end of the series. Candles missing in the series are
+/// skipped, so the first and last resampled candle may be incomplete.
+///
+/// If a candle does not have the timeframe `from` or is not after the previous
+/// candle, an error is emitted and the candle is skipped.
+///
+/// # Errors
+///
+/// Returns an error if `from` does not [subdivide](Timeframe::subdivides)
+/// `to`.
+pub fn resample<I>(
+    candles: I,
+    from: Timeframe,
+    to: Timeframe,
+) -> Result<Resample<I::IntoIter>, Error>
+where
+    I: IntoIterator<Item = Candle>,
+{
+    if !from.subdivides(to) {
+        return Err(Error::ResampleTimeframe(from, to));
+    }
+    Ok(Resample {
+        candles: candles.into_iter(),
+        from,
+        to,
+        current: None,
+        last: None,
+    })
+}
+
+/// Iterator returned by [`resample`].
+#[derive(Clone, Debug)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Resample<I> {
+    candles: I,
+    from: Timeframe,
+    to: Timeframe,
+    current: Option<Candle>,
+    last: Option<OffsetDateTime>,
+}
+
+impl<I> Iterator for Resample<I>
+where
+    I: Iterator<Item = Candle>,
+{
+    type Item = Result<Candle, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some(candle) = self.candles.next() else {
+                return self.current.take().map(Ok);
+            };
+
+            if candle.timeframe != self.from {
+                return Some(Err(Error::ResampleCandle(
+                    candle.timestamp,
+                    candle.timeframe,
+                    self.from,
+                )));
+            }
+            if self.last.is_some_and(|last| candle.timestamp <= last) {
+                return Some(Err(Error::SeriesOrder(candle.timestamp)));
+            }
+            self.last = Some(candle.timestamp);
+
+            let timestamp = self.to.round_down(candle.timestamp);
+
+            match &mut self.current {
+                Some(current) if current.timestamp == timestamp => {
+                    current.sources = current.sources.min(candle.sources);
+                    current.high = current.high.max(candle.high);
+                    current.low = current.low.min(candle.low);
+                    current.close = candle.close;
+                    current.volume += candle.volume;
+                }
+                current => {
+                    let next = Candle {
+                        timestamp,
+                        timeframe: self.to,
+                        ..candle
+                    };
+
+                    if let Some(previous) = current.replace(next) {
+                        return Some(Ok(previous));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Merge a stream of candles sorted by timestamp into one candle per
+/// timestamp.
+///
+/// The consecutive candles with the same timestamp, e.g. of several exchanges,
+/// are merged by [`Candle::merge`]. A candle without another one of its
+/// timestamp is passed through unchanged. Only the candles of the current
+/// timestamp are kept in memory, so a whole day of many exchanges is merged
+/// without grouping it first.
+///
+/// If a candle is before the previous candle, an error is emitted and the
+/// candle is skipped. An error of [`Candle::merge`] is emitted in place of the
+/// merged candle.
+pub fn merge_by_timestamp<I>(candles: I) -> MergeByTimestamp<I::IntoIter>
+where
+    I: IntoIterator<Item = Candle>,
+{
+    MergeByTimestamp {
+        candles: candles.into_iter(),
+        group: Vec::new(),
+    }
+}
+
+/// Iterator returned by [`merge_by_timestamp`].
+#[derive(Clone, Debug)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct MergeByTimestamp<I> {
+    candles: I,
+    group: Vec<Candle>,
+}
+
+impl<I> MergeByTimestamp<I> {
+    /// Merge the candles of the current group and start a new one.
+    fn flush(&mut self, next: Option<Candle>) -> Option<Result<Candle, Error>> {
+        let group = std::mem::replace(&mut self.group, next.into_iter().collect());
+
+        match group.as_slice() {
+            [] => None,
+            [candle] => Some(Ok(*candle)),
+            group => Some(Candle::merge(group)),
+        }
+    }
+}
+
+impl<I> Iterator for MergeByTimestamp<I>
+where
+    I: Iterator<Item = Candle>,
+{
+    type Item = Result<Candle, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some(candle) = self.candles.next() else {
+                return self.flush(None);
+            };
+
+            match self.group.first() {
+                Some(first) if candle.timestamp < first.timestamp => {
+                    return Some(Err(Error::SeriesOrder(candle.timestamp)));
+                }
+                Some(first) if candle.timestamp > first.timestamp => {
+                    return self.flush(Some(candle));
+                }
+                _ => self.group.push(candle),
+            }
+        }
+    }
+}
+
+/// Start and end of the days covered by a series of candles.
+fn span(candles: &[Candle]) -> Option<(OffsetDateTime, OffsetDateTime)> {
+    let first = candles.first()?;
+    let last = candles.last()?;
+
+    Some((
+        Timeframe::OneDay.round_down(first.timestamp),
+        Timeframe::OneDay.round_down(last.timestamp) + Timeframe::OneDay.duration(),
+    ))
+}
+
+/// Number of candles of the timeframe between `start` and `end`.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn slots(start: OffsetDateTime, end: OffsetDateTime, timeframe: Timeframe) -> usize {
+    let seconds = (end - start).whole_seconds().max(0) as u64;
+
+    (seconds / timeframe.duration().as_secs()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use time::{macros::datetime, Duration};
+
+    use super::*;
+
+    const START: OffsetDateTime = datetime!(2024-01-01 00:00 UTC);
+
+    /// Candles of one day without the candles at the given indices.
+    fn series(missing: &[i64]) -> Vec<Candle> {
+        (0..288)
+            .filter(|index| !missing.contains(index))
+            .map(|index| {
+                Candle::new(
+                    START + Duration::minutes(5 * index),
+                    Timeframe::FiveMinutes,
+                    dec!(1),
+                    dec!(1),
+                    dec!(1),
+                    dec!(1),
+                    dec!(1),
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn complete() {
+        assert_eq!(
+            validate_series(&series(&[]), Timeframe::FiveMinutes),
+            Ok(())
+        );
+        assert_eq!(
+            validate_series(
+                &series(&[0, 1, 10, 11, 12, 13, 14, 287]),
+                Timeframe::FiveMinutes
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn gap() {
+        assert_eq!(
+            validate_series(&series(&[10, 11, 12, 13, 14, 15]), Timeframe::FiveMinutes),
+            Err(Error::SeriesGap(START + Duration::minutes(50), 6))
+        );
+        assert_eq!(
+            validate_series(
+                &series(&[282, 283, 284, 285, 286, 287]),
+                Timeframe::FiveMinutes
+            ),
+            Err(Error::SeriesGap(START + Duration::minutes(5 * 282), 6))
+        );
+    }
+
+    #[test]
+    fn gap_distance() {
+        assert_eq!(
+            validate_series(&series(&[10, 11, 15]), Timeframe::FiveMinutes),
+            Err(Error::SeriesGapDistance(START + Duration::minutes(75), 3))
+        );
+    }
+
+    #[test]
+    fn missing() {
+        let missing = (0..15).map(|gap| gap * 10).collect::<Vec<_>>();
+
+        assert_eq!(
+            validate_series(&series(&missing), Timeframe::FiveMinutes),
+            Err(Error::SeriesMissing(15, 288))
+        );
+        assert_eq!(
+            validate_series(&series(&missing[..14]), Timeframe::FiveMinutes),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn find_gaps() {
+        assert_eq!(gaps(&series(&[]), Timeframe::FiveMinutes), vec![]);
+        assert_eq!(
+            gaps(&series(&[0, 10, 11, 287]), Timeframe::FiveMinutes),
+            vec![
+                (START, 1),
+                (START + Duration::minutes(50), 2),
+                (START + Duration::minutes(5 * 287), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn resample_hours() {
+        let candles = (0..30).map(|index| {
+            let price = Decimal::from(index);
+
+            Candle::new(
+                START + Duration::minutes(5 * index),
+                Timeframe::FiveMinutes,
+                price,
+                price + dec!(1),
+                price - dec!(1),
+                price + dec!(0.5),
+                dec!(2),
+            )
+            .unwrap()
+        });
+        let resampled = resample(candles, Timeframe::FiveMinutes, Timeframe::OneHour)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(resampled.len(), 3);
+        for (candle, (timestamp, open, close, volume)) in resampled.iter().zip([
+            (START, dec!(0), dec!(11.5), dec!(24)),
+            (START + Duration::hours(1), dec!(12), dec!(23.5), dec!(24)),
+            (START + Duration::hours(2), dec!(24), dec!(29.5), dec!(12)),
+        ]) {
+            assert_eq!(candle.timestamp, timestamp);
+            assert_eq!(candle.timeframe, Timeframe::OneHour);
+            assert_eq!(candle.sources(), 1);
+            assert_eq!(candle.open, open);
+            assert_eq!(candle.high, close + dec!(0.5));
+            assert_eq!(candle.low, open - dec!(1));
+            assert_eq!(candle.close, close);
+            assert_eq!(candle.volume, volume);
+        }
+    }
+
+    #[test]
+    fn resample_invalid() {
+        let mut candles = series(&[]);
+
+        assert_eq!(
+            resample(candles.clone(), Timeframe::OneHour, Timeframe::Quarters).err(),
+            Some(Error::ResampleTimeframe(
+                Timeframe::OneHour,
+                Timeframe::Quarters
+            ))
+        );
+        assert_eq!(
+            resample(candles.clone(), Timeframe::Quarters, Timeframe::OneHour)
+                .unwrap()
+                .next(),
+            Some(Err(Error::ResampleCandle(
+                START,
+                Timeframe::FiveMinutes,
+                Timeframe::Quarters
+            )))
+        );
+
+        candles.swap(1, 2);
+        let resampled = resample(candles, Timeframe::FiveMinutes, Timeframe::Quarters)
+            .unwrap()
+            .map(|candle| candle.map(|candle| candle.timestamp))
+            .take(3)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            resampled,
+            [
+                Err(Error::SeriesOrder(START + Duration::minutes(5))),
+                Ok(START),
+                Ok(START + Duration::minutes(15)),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let mut candles = series(&[]);
+
+        assert_eq!(
+            validate_series(&[], Timeframe::FiveMinutes),
+            Err(Error::SeriesEmpty)
+        );
+        candles.swap(1, 2);
+        assert_eq!(
+            validate_series(&candles, Timeframe::FiveMinutes),
+            Err(Error::SeriesOrder(START + Duration::minutes(5)))
+        );
+    }
+
+    #[test]
+    fn returns() {
+        let mut candles = series(&[3]);
+        let closes = [dec!(10), dec!(0), dec!(11), dec!(5), dec!(4)];
+
+        for (candle, close) in candles.iter_mut().zip(closes) {
+            candle.close = close;
+        }
+        candles.truncate(closes.len());
+
+        // The return after the zero close is zero and the return across the
+        // missing fourth candle is skipped.
+        assert_eq!(super::returns(&candles), [dec!(-100), dec!(0), dec!(-20)]);
+        assert!(super::returns(&candles[..1]).is_empty());
+        assert!(super::returns(&[]).is_empty());
+    }
+
+    #[test]
+    fn merge_by_timestamp() {
+        let candle = |minutes: i64, price: Decimal| {
+            Candle::new(
+                START + Duration::minutes(minutes),
+                Timeframe::FiveMinutes,
+                price,
+                price,
+                price,
+                price,
+                dec!(1),
+            )
+            .unwrap()
+        };
+        let merged = super::merge_by_timestamp([
+            candle(0, dec!(10)),
+            candle(0, dec!(20)),
+            candle(5, dec!(30)),
+            candle(0, dec!(40)),
+            candle(10, dec!(50)),
+            candle(10, dec!(70)),
+            candle(10, dec!(90)),
+        ])
+        .map(|candle| candle.map(|candle| (candle.timestamp, candle.sources(), candle.close)))
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            merged,
+            [
+                Ok((START, 2, dec!(15))),
+                Err(Error::SeriesOrder(START)),
+                Ok((START + Duration::minutes(5), 1, dec!(30))),
+                Ok((START + Duration::minutes(10), 3, dec!(70))),
+            ]
+        );
+        assert_eq!(super::merge_by_timestamp([]).count(), 0);
+    }
+}